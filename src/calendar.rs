@@ -0,0 +1,152 @@
+use chrono::{DateTime, Duration, FixedOffset, TimeZone, Utc};
+use std::error::Error;
+
+/// Describes when the puzzles of an Advent of Code event unlock.
+#[derive(Clone, Debug)]
+pub struct Calendar {
+    year: i32,
+    /// Unlock time of every day, starting with the first.
+    unlocks: Vec<DateTime<FixedOffset>>,
+}
+
+impl Calendar {
+    /// Puzzles unlock at midnight US Eastern time (UTC-5).
+    pub const UNLOCK_UTC_OFFSET: i32 = -5 * 60 * 60;
+
+    pub fn new(year: i32) -> Result<Self, Box<dyn Error>> {
+        Self::with_schedule(year, 25, Self::UNLOCK_UTC_OFFSET)
+    }
+
+    /// Create a calendar with `days` puzzles unlocking at midnight in the timezone `utc_offset`
+    /// seconds east of UTC.
+    pub fn with_schedule(year: i32, days: u8, utc_offset: i32) -> Result<Self, Box<dyn Error>> {
+        let timezone = FixedOffset::east_opt(utc_offset)
+            .ok_or_else(|| format!("utc offset out of bounds: {}s", utc_offset))?;
+        if days == 0 {
+            return Err("event needs at least one day".into());
+        }
+
+        let unlocks = (1..=days)
+            .map(|day| {
+                timezone
+                    .with_ymd_and_hms(year, 12, day as u32, 0, 0, 0)
+                    .single()
+                    .ok_or_else(|| format!("december {} {} doesn't exist", day, year))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self { year, unlocks })
+    }
+
+    pub fn year(&self) -> i32 {
+        self.year
+    }
+
+    /// Number of days with a puzzle.
+    pub fn days(&self) -> u8 {
+        self.unlocks.len() as u8
+    }
+
+    /// Get the day whose puzzle was unlocked most recently.
+    /// Returns `None` before the first and after the last day of the event.
+    pub fn unlocked_day(&self, now: DateTime<Utc>) -> Option<u8> {
+        let end = *self.unlocks.last()? + Duration::days(1);
+        if now >= end {
+            return None;
+        }
+
+        let unlocked = self.unlocks.iter().filter(|&&time| time <= now).count();
+        if unlocked == 0 {
+            None
+        } else {
+            Some(unlocked as u8)
+        }
+    }
+
+    /// Get the next point in time a puzzle unlocks.
+    pub fn next_unlock(&self, now: DateTime<Utc>) -> Option<DateTime<FixedOffset>> {
+        self.unlocks.iter().copied().find(|&time| time > now)
+    }
+
+    /// Get the day which should be solved at the given point in time.
+    pub fn current_day(&self, now: DateTime<Utc>) -> Result<u8, Box<dyn Error>> {
+        if let Some(day) = self.unlocked_day(now) {
+            return Ok(day);
+        }
+
+        if let Some(unlock) = self.next_unlock(now) {
+            let remaining = unlock.signed_duration_since(now);
+            return Err(format!("next unlock in {}", format_duration(remaining)).into());
+        }
+
+        Err(format!("the {} event is over", self.year).into())
+    }
+}
+
+/// Format a duration as `HH:MM:SS`.
+/// Hours aren't wrapped so durations longer than a day have more than 24 hours.
+fn format_duration(d: Duration) -> String {
+    let secs = d.num_seconds();
+    format!(
+        "{:02}:{:02}:{:02}",
+        secs / 3600,
+        (secs / 60) % 60,
+        secs % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(month: u32, day: u32, hour: u32, min: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2019, month, day, hour, min, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_unlocked_day() {
+        let cal = Calendar::new(2019).unwrap();
+
+        // midnight EST is 05:00 UTC
+        assert_eq!(cal.unlocked_day(utc(12, 1, 4, 59)), None);
+        assert_eq!(cal.unlocked_day(utc(12, 1, 5, 0)), Some(1));
+        assert_eq!(cal.unlocked_day(utc(12, 10, 23, 0)), Some(10));
+        assert_eq!(cal.unlocked_day(utc(12, 11, 4, 0)), Some(10));
+        assert_eq!(cal.unlocked_day(utc(12, 26, 4, 59)), Some(25));
+        assert_eq!(cal.unlocked_day(utc(12, 26, 5, 0)), None);
+    }
+
+    #[test]
+    fn test_current_day() {
+        let cal = Calendar::new(2019).unwrap();
+
+        assert_eq!(cal.current_day(utc(12, 3, 12, 0)).unwrap(), 3);
+        assert_eq!(
+            cal.current_day(utc(11, 30, 3, 30)).unwrap_err().to_string(),
+            "next unlock in 25:30:00"
+        );
+        assert_eq!(
+            cal.current_day(utc(12, 31, 0, 0)).unwrap_err().to_string(),
+            "the 2019 event is over"
+        );
+    }
+
+    #[test]
+    fn test_custom_calendar() {
+        let cal = Calendar::with_schedule(2019, 12, 0).unwrap();
+
+        assert_eq!(cal.unlocked_day(utc(12, 1, 0, 0)), Some(1));
+        assert_eq!(cal.unlocked_day(utc(12, 12, 23, 59)), Some(12));
+        assert_eq!(cal.unlocked_day(utc(12, 13, 0, 0)), None);
+        assert_eq!(cal.next_unlock(utc(12, 12, 0, 0)), None);
+    }
+
+    #[test]
+    fn test_invalid_calendar() {
+        assert!(Calendar::with_schedule(2019, 32, 0).is_err());
+        assert!(Calendar::with_schedule(2019, 0, 0).is_err());
+        assert!(Calendar::with_schedule(2019, 25, 24 * 60 * 60).is_err());
+        assert!(Calendar::new(i32::MAX).is_err());
+    }
+}
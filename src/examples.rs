@@ -0,0 +1,290 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Example taken from a puzzle description together with its expected answers.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Example {
+    pub input: String,
+    pub first: Option<String>,
+    pub second: Option<String>,
+    /// Puzzle parameters required by the example (e.g. a smaller image size).
+    pub params: Vec<(String, String)>,
+}
+
+impl Example {
+    fn answers_to_string(&self) -> String {
+        let mut s = String::new();
+        if let Some(first) = &self.first {
+            s.push_str(&format!("first: {}\n", first));
+        }
+        if let Some(second) = &self.second {
+            s.push_str(&format!("second: {}\n", second));
+        }
+        for (name, value) in self.params.iter() {
+            s.push_str(&format!("param {}: {}\n", name, value));
+        }
+
+        s
+    }
+
+    #[cfg(test)]
+    fn parse_answers(&mut self, raw: &str) {
+        for line in raw.lines() {
+            let mut parts = line.splitn(2, ':').map(str::trim);
+            let (key, value) = match (parts.next(), parts.next()) {
+                (Some(key), Some(value)) => (key, value.to_string()),
+                _ => continue,
+            };
+
+            match key {
+                "first" => self.first = Some(value),
+                "second" => self.second = Some(value),
+                _ if key.starts_with("param ") => {
+                    let name = key["param ".len()..].trim().to_string();
+                    self.params.push((name, value));
+                }
+                _ => (),
+            }
+        }
+    }
+}
+
+/// Replace the HTML entities used on the puzzle pages.
+fn unescape_html(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Remove all tags from an HTML fragment and unescape the text.
+fn strip_tags(s: &str) -> String {
+    let mut text = String::with_capacity(s.len());
+    let mut in_tag = false;
+    for c in s.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            _ if !in_tag => text.push(c),
+            _ => (),
+        }
+    }
+
+    unescape_html(&text)
+}
+
+/// Split a document into the contents of all elements with the given tag.
+fn elements<'a>(html: &'a str, open: &'a str, close: &'a str) -> Vec<(usize, &'a str)> {
+    let mut found = Vec::new();
+    let mut pos = 0;
+    while let Some(start) = html[pos..].find(open).map(|i| i + pos) {
+        let content_start = match html[start..].find('>') {
+            Some(i) => start + i + 1,
+            None => break,
+        };
+        let end = match html[content_start..].find(close) {
+            Some(i) => content_start + i,
+            None => break,
+        };
+
+        found.push((start, &html[content_start..end]));
+        pos = end + close.len();
+    }
+
+    found
+}
+
+/// Find the last emphasized code snippet in the fragment.
+/// The puzzle descriptions highlight answers as `<code><em>42</em></code>`.
+fn last_emphasized_answer(html: &str) -> Option<String> {
+    elements(html, "<code><em>", "</em></code>")
+        .into_iter()
+        .chain(elements(html, "<em><code>", "</code></em>"))
+        .max_by_key(|(start, _)| *start)
+        .map(|(_, answer)| strip_tags(answer))
+}
+
+/// Extract all examples of one part of the puzzle description.
+/// Each `<pre><code>` block is paired with the last emphasized answer before the next block.
+fn extract_from_article(article: &str) -> Vec<(String, String)> {
+    let blocks = elements(article, "<pre><code>", "</code></pre>");
+
+    blocks
+        .iter()
+        .enumerate()
+        .filter_map(|(i, (start, content))| {
+            let text_start = start + article[*start..].find("</pre>")?;
+            let text_end = blocks.get(i + 1).map_or(article.len(), |(s, _)| *s);
+            let answer = last_emphasized_answer(&article[text_start..text_end])?;
+
+            Some((strip_tags(content), answer))
+        })
+        .collect()
+}
+
+/// Extract the examples from a saved puzzle description page.
+/// The first `<article>` describes the first part, the second one the second part.
+/// Examples from the second part with the same input as in the first part are merged.
+pub fn extract_examples(html: &str) -> Vec<Example> {
+    let mut examples: Vec<Example> = Vec::new();
+
+    for (part, (_, article)) in elements(html, "<article", "</article>")
+        .into_iter()
+        .take(2)
+        .enumerate()
+    {
+        for (input, answer) in extract_from_article(article) {
+            let index = match examples.iter().position(|e| e.input == input) {
+                Some(index) => index,
+                None => {
+                    examples.push(Example {
+                        input,
+                        ..Example::default()
+                    });
+                    examples.len() - 1
+                }
+            };
+
+            let example = &mut examples[index];
+            if part == 0 {
+                example.first = Some(answer);
+            } else {
+                example.second = Some(answer);
+            }
+        }
+    }
+
+    examples
+}
+
+fn day_dir(dir: &Path, day: u8) -> PathBuf {
+    dir.join(format!("day_{:02}", day))
+}
+
+/// Write the examples as fixtures for the given day.
+/// Every example is stored as `example_N.txt` with its answers in `example_N.answers`.
+/// Parameters required by an example are added to the answers as `param NAME: VALUE`.
+pub fn write_fixtures(dir: &Path, day: u8, examples: &[Example]) -> io::Result<()> {
+    let day_dir = day_dir(dir, day);
+    fs::create_dir_all(&day_dir)?;
+
+    for (i, example) in examples.iter().enumerate() {
+        let name = format!("example_{}", i + 1);
+        fs::write(day_dir.join(format!("{}.txt", name)), &example.input)?;
+        fs::write(
+            day_dir.join(format!("{}.answers", name)),
+            example.answers_to_string(),
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Load all fixtures of the given day.
+/// Returns the name of each fixture together with the example.
+#[cfg(test)]
+pub fn load_fixtures(dir: &Path, day: u8) -> io::Result<Vec<(String, Example)>> {
+    let day_dir = day_dir(dir, day);
+    if !day_dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut paths = fs::read_dir(&day_dir)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<io::Result<Vec<_>>>()?;
+    paths.sort();
+
+    let mut fixtures = Vec::new();
+    for path in paths {
+        if path.extension().is_none_or(|ext| ext != "txt") {
+            continue;
+        }
+
+        let mut example = Example {
+            input: fs::read_to_string(&path)?,
+            ..Example::default()
+        };
+        match fs::read_to_string(path.with_extension("answers")) {
+            Ok(raw) => example.parse_answers(&raw),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => (),
+            Err(e) => return Err(e),
+        }
+
+        let name = path.file_stem().unwrap_or_default().to_string_lossy();
+        fixtures.push((format!("day_{:02}/{}", day, name), example));
+    }
+
+    Ok(fixtures)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::temp_path;
+
+    const PAGE: &str = r#"<main>
+<article class="day-desc"><h2>--- Day 42: Test ---</h2><p>For example:</p>
+<pre><code>1,2
+3,4
+</code></pre>
+<p>Here, the sum is <code>10</code> and the answer is <code><em>24</em></code>.</p>
+<pre><code>&lt;x=<em>1</em>&gt;
+</code></pre>
+<p>This one results in <em><code>-1</code></em>.</p>
+<pre><code>no answer for this one
+</code></pre>
+</article>
+<p>Your puzzle answer was <code>1</code>.</p>
+<article class="day-desc"><h2 id="part2">--- Part Two ---</h2>
+<pre><code>1,2
+3,4
+</code></pre>
+<p>Now it's <code><em>6</em></code>.</p>
+</article>
+</main>"#;
+
+    #[test]
+    fn test_extract_examples() {
+        assert_eq!(
+            extract_examples(PAGE),
+            vec![
+                Example {
+                    input: String::from("1,2\n3,4\n"),
+                    first: Some(String::from("24")),
+                    second: Some(String::from("6")),
+                    params: Vec::new(),
+                },
+                Example {
+                    input: String::from("<x=1>\n"),
+                    first: Some(String::from("-1")),
+                    second: None,
+                    params: Vec::new(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fixtures_round_trip() -> io::Result<()> {
+        let dir = temp_path("fixtures");
+        let mut examples = extract_examples(PAGE);
+        examples[1]
+            .params
+            .push((String::from("size"), String::from("3")));
+        write_fixtures(&dir, 42, &examples)?;
+
+        let fixtures = load_fixtures(&dir, 42)?;
+        assert_eq!(
+            fixtures.iter().map(|(n, _)| n.as_str()).collect::<Vec<_>>(),
+            vec!["day_42/example_1", "day_42/example_2"]
+        );
+        assert_eq!(
+            fixtures.into_iter().map(|(_, e)| e).collect::<Vec<_>>(),
+            examples
+        );
+
+        fs::remove_dir_all(&dir)
+    }
+}
@@ -0,0 +1,217 @@
+use crate::input::{Input, InputDir};
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Environment variable which takes precedence over the session file.
+pub const SESSION_ENV_VAR: &str = "AOC_SESSION";
+
+pub const BASE_URL: &str = "https://adventofcode.com";
+
+const USER_AGENT: &str = "github.com/siku2/aoc_2019 by simon@siku2.io";
+
+/// Read the session cookie value from the environment or the given file.
+/// Returns `None` if neither of them is set, empty values count as not set.
+pub fn read_session(path: impl AsRef<Path>) -> io::Result<Option<String>> {
+    let non_empty = |s: &str| Some(s.trim().to_string()).filter(|s| !s.is_empty());
+
+    if let Some(session) = env::var(SESSION_ENV_VAR)
+        .ok()
+        .as_deref()
+        .and_then(non_empty)
+    {
+        return Ok(Some(session));
+    }
+
+    match fs::read_to_string(path) {
+        Ok(s) => Ok(non_empty(&s)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Minimal HTTP client interface required to download inputs.
+pub trait HttpClient {
+    /// Perform a GET request with the given cookie header and return the body.
+    fn get(&self, url: &str, cookie: &str) -> Result<String, Box<dyn Error>>;
+}
+
+/// HTTP client backed by `ureq`.
+pub struct UreqClient {
+    agent: ureq::Agent,
+}
+
+impl UreqClient {
+    pub fn new() -> Self {
+        Self {
+            agent: ureq::AgentBuilder::new().user_agent(USER_AGENT).build(),
+        }
+    }
+}
+
+impl HttpClient for UreqClient {
+    fn get(&self, url: &str, cookie: &str) -> Result<String, Box<dyn Error>> {
+        match self.agent.get(url).set("Cookie", cookie).call() {
+            Ok(resp) => Ok(resp.into_string()?),
+            Err(ureq::Error::Status(status, _)) => {
+                Err(format!("server responded with status {}", status).into())
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// Downloads puzzle inputs using a session cookie.
+pub struct Fetcher<C: HttpClient> {
+    client: C,
+    base_url: String,
+    session: String,
+}
+
+impl<C: HttpClient> Fetcher<C> {
+    pub fn new(client: C, base_url: &str, session: &str) -> Self {
+        Self {
+            client,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            session: session.to_string(),
+        }
+    }
+
+    pub fn input_url(&self, year: i32, day: u8) -> String {
+        format!("{}/{}/day/{}/input", self.base_url, year, day)
+    }
+
+    pub fn fetch_input(&self, year: i32, day: u8) -> Result<String, Box<dyn Error>> {
+        let cookie = format!("session={}", self.session);
+        self.client.get(&self.input_url(year, day), &cookie)
+    }
+
+    /// Read the input from the input directory.
+    /// If the day isn't cached yet, the input is downloaded and written to the directory first.
+    pub fn load_or_fetch(
+        &self,
+        dir: &InputDir,
+        year: i32,
+        day: u8,
+    ) -> Result<Input, Box<dyn Error>> {
+        if let Some(input) = dir.load(year, day)? {
+            return Ok(input);
+        }
+
+        let raw = self.fetch_input(year, day)?;
+        dir.store(year, day, &raw)?;
+
+        Ok(Input::new(&raw))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::temp_path;
+    use std::cell::Cell;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    struct CountingClient {
+        calls: Cell<usize>,
+    }
+
+    impl HttpClient for CountingClient {
+        fn get(&self, url: &str, cookie: &str) -> Result<String, Box<dyn Error>> {
+            self.calls.set(self.calls.get() + 1);
+            Ok(format!("{}\n{}\n", url, cookie))
+        }
+    }
+
+    fn temp_input_dir(name: &str) -> InputDir {
+        InputDir::new(temp_path(name), InputDir::DEFAULT_PATTERN)
+    }
+
+    #[test]
+    fn test_read_empty_session() -> Result<(), Box<dyn Error>> {
+        let path = temp_path("session");
+        fs::write(&path, " \n")?;
+        let session = read_session(&path);
+        fs::remove_file(&path)?;
+
+        // the environment variable takes precedence, so this only holds if it's not set
+        if env::var(SESSION_ENV_VAR).map_or(true, |s| s.trim().is_empty()) {
+            assert_eq!(session?, None);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_or_fetch_caches() -> Result<(), Box<dyn Error>> {
+        let dir = temp_input_dir("fetch_cache");
+        let fetcher = Fetcher::new(
+            CountingClient {
+                calls: Cell::new(0),
+            },
+            "http://example.com/",
+            "abc",
+        );
+
+        let input = fetcher.load_or_fetch(&dir, 2019, 4)?;
+        assert_eq!(
+            input.raw,
+            "http://example.com/2019/day/4/input\nsession=abc\n"
+        );
+        assert_eq!(fetcher.client.calls.get(), 1);
+
+        let input = fetcher.load_or_fetch(&dir, 2019, 4)?;
+        assert_eq!(
+            input.raw,
+            "http://example.com/2019/day/4/input\nsession=abc\n"
+        );
+        assert_eq!(fetcher.client.calls.get(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_ureq_client_stub_server() -> Result<(), Box<dyn Error>> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let addr = listener.local_addr()?;
+
+        let server = thread::spawn(move || -> io::Result<Vec<String>> {
+            let (stream, _) = listener.accept()?;
+            let mut request = Vec::new();
+            let mut reader = BufReader::new(stream.try_clone()?);
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line)?;
+                let line = line.trim_end().to_string();
+                if line.is_empty() {
+                    break;
+                }
+                request.push(line);
+            }
+
+            let body = "1-2\n";
+            let mut stream = stream;
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            )?;
+
+            Ok(request)
+        });
+
+        let fetcher = Fetcher::new(UreqClient::new(), &format!("http://{}", addr), "abc");
+        assert_eq!(fetcher.fetch_input(2019, 4)?, "1-2\n");
+
+        let request = server.join().unwrap()?;
+        assert_eq!(request[0], "GET /2019/day/4/input HTTP/1.1");
+        assert!(request.iter().any(|l| l == "Cookie: session=abc"));
+
+        Ok(())
+    }
+}
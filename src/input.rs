@@ -1,13 +1,18 @@
-use std::{io, str};
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::{fs, io, str};
 
 pub struct Input {
     pub raw: String,
+    params: HashMap<String, String>,
 }
 
 impl Input {
     pub fn new(raw: &str) -> Input {
         Input {
             raw: String::from(raw),
+            params: HashMap::new(),
         }
     }
 
@@ -15,7 +20,25 @@ impl Input {
         let mut s = String::new();
         reader.read_to_string(&mut s)?;
 
-        io::Result::Ok(Input { raw: s })
+        io::Result::Ok(Input {
+            raw: s,
+            params: HashMap::new(),
+        })
+    }
+
+    /// Override the value of a puzzle parameter.
+    pub fn set_param(&mut self, name: &str, value: &str) {
+        self.params.insert(String::from(name), String::from(value));
+    }
+
+    #[cfg(test)]
+    pub fn with_param(mut self, name: &str, value: &str) -> Input {
+        self.set_param(name, value);
+        self
+    }
+
+    pub fn param_names<'a>(&'a self) -> impl Iterator<Item = &'a str> + 'a {
+        self.params.keys().map(String::as_str)
     }
 
     pub fn lines<'a>(&'a self) -> impl Iterator<Item = &str> + 'a {
@@ -56,3 +79,174 @@ impl Input {
         self.map_csv(|s| s.parse())
     }
 }
+
+/// Named parameter of a puzzle.
+/// The value can be overridden for an input, otherwise the default is used.
+pub struct Param {
+    pub name: &'static str,
+    pub default: &'static str,
+    pub help: &'static str,
+}
+
+impl Param {
+    pub const fn new(name: &'static str, default: &'static str, help: &'static str) -> Param {
+        Param {
+            name,
+            default,
+            help,
+        }
+    }
+
+    /// Get the parsed value of the parameter for the given input.
+    pub fn get<T: str::FromStr>(&self, i: &Input) -> Result<T, Box<dyn Error>> {
+        let value = i
+            .params
+            .get(self.name)
+            .map(String::as_str)
+            .unwrap_or(self.default);
+
+        value
+            .parse()
+            .map_err(|_| format!("invalid value for parameter {}: {}", self.name, value).into())
+    }
+
+    /// Write to the file named by the parameter unless it's empty.
+    /// The contents are only created if they're written, returns whether they were.
+    pub fn write_file<C: AsRef<[u8]>>(
+        &self,
+        i: &Input,
+        contents: impl FnOnce() -> C,
+    ) -> Result<bool, Box<dyn Error>> {
+        let path: String = self.get(i)?;
+        if path.is_empty() {
+            return Ok(false);
+        }
+
+        fs::write(&path, contents())
+            .map_err(|e| format!("couldn't write {} {}: {}", self.name, path, e))?;
+        Ok(true)
+    }
+}
+
+/// Directory containing the puzzle inputs with one file per day.
+pub struct InputDir {
+    path: PathBuf,
+    pattern: String,
+}
+
+impl InputDir {
+    /// File name pattern used if none is specified.
+    /// `{year}` and `{day}` are replaced by the year and the zero-padded day respectively.
+    pub const DEFAULT_PATTERN: &'static str = "day_{day}.txt";
+
+    pub fn new(path: impl AsRef<Path>, pattern: &str) -> InputDir {
+        InputDir {
+            path: path.as_ref().to_path_buf(),
+            pattern: String::from(pattern),
+        }
+    }
+
+    /// Get the path of the input file for the given day.
+    pub fn path_for(&self, year: i32, day: u8) -> PathBuf {
+        let name = self
+            .pattern
+            .replace("{year}", &year.to_string())
+            .replace("{day}", &format!("{:02}", day));
+
+        self.path.join(name)
+    }
+
+    /// Read the input for the given day.
+    /// Returns `None` if there's no input file for the day.
+    pub fn load(&self, year: i32, day: u8) -> io::Result<Option<Input>> {
+        match fs::File::open(self.path_for(year, day)) {
+            Ok(mut file) => Input::from_reader(&mut file).map(Some),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Write the input for the given day to the directory.
+    pub fn store(&self, year: i32, day: u8, raw: &str) -> io::Result<()> {
+        let path = self.path_for(year, day);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        fs::write(path, raw)
+    }
+}
+
+/// Path in the temporary directory which no other test uses, the file name ends with `name`.
+#[cfg(test)]
+pub fn temp_path(name: &str) -> PathBuf {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static COUNT: AtomicUsize = AtomicUsize::new(0);
+    let n = COUNT.fetch_add(1, Ordering::Relaxed);
+    std::env::temp_dir().join(format!("aoc_2019_{}_{}_{}", std::process::id(), n, name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_param() -> Result<(), Box<dyn Error>> {
+        const STEPS: Param = Param::new("steps", "1000", "number of steps");
+
+        assert_eq!(STEPS.get::<usize>(&Input::new(""))?, 1000);
+        assert_eq!(
+            STEPS.get::<usize>(&Input::new("").with_param("steps", "10"))?,
+            10
+        );
+        assert!(STEPS
+            .get::<usize>(&Input::new("").with_param("steps", "ten"))
+            .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_param_write_file() -> Result<(), Box<dyn Error>> {
+        const REPORT: Param = Param::new("report", "", "file to write the report to");
+
+        let written = REPORT.write_file(&Input::new(""), || -> String { unreachable!() })?;
+        assert!(!written);
+
+        let path = temp_path("report.txt");
+        let input = Input::new("").with_param("report", &path.to_string_lossy());
+        assert!(REPORT.write_file(&input, || "done")?);
+        let contents = fs::read_to_string(&path)?;
+        fs::remove_file(&path)?;
+        assert_eq!(contents, "done");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_input_dir_path_for() {
+        let dir = InputDir::new("inputs", InputDir::DEFAULT_PATTERN);
+        assert_eq!(dir.path_for(2019, 3), Path::new("inputs/day_03.txt"));
+        assert_eq!(dir.path_for(2019, 20), Path::new("inputs/day_20.txt"));
+
+        let dir = InputDir::new("cache", "{year}/{day}.in");
+        assert_eq!(dir.path_for(2018, 7), Path::new("cache/2018/07.in"));
+    }
+
+    #[test]
+    fn test_input_dir_load() -> io::Result<()> {
+        let path = temp_path("inputs");
+        fs::create_dir_all(&path)?;
+        let dir = InputDir::new(&path, InputDir::DEFAULT_PATTERN);
+
+        // blank lines must survive, day 20 depends on them
+        fs::write(dir.path_for(2019, 20), "  A\n\n#.#\n")?;
+        let input = dir.load(2019, 20)?.expect("input should exist");
+        assert_eq!(input.raw, "  A\n\n#.#\n");
+
+        assert!(dir.load(2019, 21)?.is_none());
+
+        fs::remove_dir_all(&path)
+    }
+}
@@ -0,0 +1,293 @@
+//! Parser for the output of the text adventure played by the Intcode droid.
+//!
+//! The droid only prints text, [`parse`] turns it into events which tools like a mapper or a
+//! solver can work with instead of looking for substrings.
+
+pub const DOORS_HERE: &str = "Doors here lead:";
+pub const ITEMS_HERE: &str = "Items here:";
+pub const INVENTORY: &str = "Items in your inventory:";
+pub const NO_ITEMS: &str = "You aren't carrying any items.";
+pub const COMMAND_PROMPT: &str = "Command?";
+
+const YOU_TAKE: &str = "You take the ";
+const YOU_DROP: &str = "You drop the ";
+const TOO_HEAVY: &str = "Droids on this ship are heavier than the detected value";
+const TOO_LIGHT: &str = "Droids on this ship are lighter than the detected value";
+const PASSWORD_PREFIX: &str = "by typing ";
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Room {
+    pub name: String,
+    pub description: String,
+    pub doors: Vec<String>,
+    pub items: Vec<String>,
+}
+
+/// Why the droid was sent back from the pressure-sensitive floor.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Weight {
+    TooHeavy,
+    TooLight,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Event {
+    /// The droid entered (or looked at) a room.
+    Room(Room),
+    Taken(String),
+    Dropped(String),
+    Inventory(Vec<String>),
+    /// The droid was ejected back to the checkpoint.
+    Ejected(Weight),
+    /// The droid passed the floor and got the password for the airlock.
+    Success {
+        password: String,
+    },
+    /// Any other line, like "You can't go that way."
+    Message(String),
+}
+
+/// List the lines starting with "- " are added to.
+#[derive(Clone, Copy)]
+enum List {
+    Doors,
+    Items,
+    Inventory,
+}
+
+fn room_name(line: &str) -> Option<&str> {
+    line.strip_prefix("== ")?.strip_suffix(" ==")
+}
+
+/// Parse a line which isn't part of a room or list.
+fn parse_message(line: &str) -> Event {
+    if let Some(item) = line.strip_prefix(YOU_TAKE) {
+        return Event::Taken(item.trim_end_matches('.').to_string());
+    }
+    if let Some(item) = line.strip_prefix(YOU_DROP) {
+        return Event::Dropped(item.trim_end_matches('.').to_string());
+    }
+    if line == NO_ITEMS {
+        return Event::Inventory(Vec::new());
+    }
+    if line.contains(TOO_HEAVY) {
+        return Event::Ejected(Weight::TooHeavy);
+    }
+    if line.contains(TOO_LIGHT) {
+        return Event::Ejected(Weight::TooLight);
+    }
+
+    let password = line
+        .find(PASSWORD_PREFIX)
+        .and_then(|start| line[start + PASSWORD_PREFIX.len()..].split(' ').next())
+        .filter(|p| !p.is_empty() && p.chars().all(|c| c.is_ascii_alphanumeric()));
+    match password {
+        Some(password) => Event::Success {
+            password: password.to_string(),
+        },
+        None => Event::Message(line.to_string()),
+    }
+}
+
+/// Turn the output of the droid into events in the order they happened.
+pub fn parse(output: &str) -> Vec<Event> {
+    let mut events = Vec::new();
+    // room or inventory which is still being read
+    let mut pending: Option<Event> = None;
+    let mut list: Option<List> = None;
+    let mut needs_description = false;
+
+    for line in output.lines().map(str::trim_end) {
+        if line.is_empty() {
+            list = None;
+            continue;
+        }
+
+        if let Some(name) = room_name(line) {
+            events.extend(pending.take());
+            pending = Some(Event::Room(Room {
+                name: name.to_string(),
+                ..Room::default()
+            }));
+            needs_description = true;
+            continue;
+        }
+
+        match (line, &mut pending) {
+            (DOORS_HERE, Some(Event::Room(_))) => list = Some(List::Doors),
+            (ITEMS_HERE, Some(Event::Room(_))) => list = Some(List::Items),
+            (INVENTORY, _) => {
+                events.extend(pending.take());
+                pending = Some(Event::Inventory(Vec::new()));
+                list = Some(List::Inventory);
+            }
+            (COMMAND_PROMPT, _) => {
+                events.extend(pending.take());
+                list = None;
+            }
+            _ => {
+                let entry = line.strip_prefix("- ").map(String::from);
+                match (entry, list, &mut pending) {
+                    (Some(door), Some(List::Doors), Some(Event::Room(room))) => {
+                        room.doors.push(door)
+                    }
+                    (Some(item), Some(List::Items), Some(Event::Room(room))) => {
+                        room.items.push(item)
+                    }
+                    (Some(item), Some(List::Inventory), Some(Event::Inventory(items))) => {
+                        items.push(item)
+                    }
+                    (_, _, Some(Event::Room(room))) if needs_description => {
+                        room.description = line.to_string();
+                    }
+                    _ => {
+                        events.extend(pending.take());
+                        events.push(parse_message(line));
+                    }
+                }
+            }
+        }
+        needs_description = false;
+    }
+    events.extend(pending);
+
+    events
+}
+
+/// The room the droid ended up in, if the events mention one.
+pub fn last_room(events: &[Event]) -> Option<&Room> {
+    events.iter().rev().find_map(|e| match e {
+        Event::Room(room) => Some(room),
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(s: &[&str]) -> Vec<String> {
+        s.iter().map(|s| s.to_string()).collect()
+    }
+
+    const START: &str = "
+
+
+== Hull Breach ==
+You got in through a hole in the floor here. To keep your ship from also freezing, the hole has been sealed.
+
+Doors here lead:
+- north
+- east
+- west
+
+Command?
+";
+
+    const EJECTED: &str = "
+
+
+== Pressure-Sensitive Floor ==
+Analyzing...
+
+Doors here lead:
+- west
+
+A loud, robotic voice says \"Alert! Droids on this ship are lighter than the detected value!\" and you are ejected back to the checkpoint.
+
+
+
+== Security Checkpoint ==
+In the next room, a pressure-sensitive floor will verify your identity.
+
+Doors here lead:
+- north
+- east
+
+Items here:
+- mutex
+
+Command?
+";
+
+    const SUCCESS: &str = "
+
+
+== Pressure-Sensitive Floor ==
+Analyzing...
+
+Doors here lead:
+- west
+
+A loud, robotic voice says \"Analysis complete! You may proceed.\" and you enter the cockpit.
+Santa notices your small droid, looks puzzled for a moment, realizes what has happened, and radios your ship directly.
+\"Oh, hello! You should be able to get in by typing 2147485856 on the keypad at the main airlock.\"
+";
+
+    #[test]
+    fn test_parse_room() {
+        assert_eq!(
+            parse(START),
+            vec![Event::Room(Room {
+                name: "Hull Breach".to_string(),
+                description: "You got in through a hole in the floor here. To keep your ship from also freezing, the hole has been sealed.".to_string(),
+                doors: strings(&["north", "east", "west"]),
+                items: vec![],
+            })]
+        );
+    }
+
+    #[test]
+    fn test_parse_items() {
+        assert_eq!(
+            parse("\nYou take the mutex.\n\nCommand?\n"),
+            vec![Event::Taken("mutex".to_string())]
+        );
+        assert_eq!(
+            parse("\nYou drop the mutex.\n\nCommand?\n"),
+            vec![Event::Dropped("mutex".to_string())]
+        );
+        assert_eq!(
+            parse("\nItems in your inventory:\n- mutex\n- space law space brochure\n\nCommand?\n"),
+            vec![Event::Inventory(strings(&[
+                "mutex",
+                "space law space brochure"
+            ]))]
+        );
+        assert_eq!(
+            parse("\nYou aren't carrying any items.\n\nCommand?\n"),
+            vec![Event::Inventory(vec![])]
+        );
+        assert_eq!(
+            parse("\nYou can't go that way.\n\nCommand?\n"),
+            vec![Event::Message("You can't go that way.".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_parse_ejected() {
+        let events = parse(EJECTED);
+        assert_eq!(events.len(), 3);
+        assert!(matches!(&events[0], Event::Room(r) if r.name == "Pressure-Sensitive Floor"));
+        assert_eq!(events[1], Event::Ejected(Weight::TooLight));
+
+        let room = last_room(&events).unwrap();
+        assert_eq!(room.name, "Security Checkpoint");
+        assert_eq!(room.doors, strings(&["north", "east"]));
+        assert_eq!(room.items, strings(&["mutex"]));
+    }
+
+    #[test]
+    fn test_parse_success() {
+        let events = parse(SUCCESS);
+        assert_eq!(
+            events.last(),
+            Some(&Event::Success {
+                password: "2147485856".to_string()
+            })
+        );
+        assert!(events[1..events.len() - 1]
+            .iter()
+            .all(|e| matches!(e, Event::Message(_))));
+    }
+}
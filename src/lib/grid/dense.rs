@@ -0,0 +1,270 @@
+use super::{Point, Rect, Render};
+use crate::input::Input;
+
+use std::error::Error;
+use std::fmt;
+use std::ops;
+
+/// Grid storing a value for every point of a rectangle with its top left corner at the origin.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    pub fn new(width: usize, height: usize, fill: T) -> Self
+    where
+        T: Clone,
+    {
+        Self {
+            width,
+            height,
+            cells: vec![fill; width * height],
+        }
+    }
+
+    /// Create a grid from its cells in reading order.
+    pub fn from_cells(width: usize, cells: Vec<T>) -> Result<Self, Box<dyn Error>> {
+        if width == 0 || !cells.len().is_multiple_of(width) {
+            return Err(format!("{} cells don't fit into rows of {}", cells.len(), width).into());
+        }
+
+        Ok(Self {
+            width,
+            height: cells.len() / width,
+            cells,
+        })
+    }
+
+    /// Parse a grid with one row per line.
+    /// `f` returns `None` for characters which aren't valid cells.
+    pub fn from_lines<'a>(
+        lines: impl IntoIterator<Item = &'a str>,
+        mut f: impl FnMut(char) -> Option<T>,
+    ) -> Result<Self, Box<dyn Error>> {
+        let mut width = None;
+        let mut cells = Vec::new();
+        for (y, line) in lines.into_iter().enumerate() {
+            let len = cells.len();
+            for (x, c) in line.chars().enumerate() {
+                let cell =
+                    f(c).ok_or_else(|| format!("unexpected character {:?} at ({}, {})", c, x, y))?;
+                cells.push(cell);
+            }
+
+            let line_width = cells.len() - len;
+            match width {
+                Some(width) if width != line_width => {
+                    return Err(format!(
+                        "row {} has {} cells but the previous rows have {}",
+                        y, line_width, width
+                    )
+                    .into())
+                }
+                _ => width = Some(line_width),
+            }
+        }
+
+        let width = width.ok_or("grid is empty")?;
+        Self::from_cells(width, cells)
+    }
+
+    /// Parse a grid from the non-empty lines of the input.
+    pub fn from_input(i: &Input, f: impl FnMut(char) -> Option<T>) -> Result<Self, Box<dyn Error>> {
+        Self::from_lines(i.lines(), f)
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn bounds(&self) -> Rect {
+        Rect::with_size(self.width, self.height)
+    }
+
+    fn index_of(&self, point: Point) -> Option<usize> {
+        if self.contains(point) {
+            Some(point.y as usize * self.width + point.x as usize)
+        } else {
+            None
+        }
+    }
+
+    fn point_of(&self, index: usize) -> Point {
+        Point::new((index % self.width) as isize, (index / self.width) as isize)
+    }
+
+    pub fn contains(&self, point: Point) -> bool {
+        point.x >= 0
+            && point.y >= 0
+            && (point.x as usize) < self.width
+            && (point.y as usize) < self.height
+    }
+
+    pub fn get(&self, point: Point) -> Option<&T> {
+        self.index_of(point).map(|i| &self.cells[i])
+    }
+
+    pub fn get_mut(&mut self, point: Point) -> Option<&mut T> {
+        self.index_of(point).map(move |i| &mut self.cells[i])
+    }
+
+    /// Iterate over all points in reading order.
+    pub fn points(&self) -> impl Iterator<Item = Point> {
+        self.bounds().points()
+    }
+
+    /// Iterate over all cells in reading order.
+    pub fn iter(&self) -> impl Iterator<Item = (Point, &T)> {
+        self.cells
+            .iter()
+            .enumerate()
+            .map(move |(i, cell)| (self.point_of(i), cell))
+    }
+
+    /// Iterate over the cells of a row.
+    pub fn row(&self, y: usize) -> &[T] {
+        &self.cells[y * self.width..(y + 1) * self.width]
+    }
+
+    /// Find the first point (in reading order) whose cell matches the predicate.
+    pub fn find(&self, predicate: impl FnMut(&T) -> bool) -> Option<Point> {
+        self.cells
+            .iter()
+            .position(predicate)
+            .map(|i| self.point_of(i))
+    }
+
+    /// Iterate over the orthogonally adjacent points inside of the grid.
+    pub fn neighbours4(&self, point: Point) -> impl Iterator<Item = Point> + '_ {
+        point.neighbours4().filter(move |&p| self.contains(p))
+    }
+
+    /// Iterate over all surrounding points inside of the grid, including diagonals.
+    pub fn neighbours8(&self, point: Point) -> impl Iterator<Item = Point> + '_ {
+        point.neighbours8().filter(move |&p| self.contains(p))
+    }
+
+    pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> Grid<U> {
+        Grid {
+            width: self.width,
+            height: self.height,
+            cells: self.cells.iter().map(f).collect(),
+        }
+    }
+
+    /// Display the grid by rendering every cell as a character.
+    pub fn display<'a>(&'a self, f: impl Fn(&T) -> char + 'a) -> impl fmt::Display + 'a {
+        Render {
+            bounds: Some(self.bounds()).filter(|b| b.area() > 0),
+            cell: move |p| f(&self[p]),
+        }
+    }
+}
+
+impl<T> ops::Index<Point> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, point: Point) -> &Self::Output {
+        self.get(point)
+            .unwrap_or_else(|| panic!("point {} out of bounds", point))
+    }
+}
+
+impl<T> ops::IndexMut<Point> for Grid<T> {
+    fn index_mut(&mut self, point: Point) -> &mut Self::Output {
+        self.get_mut(point)
+            .unwrap_or_else(|| panic!("point {} out of bounds", point))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_wall(c: char) -> Option<bool> {
+        match c {
+            '#' => Some(true),
+            '.' => Some(false),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_from_input() -> Result<(), Box<dyn Error>> {
+        let grid = Grid::from_input(
+            &Input::new(
+                "
+                #..
+                .#.
+                ",
+            ),
+            parse_wall,
+        )?;
+
+        assert_eq!((grid.width(), grid.height()), (3, 2));
+        assert_eq!(grid.get(Point::new(1, 1)), Some(&true));
+        assert_eq!(grid.get(Point::new(2, 1)), Some(&false));
+        assert_eq!(grid.get(Point::new(3, 0)), None);
+        assert_eq!(grid.get(Point::new(0, -1)), None);
+        assert_eq!(grid.row(1), &[false, true, false]);
+        assert_eq!(
+            grid.iter()
+                .filter(|(_, &wall)| wall)
+                .map(|(p, _)| p)
+                .collect::<Vec<_>>(),
+            vec![Point::new(0, 0), Point::new(1, 1)]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_input_errors() {
+        assert_eq!(
+            Grid::from_input(&Input::new("#.\n#x"), parse_wall)
+                .unwrap_err()
+                .to_string(),
+            "unexpected character 'x' at (1, 1)"
+        );
+        assert_eq!(
+            Grid::from_input(&Input::new("#.\n#"), parse_wall)
+                .unwrap_err()
+                .to_string(),
+            "row 1 has 1 cells but the previous rows have 2"
+        );
+        assert!(Grid::from_input(&Input::new(""), parse_wall).is_err());
+    }
+
+    #[test]
+    fn test_neighbours() {
+        let grid = Grid::new(3, 3, 0);
+        assert_eq!(grid.neighbours4(Point::new(1, 1)).count(), 4);
+        assert_eq!(grid.neighbours8(Point::new(1, 1)).count(), 8);
+        assert_eq!(
+            grid.neighbours4(Point::ZERO).collect::<Vec<_>>(),
+            vec![Point::new(1, 0), Point::new(0, 1)]
+        );
+        assert_eq!(grid.neighbours8(Point::new(2, 2)).count(), 3);
+    }
+
+    #[test]
+    fn test_mutate_and_display() {
+        let mut grid = Grid::new(3, 2, false);
+        grid[Point::new(2, 0)] = true;
+        *grid.get_mut(Point::new(0, 1)).unwrap() = true;
+
+        assert_eq!(grid.find(|&b| b), Some(Point::new(2, 0)));
+        assert_eq!(
+            grid.display(|&b| if b { '#' } else { '.' }).to_string(),
+            "..#\n#..\n"
+        );
+        assert_eq!(grid.map(|&b| b as u8).row(1), &[1, 0, 0]);
+    }
+}
@@ -0,0 +1,37 @@
+//! Points, directions and grids shared by the puzzles which are laid out on a 2D grid.
+
+mod dense;
+mod point;
+mod rect;
+mod sparse;
+
+pub use dense::Grid;
+pub use point::{Direction, Point};
+pub use rect::Rect;
+pub use sparse::SparseGrid;
+
+use std::fmt;
+
+/// Renders every point of the bounds as a character, one line per row.
+struct Render<F: Fn(Point) -> char> {
+    bounds: Option<Rect>,
+    cell: F,
+}
+
+impl<F: Fn(Point) -> char> fmt::Display for Render<F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let bounds = match &self.bounds {
+            Some(bounds) => bounds,
+            None => return Ok(()),
+        };
+
+        for y in bounds.min.y..=bounds.max.y {
+            for x in bounds.min.x..=bounds.max.x {
+                write!(f, "{}", (self.cell)(Point::new(x, y)))?;
+            }
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,292 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops;
+
+/// Point (or vector) on a 2D grid.
+/// The y axis points downwards, matching the way grids are laid out in the puzzle inputs.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Point<T = isize> {
+    pub x: T,
+    pub y: T,
+}
+
+impl<T> Point<T> {
+    pub const fn new(x: T, y: T) -> Self {
+        Self { x, y }
+    }
+}
+
+impl Point {
+    pub const ZERO: Self = Self::new(0, 0);
+
+    /// Offsets of the orthogonally adjacent points clockwise starting at the top.
+    pub const OFFSETS_4: [Self; 4] = [
+        Self::new(0, -1),
+        Self::new(1, 0),
+        Self::new(0, 1),
+        Self::new(-1, 0),
+    ];
+
+    /// Offsets of all surrounding points including diagonals clockwise starting at the top.
+    pub const OFFSETS_8: [Self; 8] = [
+        Self::new(0, -1),
+        Self::new(1, -1),
+        Self::new(1, 0),
+        Self::new(1, 1),
+        Self::new(0, 1),
+        Self::new(-1, 1),
+        Self::new(-1, 0),
+        Self::new(-1, -1),
+    ];
+
+    /// Get the manhattan length of the vector.
+    pub fn manhattan(self) -> isize {
+        self.x.abs() + self.y.abs()
+    }
+
+    pub fn manhattan_distance(self, other: Self) -> isize {
+        (self - other).manhattan()
+    }
+
+    /// Iterate over the orthogonally adjacent points.
+    pub fn neighbours4(self) -> impl Iterator<Item = Self> {
+        Self::OFFSETS_4.iter().map(move |&offset| self + offset)
+    }
+
+    /// Iterate over all surrounding points including diagonals.
+    pub fn neighbours8(self) -> impl Iterator<Item = Self> {
+        Self::OFFSETS_8.iter().map(move |&offset| self + offset)
+    }
+
+    /// Get the adjacent point in the given direction.
+    pub fn step(self, dir: Direction) -> Self {
+        self + dir.offset()
+    }
+
+    /// Rotate the vector 90° clockwise around the origin.
+    pub fn rotate_cw(self) -> Self {
+        Self::new(-self.y, self.x)
+    }
+
+    /// Rotate the vector 90° counter-clockwise around the origin.
+    pub fn rotate_ccw(self) -> Self {
+        Self::new(self.y, -self.x)
+    }
+}
+
+/// Points are ordered the way they're read, row by row from left to right.
+impl<T: Ord> Ord for Point<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.y.cmp(&other.y).then_with(|| self.x.cmp(&other.x))
+    }
+}
+
+impl<T: Ord> PartialOrd for Point<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Point<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "({}, {})", self.x, self.y)
+    }
+}
+
+impl<T: ops::Add<Output = T>> ops::Add for Point<T> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self::new(self.x + rhs.x, self.y + rhs.y)
+    }
+}
+
+impl<T: ops::AddAssign> ops::AddAssign for Point<T> {
+    fn add_assign(&mut self, rhs: Self) {
+        self.x += rhs.x;
+        self.y += rhs.y;
+    }
+}
+
+impl<T: ops::Sub<Output = T>> ops::Sub for Point<T> {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self::new(self.x - rhs.x, self.y - rhs.y)
+    }
+}
+
+impl<T: ops::SubAssign> ops::SubAssign for Point<T> {
+    fn sub_assign(&mut self, rhs: Self) {
+        self.x -= rhs.x;
+        self.y -= rhs.y;
+    }
+}
+
+impl<T: ops::Neg<Output = T>> ops::Neg for Point<T> {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        Self::new(-self.x, -self.y)
+    }
+}
+
+impl<T: ops::Mul<Output = T> + Copy> ops::Mul<T> for Point<T> {
+    type Output = Self;
+
+    fn mul(self, rhs: T) -> Self::Output {
+        Self::new(self.x * rhs, self.y * rhs)
+    }
+}
+
+impl<T: ops::Div<Output = T> + Copy> ops::Div<T> for Point<T> {
+    type Output = Self;
+
+    fn div(self, rhs: T) -> Self::Output {
+        Self::new(self.x / rhs, self.y / rhs)
+    }
+}
+
+/// Cardinal direction on the grid.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Direction {
+    Up,
+    Right,
+    Down,
+    Left,
+}
+
+impl Direction {
+    /// All directions clockwise starting at the top.
+    pub const ALL: [Self; 4] = [Self::Up, Self::Right, Self::Down, Self::Left];
+
+    fn index(self) -> isize {
+        match self {
+            Self::Up => 0,
+            Self::Right => 1,
+            Self::Down => 2,
+            Self::Left => 3,
+        }
+    }
+
+    /// Parse a direction from either `U`, `R`, `D`, `L` or an arrow like `^`, `>`, `v`, `<`.
+    pub fn from_char(c: char) -> Option<Self> {
+        let dir = match c {
+            'U' | '^' => Self::Up,
+            'R' | '>' => Self::Right,
+            'D' | 'v' => Self::Down,
+            'L' | '<' => Self::Left,
+            _ => return None,
+        };
+
+        Some(dir)
+    }
+
+    pub fn from_offset(offset: Point) -> Option<Self> {
+        Self::ALL.iter().copied().find(|dir| dir.offset() == offset)
+    }
+
+    /// Get the unit vector pointing in the direction.
+    pub fn offset(self) -> Point {
+        Point::OFFSETS_4[self.index() as usize]
+    }
+
+    /// Rotate the direction by the given number of 90° turns.
+    /// Positive turns rotate clockwise, negative ones counter-clockwise.
+    pub fn rotate(self, turns: isize) -> Self {
+        Self::ALL[(self.index() + turns).rem_euclid(4) as usize]
+    }
+
+    pub fn rotate_cw(self) -> Self {
+        self.rotate(1)
+    }
+
+    pub fn rotate_ccw(self) -> Self {
+        self.rotate(-1)
+    }
+
+    pub fn reverse(self) -> Self {
+        self.rotate(2)
+    }
+
+    /// Get the number of clockwise 90° turns required to face the other direction.
+    pub fn turns_to(self, other: Self) -> isize {
+        (other.index() - self.index()).rem_euclid(4)
+    }
+
+    pub fn arrow(self) -> char {
+        match self {
+            Self::Up => '^',
+            Self::Right => '>',
+            Self::Down => 'v',
+            Self::Left => '<',
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_point_ops() {
+        let p = Point::new(3, -2);
+        assert_eq!(p + Point::new(1, 1), Point::new(4, -1));
+        assert_eq!(p - Point::new(1, 1), Point::new(2, -3));
+        assert_eq!(-p, Point::new(-3, 2));
+        assert_eq!(p * 3, Point::new(9, -6));
+        assert_eq!(p.manhattan(), 5);
+        assert_eq!(p.manhattan_distance(Point::new(-1, 1)), 7);
+    }
+
+    #[test]
+    fn test_point_rotation() {
+        let up = Point::new(0, -1);
+        assert_eq!(up.rotate_cw(), Point::new(1, 0));
+        assert_eq!(up.rotate_cw().rotate_cw(), Point::new(0, 1));
+        assert_eq!(up.rotate_ccw(), Point::new(-1, 0));
+        assert_eq!(up.rotate_cw().rotate_ccw(), up);
+    }
+
+    #[test]
+    fn test_point_order() {
+        let mut points = vec![Point::new(1, 1), Point::new(2, 0), Point::new(0, 1)];
+        points.sort();
+        assert_eq!(
+            points,
+            vec![Point::new(2, 0), Point::new(0, 1), Point::new(1, 1)]
+        );
+    }
+
+    #[test]
+    fn test_neighbours() {
+        let p = Point::new(5, 5);
+        assert_eq!(
+            p.neighbours4().collect::<Vec<_>>(),
+            vec![
+                Point::new(5, 4),
+                Point::new(6, 5),
+                Point::new(5, 6),
+                Point::new(4, 5)
+            ]
+        );
+        assert_eq!(p.neighbours8().count(), 8);
+        assert!(p.neighbours8().all(|n| n != p && (n - p).manhattan() <= 2));
+    }
+
+    #[test]
+    fn test_direction() {
+        assert_eq!(Direction::Up.rotate_cw(), Direction::Right);
+        assert_eq!(Direction::Up.rotate_ccw(), Direction::Left);
+        assert_eq!(Direction::Left.rotate(5), Direction::Up);
+        assert_eq!(Direction::Left.rotate(-6), Direction::Right);
+        assert_eq!(Direction::Down.reverse(), Direction::Up);
+        assert_eq!(Direction::Up.turns_to(Direction::Left), 3);
+
+        for &dir in Direction::ALL.iter() {
+            assert_eq!(Direction::from_offset(dir.offset()), Some(dir));
+            assert_eq!(Direction::from_char(dir.arrow()), Some(dir));
+            assert_eq!(dir.offset().rotate_cw(), dir.rotate_cw().offset());
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use super::Point;
+
+/// Rectangle spanned by its top left and bottom right corner (both inclusive).
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Rect {
+    pub min: Point,
+    pub max: Point,
+}
+
+impl Rect {
+    pub const fn new(min: Point, max: Point) -> Self {
+        Self { min, max }
+    }
+
+    /// Create a rectangle with the given size and its top left corner at the origin.
+    pub fn with_size(width: usize, height: usize) -> Self {
+        Self::new(
+            Point::ZERO,
+            Point::new(width as isize - 1, height as isize - 1),
+        )
+    }
+
+    /// Get the smallest rectangle containing all points.
+    /// Returns `None` if there are no points.
+    pub fn from_points(points: impl IntoIterator<Item = Point>) -> Option<Self> {
+        let mut points = points.into_iter();
+        let first = points.next()?;
+        let mut rect = Self::new(first, first);
+        for point in points {
+            rect.extend(point);
+        }
+
+        Some(rect)
+    }
+
+    /// Grow the rectangle just enough to contain the point.
+    pub fn extend(&mut self, point: Point) {
+        self.min = Point::new(self.min.x.min(point.x), self.min.y.min(point.y));
+        self.max = Point::new(self.max.x.max(point.x), self.max.y.max(point.y));
+    }
+
+    pub fn width(&self) -> usize {
+        (self.max.x - self.min.x + 1).max(0) as usize
+    }
+
+    pub fn height(&self) -> usize {
+        (self.max.y - self.min.y + 1).max(0) as usize
+    }
+
+    pub fn area(&self) -> usize {
+        self.width() * self.height()
+    }
+
+    pub fn contains(&self, point: Point) -> bool {
+        (self.min.x..=self.max.x).contains(&point.x) && (self.min.y..=self.max.y).contains(&point.y)
+    }
+
+    /// Check whether the point lies on the outermost ring of the rectangle.
+    pub fn on_border(&self, point: Point) -> bool {
+        self.contains(point)
+            && (point.x == self.min.x
+                || point.x == self.max.x
+                || point.y == self.min.y
+                || point.y == self.max.y)
+    }
+
+    /// Iterate over all points in reading order.
+    pub fn points(&self) -> impl Iterator<Item = Point> {
+        let (min, max) = (self.min, self.max);
+        (min.y..=max.y).flat_map(move |y| (min.x..=max.x).map(move |x| Point::new(x, y)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_points() {
+        let rect =
+            Rect::from_points(vec![Point::new(2, 3), Point::new(-1, 5), Point::new(4, 0)]).unwrap();
+        assert_eq!(rect, Rect::new(Point::new(-1, 0), Point::new(4, 5)));
+        assert_eq!((rect.width(), rect.height(), rect.area()), (6, 6, 36));
+        assert_eq!(Rect::from_points(Vec::new()), None);
+    }
+
+    #[test]
+    fn test_contains() {
+        let rect = Rect::with_size(3, 2);
+        assert!(rect.contains(Point::new(2, 1)));
+        assert!(!rect.contains(Point::new(3, 1)));
+        assert!(!rect.contains(Point::new(0, -1)));
+
+        assert!(rect.on_border(Point::new(0, 1)));
+        assert!(!rect.on_border(Point::new(-1, 1)));
+        assert!(!Rect::with_size(3, 3).on_border(Point::new(1, 1)));
+    }
+
+    #[test]
+    fn test_points() {
+        let points: Vec<_> = Rect::new(Point::new(1, 1), Point::new(2, 2))
+            .points()
+            .collect();
+        assert_eq!(
+            points,
+            vec![
+                Point::new(1, 1),
+                Point::new(2, 1),
+                Point::new(1, 2),
+                Point::new(2, 2)
+            ]
+        );
+    }
+}
@@ -0,0 +1,183 @@
+use super::{Point, Rect, Render};
+use crate::input::Input;
+
+use std::collections::HashMap;
+use std::fmt;
+use std::iter::FromIterator;
+
+/// Grid which only stores the points that have a value.
+/// Useful for grids without fixed bounds or with mostly empty space.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SparseGrid<T> {
+    cells: HashMap<Point, T>,
+}
+
+impl<T> Default for SparseGrid<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> SparseGrid<T> {
+    pub fn new() -> Self {
+        Self {
+            cells: HashMap::new(),
+        }
+    }
+
+    /// Parse a grid with one row per line.
+    /// Characters for which `f` returns `None` are left empty.
+    pub fn from_lines<'a>(
+        lines: impl IntoIterator<Item = &'a str>,
+        mut f: impl FnMut(char) -> Option<T>,
+    ) -> Self {
+        lines
+            .into_iter()
+            .enumerate()
+            .flat_map(|(y, line)| {
+                line.chars()
+                    .enumerate()
+                    .map(move |(x, c)| (Point::new(x as isize, y as isize), c))
+            })
+            .filter_map(|(p, c)| f(c).map(|cell| (p, cell)))
+            .collect()
+    }
+
+    /// Parse a grid from the non-empty lines of the input.
+    pub fn from_input(i: &Input, f: impl FnMut(char) -> Option<T>) -> Self {
+        Self::from_lines(i.lines(), f)
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    pub fn contains(&self, point: Point) -> bool {
+        self.cells.contains_key(&point)
+    }
+
+    pub fn get(&self, point: Point) -> Option<&T> {
+        self.cells.get(&point)
+    }
+
+    pub fn get_mut(&mut self, point: Point) -> Option<&mut T> {
+        self.cells.get_mut(&point)
+    }
+
+    /// Set the value of a point, returning the previous one.
+    pub fn insert(&mut self, point: Point, value: T) -> Option<T> {
+        self.cells.insert(point, value)
+    }
+
+    pub fn remove(&mut self, point: Point) -> Option<T> {
+        self.cells.remove(&point)
+    }
+
+    /// Iterate over all points with a value in arbitrary order.
+    pub fn points(&self) -> impl Iterator<Item = Point> + '_ {
+        self.cells.keys().copied()
+    }
+
+    /// Iterate over all cells in arbitrary order.
+    pub fn iter(&self) -> impl Iterator<Item = (Point, &T)> {
+        self.cells.iter().map(|(&p, cell)| (p, cell))
+    }
+
+    /// Find the first point (in reading order) whose cell matches the predicate.
+    pub fn find(&self, mut predicate: impl FnMut(&T) -> bool) -> Option<Point> {
+        self.iter()
+            .filter(|(_, cell)| predicate(cell))
+            .map(|(p, _)| p)
+            .min()
+    }
+
+    /// Get the smallest rectangle containing all points with a value.
+    pub fn bounds(&self) -> Option<Rect> {
+        Rect::from_points(self.points())
+    }
+
+    /// Iterate over the orthogonally adjacent points which have a value.
+    pub fn neighbours4(&self, point: Point) -> impl Iterator<Item = Point> + '_ {
+        point.neighbours4().filter(move |&p| self.contains(p))
+    }
+
+    /// Iterate over all surrounding points which have a value, including diagonals.
+    pub fn neighbours8(&self, point: Point) -> impl Iterator<Item = Point> + '_ {
+        point.neighbours8().filter(move |&p| self.contains(p))
+    }
+
+    /// Display the bounds of the grid by rendering every point as a character.
+    /// `f` receives `None` for points without a value.
+    pub fn display<'a>(&'a self, f: impl Fn(Option<&T>) -> char + 'a) -> impl fmt::Display + 'a {
+        Render {
+            bounds: self.bounds(),
+            cell: move |p| f(self.get(p)),
+        }
+    }
+}
+
+impl<T> FromIterator<(Point, T)> for SparseGrid<T> {
+    fn from_iter<I: IntoIterator<Item = (Point, T)>>(iter: I) -> Self {
+        Self {
+            cells: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl<T> Extend<(Point, T)> for SparseGrid<T> {
+    fn extend<I: IntoIterator<Item = (Point, T)>>(&mut self, iter: I) {
+        self.cells.extend(iter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_input() {
+        let grid = SparseGrid::from_input(
+            &Input::new(
+                "
+                .#.
+                #a#
+                ",
+            ),
+            |c| if c == '.' { None } else { Some(c) },
+        );
+
+        assert_eq!(grid.len(), 4);
+        assert_eq!(grid.get(Point::new(1, 1)), Some(&'a'));
+        assert_eq!(grid.get(Point::new(0, 0)), None);
+        assert_eq!(grid.find(|&c| c == '#'), Some(Point::new(1, 0)));
+        assert_eq!(grid.neighbours4(Point::new(1, 1)).count(), 3);
+        assert_eq!(grid.neighbours8(Point::new(1, 0)).count(), 3);
+    }
+
+    #[test]
+    fn test_display() {
+        let mut grid = SparseGrid::new();
+        assert_eq!(grid.display(|_| '#').to_string(), "");
+
+        grid.insert(Point::new(-1, 2), 'a');
+        grid.insert(Point::new(1, 3), 'b');
+        assert_eq!(
+            grid.bounds(),
+            Some(Rect::new(Point::new(-1, 2), Point::new(1, 3)))
+        );
+        assert_eq!(
+            grid.display(|c| c.copied().unwrap_or(' ')).to_string(),
+            "a  \n  b\n"
+        );
+
+        assert_eq!(grid.remove(Point::new(-1, 2)), Some('a'));
+        assert_eq!(
+            grid.display(|c| c.copied().unwrap_or(' ')).to_string(),
+            "b\n"
+        );
+    }
+}
@@ -0,0 +1,339 @@
+use super::{Image, Rgb};
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+
+/// Codes are at most 12 bits wide, the last one is never assigned so the table is reset before.
+const MAX_CODE: u16 = 4095;
+
+/// Packs codes of varying width, least significant bit first.
+struct BitWriter {
+    out: Vec<u8>,
+    bits: u32,
+    len: u32,
+}
+
+impl BitWriter {
+    fn new() -> Self {
+        Self {
+            out: Vec::new(),
+            bits: 0,
+            len: 0,
+        }
+    }
+
+    fn write(&mut self, code: u16, width: u32) {
+        self.bits |= (code as u32) << self.len;
+        self.len += width;
+        while self.len >= 8 {
+            self.out.push(self.bits as u8);
+            self.bits >>= 8;
+            self.len -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.len > 0 {
+            self.out.push(self.bits as u8);
+        }
+        self.out
+    }
+}
+
+/// Variable width LZW coding as used by GIF.
+struct LzwEncoder {
+    min_width: u32,
+    width: u32,
+    /// Last assigned code.
+    hi: u16,
+    overflow: u16,
+    table: HashMap<(u16, u8), u16>,
+    writer: BitWriter,
+}
+
+impl LzwEncoder {
+    fn new(min_width: u32) -> Self {
+        let mut encoder = Self {
+            min_width,
+            width: 0,
+            hi: 0,
+            overflow: 0,
+            table: HashMap::new(),
+            writer: BitWriter::new(),
+        };
+        encoder.reset();
+        encoder
+    }
+
+    fn clear_code(&self) -> u16 {
+        1 << self.min_width
+    }
+
+    fn reset(&mut self) {
+        self.writer
+            .write(self.clear_code(), self.width.max(self.min_width + 1));
+        self.width = self.min_width + 1;
+        self.hi = self.clear_code() + 1;
+        self.overflow = 1 << self.width;
+        self.table.clear();
+    }
+
+    /// Advance to the next code, returns false if the table was reset instead.
+    fn next_code(&mut self) -> bool {
+        self.hi += 1;
+        if self.hi == self.overflow {
+            self.width += 1;
+            self.overflow <<= 1;
+        }
+        if self.hi == MAX_CODE {
+            self.reset();
+            return false;
+        }
+
+        true
+    }
+
+    fn encode(mut self, data: &[u8]) -> Vec<u8> {
+        let eoi = self.clear_code() + 1;
+        let (&first, rest) = match data.split_first() {
+            Some(split) => split,
+            None => {
+                self.writer.write(eoi, self.width);
+                return self.writer.finish();
+            }
+        };
+
+        let mut prefix = first as u16;
+        for &b in rest {
+            if let Some(&code) = self.table.get(&(prefix, b)) {
+                prefix = code;
+                continue;
+            }
+
+            self.writer.write(prefix, self.width);
+            if self.next_code() {
+                self.table.insert((prefix, b), self.hi);
+            }
+            prefix = b as u16;
+        }
+
+        self.writer.write(prefix, self.width);
+        self.next_code();
+        self.writer.write(eoi, self.width);
+
+        self.writer.finish()
+    }
+}
+
+/// Collect the distinct colours of all images in order of appearance.
+fn palette(images: &[Image]) -> Result<Vec<Rgb>, Box<dyn Error>> {
+    let mut colors = Vec::new();
+    let mut seen = HashSet::new();
+    for &color in images.iter().flat_map(|i| i.pixels.iter()) {
+        if seen.insert(color) {
+            colors.push(color);
+        }
+    }
+
+    if colors.len() > 256 {
+        return Err(format!("gif supports at most 256 colours, got {}", colors.len()).into());
+    }
+
+    Ok(colors)
+}
+
+/// Encode the images as an endlessly looping animated GIF.
+/// All images must have the same size.
+pub fn encode_gif(images: &[Image], fps: f64) -> Result<Vec<u8>, Box<dyn Error>> {
+    let first = images.first().ok_or("no images to encode")?;
+    let (width, height) = (first.width, first.height);
+    if images
+        .iter()
+        .any(|i| (i.width, i.height) != (width, height))
+    {
+        return Err("all images must have the same size".into());
+    }
+    if width > u16::MAX as usize || height > u16::MAX as usize {
+        return Err(format!(
+            "image of {}x{} pixels is too large for a gif",
+            width, height
+        )
+        .into());
+    }
+
+    let colors = palette(images)?;
+    let index: HashMap<Rgb, u8> = colors
+        .iter()
+        .enumerate()
+        .map(|(i, &c)| (c, i as u8))
+        .collect();
+    // the colour table has 2^bits entries, image data uses at least 2 bits
+    let bits = (1..=8).find(|&b| 1 << b >= colors.len()).unwrap_or(8);
+    let min_width = bits.max(2);
+
+    let mut out = b"GIF89a".to_vec();
+    out.extend_from_slice(&(width as u16).to_le_bytes());
+    out.extend_from_slice(&(height as u16).to_le_bytes());
+    // global colour table, colour resolution and table size
+    out.push(0x80 | ((bits as u8 - 1) << 4) | (bits as u8 - 1));
+    // background colour and aspect ratio
+    out.extend_from_slice(&[0, 0]);
+    for i in 0..1 << bits {
+        out.extend_from_slice(&colors.get(i).copied().unwrap_or([0, 0, 0]));
+    }
+
+    // loop forever
+    out.extend_from_slice(&[0x21, 0xFF, 0x0B]);
+    out.extend_from_slice(b"NETSCAPE2.0");
+    out.extend_from_slice(&[0x03, 0x01, 0x00, 0x00, 0x00]);
+
+    // most viewers don't go below two hundredths of a second
+    let delay = if fps > 0.0 {
+        (100.0 / fps).round().clamp(2.0, u16::MAX as f64) as u16
+    } else {
+        0
+    };
+
+    for image in images {
+        // graphic control extension with the frame delay
+        out.extend_from_slice(&[0x21, 0xF9, 0x04, 0x00]);
+        out.extend_from_slice(&delay.to_le_bytes());
+        out.extend_from_slice(&[0x00, 0x00]);
+
+        // image descriptor covering the whole screen
+        out.push(0x2C);
+        out.extend_from_slice(&[0, 0, 0, 0]);
+        out.extend_from_slice(&(width as u16).to_le_bytes());
+        out.extend_from_slice(&(height as u16).to_le_bytes());
+        out.push(0);
+
+        let indices: Vec<u8> = image.pixels.iter().map(|c| index[c]).collect();
+        out.push(min_width as u8);
+        for block in LzwEncoder::new(min_width).encode(&indices).chunks(255) {
+            out.push(block.len() as u8);
+            out.extend_from_slice(block);
+        }
+        out.push(0);
+    }
+
+    out.push(0x3B);
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lzw_decode(min_width: u32, data: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+        let clear = 1 << min_width;
+        let eoi = clear + 1;
+        let initial: Vec<Vec<u8>> = (0..=eoi).map(|c| vec![c as u8]).collect();
+
+        let mut dict = initial.clone();
+        let mut width = min_width + 1;
+        let mut prev: Option<Vec<u8>> = None;
+        let mut out = Vec::new();
+
+        let mut pos = 0;
+        loop {
+            if pos + width as usize > data.len() * 8 {
+                return Err("missing end of information code".into());
+            }
+            let code = (0..width as usize).fold(0, |code, i| {
+                let bit = (data[(pos + i) / 8] >> ((pos + i) % 8)) & 1;
+                code | (bit as usize) << i
+            });
+            pos += width as usize;
+
+            if code == clear {
+                dict = initial.clone();
+                width = min_width + 1;
+                prev = None;
+                continue;
+            }
+            if code == eoi {
+                return Ok(out);
+            }
+
+            let entry = match (dict.get(code), &prev) {
+                (Some(entry), _) => entry.clone(),
+                (None, Some(p)) if code == dict.len() => [&p[..], &p[..1]].concat(),
+                _ => return Err(format!("invalid code {}", code).into()),
+            };
+            out.extend_from_slice(&entry);
+            if let Some(p) = prev {
+                dict.push([&p[..], &entry[..1]].concat());
+            }
+            prev = Some(entry);
+
+            if dict.len() == 1 << width && width < 12 {
+                width += 1;
+            }
+        }
+    }
+
+    #[test]
+    fn test_lzw_round_trip() -> Result<(), Box<dyn Error>> {
+        assert_eq!(lzw_decode(2, &LzwEncoder::new(2).encode(&[]))?, vec![]);
+
+        let data = vec![1, 1, 1, 1, 1, 2, 2, 2, 0, 1, 1, 1, 1, 1];
+        assert_eq!(lzw_decode(2, &LzwEncoder::new(2).encode(&data))?, data);
+
+        // long enough to run out of codes a few times
+        let mut seed = 12345u32;
+        let data: Vec<u8> = (0..50_000)
+            .map(|_| {
+                seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                (seed >> 16) as u8
+            })
+            .collect();
+        assert_eq!(lzw_decode(8, &LzwEncoder::new(8).encode(&data))?, data);
+
+        let data: Vec<u8> = data.iter().map(|b| b % 3).collect();
+        assert_eq!(lzw_decode(2, &LzwEncoder::new(2).encode(&data))?, data);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_encode_gif() -> Result<(), Box<dyn Error>> {
+        let mut first = Image::new(3, 2, [0, 0, 0]);
+        first.set(1, 1, [255, 0, 0]);
+        let mut second = first.clone();
+        second.set(0, 0, [0, 0, 255]);
+
+        let gif = encode_gif(&[first, second], 25.0)?;
+        assert_eq!(&gif[..6], b"GIF89a");
+        assert_eq!(&gif[6..10], &[3, 0, 2, 0]);
+        // three colours need a table of four entries
+        assert_eq!(gif[10], 0x80 | 0x10 | 0x01);
+        assert_eq!(&gif[13..25], &[0, 0, 0, 255, 0, 0, 0, 0, 255, 0, 0, 0]);
+        assert_eq!(&gif[25..28], &[0x21, 0xFF, 0x0B]);
+        assert_eq!(gif.last(), Some(&0x3B));
+
+        // the first frame starts after the loop extension
+        let frame = &gif[44..];
+        assert_eq!(&frame[..8], &[0x21, 0xF9, 0x04, 0x00, 4, 0, 0, 0]);
+        assert_eq!(frame[8], 0x2C);
+        assert_eq!(frame[18], 2);
+        let len = frame[19] as usize;
+        assert_eq!(lzw_decode(2, &frame[20..20 + len])?, vec![0, 0, 0, 0, 1, 0]);
+        assert_eq!(frame[20 + len], 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_encode_gif_errors() {
+        assert!(encode_gif(&[], 10.0).is_err());
+        assert!(encode_gif(&[Image::new(1, 1, [0; 3]), Image::new(2, 1, [0; 3])], 10.0).is_err());
+
+        let mut image = Image::new(300, 1, [0; 3]);
+        for x in 0..300 {
+            image.set(x, 0, [x as u8, (x / 256) as u8, 0]);
+        }
+        assert_eq!(
+            encode_gif(&[image], 10.0).unwrap_err().to_string(),
+            "gif supports at most 256 colours, got 300"
+        );
+    }
+}
@@ -0,0 +1,172 @@
+//! Raster images built from rendered frames which can be encoded as PNG or animated GIF.
+
+mod gif;
+mod png;
+
+pub use gif::encode_gif;
+pub use png::encode_png;
+
+use crate::lib::render::{Frame, Recording, Style};
+use colored::Color;
+
+pub type Rgb = [u8; 3];
+
+pub const BLACK: Rgb = [0, 0, 0];
+
+/// Image with 8 bit RGB pixels stored row by row.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<Rgb>,
+}
+
+impl Image {
+    pub fn new(width: usize, height: usize, fill: Rgb) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![fill; width * height],
+        }
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Option<Rgb> {
+        if x < self.width && y < self.height {
+            Some(self.pixels[y * self.width + x])
+        } else {
+            None
+        }
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, color: Rgb) {
+        if x < self.width && y < self.height {
+            self.pixels[y * self.width + x] = color;
+        }
+    }
+
+    /// Fill a square of `scale` pixels for every cell of the frame.
+    /// The caption isn't part of the image.
+    pub fn from_frame(frame: &Frame, scale: usize) -> Self {
+        Self::from_frame_sized(frame, scale, frame.width(), frame.height())
+    }
+
+    /// Like `from_frame` but with a fixed number of cells, padding the frame with black.
+    fn from_frame_sized(frame: &Frame, scale: usize, width: usize, height: usize) -> Self {
+        let mut image = Self::new(width * scale, height * scale, BLACK);
+        for y in 0..image.height {
+            for x in 0..image.width {
+                let p = crate::lib::grid::Point::new((x / scale) as isize, (y / scale) as isize);
+                if let Some(style) = frame.get(p) {
+                    image.set(x, y, style_rgb(style));
+                }
+            }
+        }
+
+        image
+    }
+
+    /// Convert every frame of the recording to an image of the same size.
+    /// Frames smaller than the largest one are padded with black.
+    pub fn from_recording(rec: &Recording, scale: usize) -> Vec<Self> {
+        let width = rec.frames().iter().map(Frame::width).max().unwrap_or(0);
+        let height = rec.frames().iter().map(Frame::height).max().unwrap_or(0);
+
+        rec.frames()
+            .iter()
+            .map(|frame| Self::from_frame_sized(frame, scale, width, height))
+            .collect()
+    }
+}
+
+/// Approximate the colours of a typical terminal.
+pub fn color_rgb(color: Color) -> Rgb {
+    match color {
+        Color::Black => [0, 0, 0],
+        Color::Red => [205, 49, 49],
+        Color::Green => [13, 188, 121],
+        Color::Yellow => [229, 229, 16],
+        Color::Blue => [36, 114, 200],
+        Color::Magenta => [188, 63, 188],
+        Color::Cyan => [17, 168, 205],
+        Color::White => [229, 229, 229],
+        Color::BrightBlack => [102, 102, 102],
+        Color::BrightRed => [241, 76, 76],
+        Color::BrightGreen => [35, 209, 139],
+        Color::BrightYellow => [245, 245, 67],
+        Color::BrightBlue => [59, 142, 234],
+        Color::BrightMagenta => [214, 112, 214],
+        Color::BrightCyan => [41, 184, 219],
+        Color::BrightWhite => [255, 255, 255],
+    }
+}
+
+/// Get the colour a cell is drawn with.
+/// Cells don't have a font so the colour of the character is used unless the cell is blank.
+pub fn style_rgb(style: &Style) -> Rgb {
+    let blank = style.ch.is_whitespace();
+    let color = match (style.fg, style.bg) {
+        (Some(fg), _) if !blank => color_rgb(fg),
+        (_, Some(bg)) => color_rgb(bg),
+        _ if blank => BLACK,
+        _ if style.ch == '.' => [64, 64, 64],
+        _ => [192, 192, 192],
+    };
+
+    if style.dimmed {
+        color.map(|c| c / 2)
+    } else {
+        color
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lib::grid::{Grid, Point};
+
+    #[test]
+    fn test_from_frame() {
+        let mut grid = Grid::new(2, 1, false);
+        grid[Point::new(1, 0)] = true;
+        let frame = Frame::from_grid(&grid, |&b| {
+            if b {
+                Style::plain('#').fg(Color::Red)
+            } else {
+                Style::plain(' ').bg(Color::White)
+            }
+        });
+
+        let image = Image::from_frame(&frame, 2);
+        assert_eq!((image.width, image.height), (4, 2));
+        assert_eq!(image.get(1, 1), Some(color_rgb(Color::White)));
+        assert_eq!(image.get(2, 0), Some(color_rgb(Color::Red)));
+        assert_eq!(image.get(4, 0), None);
+    }
+
+    #[test]
+    fn test_from_recording_pads_frames() {
+        let mut rec = Recording::new();
+        rec.push(Frame::from_grid(&Grid::new(1, 1, ()), |_| {
+            Style::plain('#')
+        }));
+        rec.push(Frame::from_grid(&Grid::new(2, 3, ()), |_| {
+            Style::plain('#')
+        }));
+
+        let images = Image::from_recording(&rec, 1);
+        assert_eq!(images.len(), 2);
+        assert!(images.iter().all(|i| (i.width, i.height) == (2, 3)));
+        assert_eq!(images[0].get(0, 0), Some([192, 192, 192]));
+        assert_eq!(images[0].get(1, 2), Some(BLACK));
+    }
+
+    #[test]
+    fn test_style_rgb() {
+        assert_eq!(style_rgb(&Style::plain(' ')), BLACK);
+        assert_eq!(style_rgb(&Style::plain('.').dimmed()), [32, 32, 32]);
+        assert_eq!(
+            style_rgb(&Style::plain('1').bg(Color::White)),
+            color_rgb(Color::White)
+        );
+    }
+}
@@ -0,0 +1,140 @@
+use super::Image;
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'];
+
+/// Largest amount of data a stored deflate block can hold.
+const MAX_STORED_BLOCK: usize = 0xFFFF;
+
+fn crc_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    for (n, entry) in table.iter_mut().enumerate() {
+        let mut c = n as u32;
+        for _ in 0..8 {
+            c = if c & 1 != 0 {
+                0xEDB8_8320 ^ (c >> 1)
+            } else {
+                c >> 1
+            };
+        }
+        *entry = c;
+    }
+
+    table
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let table = crc_table();
+    !data.iter().fold(!0, |crc, &b| {
+        table[((crc ^ b as u32) & 0xFF) as usize] ^ (crc >> 8)
+    })
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    const MOD: u32 = 65521;
+    let (a, b) = data.iter().fold((1, 0), |(a, b), &byte| {
+        let a = (a + byte as u32) % MOD;
+        (a, (b + a) % MOD)
+    });
+
+    (b << 16) | a
+}
+
+/// Wrap the data in a zlib stream using uncompressed deflate blocks.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    // deflate without a preset dictionary and the fastest compression level
+    let mut out = vec![0x78, 0x01];
+
+    let mut chunks = data.chunks(MAX_STORED_BLOCK).peekable();
+    if chunks.peek().is_none() {
+        out.extend_from_slice(&[1, 0, 0, 0xFF, 0xFF]);
+    }
+    while let Some(chunk) = chunks.next() {
+        let last = chunks.peek().is_none();
+        let len = chunk.len() as u16;
+        out.push(last as u8);
+        out.extend_from_slice(&len.to_le_bytes());
+        out.extend_from_slice(&(!len).to_le_bytes());
+        out.extend_from_slice(chunk);
+    }
+
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+fn write_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+
+    let start = out.len();
+    out.extend_from_slice(kind);
+    out.extend_from_slice(data);
+    let crc = crc32(&out[start..]);
+    out.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// Encode the image as an 8 bit RGB PNG.
+pub fn encode_png(image: &Image) -> Vec<u8> {
+    let mut header = Vec::new();
+    header.extend_from_slice(&(image.width as u32).to_be_bytes());
+    header.extend_from_slice(&(image.height as u32).to_be_bytes());
+    // bit depth, colour type (RGB), compression, filter and interlace method
+    header.extend_from_slice(&[8, 2, 0, 0, 0]);
+
+    let mut raw = Vec::with_capacity((image.width * 3 + 1) * image.height);
+    for row in image.pixels.chunks(image.width.max(1)) {
+        // every scanline starts with its filter type, which is always "none"
+        raw.push(0);
+        raw.extend(row.iter().flatten());
+    }
+
+    let mut out = SIGNATURE.to_vec();
+    write_chunk(&mut out, b"IHDR", &header);
+    write_chunk(&mut out, b"IDAT", &zlib_stored(&raw));
+    write_chunk(&mut out, b"IEND", &[]);
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checksums() {
+        assert_eq!(crc32(b"IEND"), 0xAE42_6082);
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(adler32(b"Wikipedia"), 0x11E6_0398);
+    }
+
+    #[test]
+    fn test_zlib_stored_blocks() {
+        let data = vec![7; MAX_STORED_BLOCK + 10];
+        let out = zlib_stored(&data);
+
+        // header, two block headers, data and checksum
+        assert_eq!(out.len(), 2 + 2 * 5 + data.len() + 4);
+        assert_eq!(out[2], 0);
+        assert_eq!(&out[3..7], &[0xFF, 0xFF, 0, 0]);
+        assert_eq!(out[2 + 5 + MAX_STORED_BLOCK], 1);
+        assert_eq!(&out[out.len() - 4..], &adler32(&data).to_be_bytes());
+    }
+
+    #[test]
+    fn test_encode_png() {
+        let mut image = Image::new(2, 1, [0, 0, 0]);
+        image.set(1, 0, [255, 128, 0]);
+        let png = encode_png(&image);
+
+        assert_eq!(&png[..8], &SIGNATURE);
+        assert_eq!(&png[12..16], b"IHDR");
+        assert_eq!(&png[16..29], &[0, 0, 0, 2, 0, 0, 0, 1, 8, 2, 0, 0, 0]);
+        assert_eq!(
+            &png[png.len() - 12..],
+            &[0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xAE, 0x42, 0x60, 0x82]
+        );
+
+        // the IDAT chunk holds the single scanline uncompressed
+        let idat = &png[33..];
+        assert_eq!(&idat[4..8], b"IDAT");
+        assert_eq!(&idat[8 + 7..8 + 7 + 7], &[0, 0, 0, 0, 255, 128, 0]);
+    }
+}
@@ -1 +1,14 @@
+// not every puzzle uses every part of the shared modules
+#![allow(dead_code)]
+
+pub mod adventure;
+pub mod grid;
+pub mod image;
 pub mod intcode;
+pub mod nbody;
+pub mod ocr;
+pub mod render;
+pub mod search;
+pub mod sif;
+pub mod springscript;
+pub mod terminal;
@@ -0,0 +1,537 @@
+//! Simulating bodies which pull each other along every axis.
+//!
+//! Every step the velocities are changed by the gravity between all pairs of bodies, then every
+//! body moves by its velocity. The axes don't influence each other, which makes it possible to
+//! find cycles for every axis on its own and combine them.
+
+use std::error::Error;
+use std::fmt::{self, Write};
+use std::str::FromStr;
+
+/// How much a body is pulled towards another one along an axis.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Gravity {
+    /// Velocity changes by one towards every other body.
+    #[default]
+    Unit,
+    /// Velocity changes by the given amount towards every other body, negative values repel.
+    Scaled(isize),
+    /// Velocity changes by the distance divided by the given amount, rounded towards zero.
+    Spring(isize),
+}
+
+impl Gravity {
+    /// Change of velocity of a body with another body `diff` away.
+    pub fn pull(self, diff: isize) -> isize {
+        match self {
+            Gravity::Unit => diff.signum(),
+            Gravity::Scaled(strength) => strength * diff.signum(),
+            Gravity::Spring(divisor) => diff / divisor,
+        }
+    }
+}
+
+/// Parses `unit`, `scaled:STRENGTH` and `spring:DIVISOR`.
+impl FromStr for Gravity {
+    type Err = Box<dyn Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.trim().splitn(2, ':');
+        let name = parts.next().unwrap_or("");
+        let value = parts
+            .next()
+            .map(|v| {
+                v.trim()
+                    .parse::<isize>()
+                    .map_err(|_| format!("invalid gravity value: {}", v))
+            })
+            .transpose()?;
+
+        match (name, value) {
+            ("unit", None) => Ok(Gravity::Unit),
+            ("scaled", Some(strength)) => Ok(Gravity::Scaled(strength)),
+            ("spring", Some(divisor)) if divisor > 0 => Ok(Gravity::Spring(divisor)),
+            _ => Err(format!("unknown gravity: {}", s).into()),
+        }
+    }
+}
+
+/// Rules of a simulation, the defaults are the ones of the moons of Jupiter.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Rules {
+    pub gravity: Gravity,
+    /// Clamp velocities to this speed along every axis.
+    /// Different velocities can end up the same, so the system might never return to the start.
+    pub speed_limit: Option<isize>,
+}
+
+impl Rules {
+    /// Advance the positions and velocities of all bodies along one axis.
+    fn step_axis(&self, axis: &mut [(isize, isize)]) {
+        for i in 0..axis.len() {
+            for j in i + 1..axis.len() {
+                let pull = self.gravity.pull(axis[j].0 - axis[i].0);
+                axis[i].1 += pull;
+                axis[j].1 -= pull;
+            }
+        }
+
+        for (pos, vel) in axis.iter_mut() {
+            if let Some(limit) = self.speed_limit {
+                *vel = (*vel).clamp(-limit, limit);
+            }
+            *pos += *vel;
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Body {
+    pub position: Vec<isize>,
+    pub velocity: Vec<isize>,
+}
+
+impl Body {
+    /// A body at rest.
+    pub fn new(position: Vec<isize>) -> Self {
+        let velocity = vec![0; position.len()];
+        Self { position, velocity }
+    }
+
+    pub fn potential_energy(&self) -> usize {
+        self.position.iter().map(|v| v.unsigned_abs()).sum()
+    }
+
+    pub fn kinetic_energy(&self) -> usize {
+        self.velocity.iter().map(|v| v.unsigned_abs()).sum()
+    }
+
+    pub fn total_energy(&self) -> usize {
+        self.potential_energy() * self.kinetic_energy()
+    }
+}
+
+/// Step at which a state is first repeated and how many steps it takes to repeat.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Cycle {
+    pub start: usize,
+    pub period: usize,
+}
+
+impl Cycle {
+    /// Cycle of a system made up of independent parts with the given cycles.
+    fn combine(self, other: Self) -> Self {
+        Self {
+            start: self.start.max(other.start),
+            period: lcm(self.period, other.period),
+        }
+    }
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+fn lcm(a: usize, b: usize) -> usize {
+    a / gcd(a, b) * b
+}
+
+/// Find the cycle of an axis using Brent's algorithm, which only needs to keep two states around.
+/// Returns `None` if no state is repeated within `max_steps` steps.
+fn find_axis_cycle(rules: &Rules, start: &[(isize, isize)], max_steps: usize) -> Option<Cycle> {
+    let mut power = 1;
+    let mut period = 1;
+    let mut tortoise = start.to_vec();
+    let mut hare = start.to_vec();
+    rules.step_axis(&mut hare);
+    let mut steps = 1;
+    while tortoise != hare {
+        if steps >= max_steps {
+            return None;
+        }
+        if power == period {
+            tortoise.clone_from(&hare);
+            power *= 2;
+            period = 0;
+        }
+        rules.step_axis(&mut hare);
+        period += 1;
+        steps += 1;
+    }
+
+    // the hare is a period ahead of the tortoise, so they meet at the start of the cycle
+    let mut tortoise = start.to_vec();
+    let mut hare = start.to_vec();
+    for _ in 0..period {
+        rules.step_axis(&mut hare);
+    }
+    let mut start = 0;
+    while tortoise != hare {
+        rules.step_axis(&mut tortoise);
+        rules.step_axis(&mut hare);
+        start += 1;
+    }
+
+    Some(Cycle { start, period })
+}
+
+/// Bodies with the same number of dimensions moving according to the rules.
+#[derive(Clone, Debug)]
+pub struct System {
+    axes: Vec<String>,
+    bodies: Vec<Body>,
+    rules: Rules,
+    steps: usize,
+    energy: Vec<usize>,
+}
+
+impl System {
+    /// Create a system, the axes are named `x`, `y`, `z` and `w` and numbered after that.
+    pub fn new(bodies: Vec<Body>, rules: Rules) -> Result<Self, Box<dyn Error>> {
+        let dimensions = bodies.first().map_or(0, |b| b.position.len());
+        let axes = (0..dimensions)
+            .map(|n| match "xyzw".chars().nth(n) {
+                Some(c) => c.to_string(),
+                None => format!("d{}", n + 1),
+            })
+            .collect();
+
+        Self::with_axes(axes, bodies, rules)
+    }
+
+    fn with_axes(
+        axes: Vec<String>,
+        bodies: Vec<Body>,
+        rules: Rules,
+    ) -> Result<Self, Box<dyn Error>> {
+        if let Some(limit) = rules.speed_limit.filter(|&l| l < 0) {
+            return Err(format!("speed limit can't be negative: {}", limit).into());
+        }
+        if let Gravity::Spring(divisor) = rules.gravity {
+            if divisor <= 0 {
+                return Err(format!("spring divisor has to be positive: {}", divisor).into());
+            }
+        }
+        if let Some(n) = bodies
+            .iter()
+            .position(|b| b.position.len() != axes.len() || b.velocity.len() != axes.len())
+        {
+            return Err(format!("body {} doesn't have {} dimensions", n + 1, axes.len()).into());
+        }
+
+        let mut system = Self {
+            axes,
+            bodies,
+            rules,
+            steps: 0,
+            energy: Vec::new(),
+        };
+        system.energy.push(system.total_energy());
+        Ok(system)
+    }
+
+    /// Parse one body per line like `<x=-1, y=0, z=2>`, the names of the axes are kept.
+    pub fn parse(text: &str, rules: Rules) -> Result<Self, Box<dyn Error>> {
+        let mut axes: Option<Vec<String>> = None;
+        let mut bodies = Vec::new();
+        for (n, line) in text
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .enumerate()
+        {
+            let inner = line
+                .strip_prefix('<')
+                .and_then(|l| l.strip_suffix('>'))
+                .ok_or_else(|| format!("line {}: expected <...>", n + 1))?;
+
+            let (names, position): (Vec<_>, Vec<_>) = inner
+                .split(',')
+                .map(|part| {
+                    let mut kv = part.splitn(2, '=').map(str::trim);
+                    match (kv.next(), kv.next().map(str::parse::<isize>)) {
+                        (Some(name), Some(Ok(value))) => Ok((name.to_string(), value)),
+                        _ => Err(format!(
+                            "line {}: invalid coordinate {}",
+                            n + 1,
+                            part.trim()
+                        )),
+                    }
+                })
+                .collect::<Result<Vec<_>, _>>()?
+                .into_iter()
+                .unzip();
+
+            match &axes {
+                Some(axes) if *axes != names => {
+                    return Err(format!("line {}: expected axes {}", n + 1, axes.join(", ")).into())
+                }
+                Some(_) => {}
+                None => axes = Some(names),
+            }
+            bodies.push(Body::new(position));
+        }
+
+        Self::with_axes(axes.unwrap_or_default(), bodies, rules)
+    }
+
+    pub fn axes(&self) -> &[String] {
+        &self.axes
+    }
+
+    pub fn bodies(&self) -> &[Body] {
+        &self.bodies
+    }
+
+    /// Number of steps simulated so far.
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    pub fn total_energy(&self) -> usize {
+        self.bodies.iter().map(Body::total_energy).sum()
+    }
+
+    /// Total energy after every step, starting with the energy before the first one.
+    pub fn energy_history(&self) -> &[usize] {
+        &self.energy
+    }
+
+    fn axis_state(&self, axis: usize) -> Vec<(isize, isize)> {
+        self.bodies
+            .iter()
+            .map(|b| (b.position[axis], b.velocity[axis]))
+            .collect()
+    }
+
+    pub fn step(&mut self) {
+        for axis in 0..self.axes.len() {
+            let mut state = self.axis_state(axis);
+            self.rules.step_axis(&mut state);
+            for (body, (pos, vel)) in self.bodies.iter_mut().zip(state) {
+                body.position[axis] = pos;
+                body.velocity[axis] = vel;
+            }
+        }
+
+        self.steps += 1;
+        self.energy.push(self.total_energy());
+    }
+
+    pub fn run(&mut self, steps: usize) {
+        for _ in 0..steps {
+            self.step();
+        }
+    }
+
+    /// Find when the system first repeats a state, counted from the current step.
+    /// Gives up if an axis doesn't repeat a state within `max_steps` steps, which happens with
+    /// rules where bodies drift apart forever.
+    pub fn find_cycle(&self, max_steps: usize) -> Result<Cycle, Box<dyn Error>> {
+        let mut cycle = Cycle {
+            start: 0,
+            period: 1,
+        };
+        for (n, axis) in self.axes.iter().enumerate() {
+            let axis_cycle = find_axis_cycle(&self.rules, &self.axis_state(n), max_steps)
+                .ok_or_else(|| {
+                    format!("axis {} doesn't repeat within {} steps", axis, max_steps)
+                })?;
+            cycle = cycle.combine(axis_cycle);
+        }
+
+        Ok(cycle)
+    }
+
+    /// Simulate the steps and list the position and velocity of every body as CSV.
+    /// The current state is included as the first step.
+    pub fn record_trajectory(&mut self, steps: usize) -> String {
+        let mut csv = String::from("step,body");
+        for axis in &self.axes {
+            write!(csv, ",{}", axis).unwrap();
+        }
+        for axis in &self.axes {
+            write!(csv, ",v{}", axis).unwrap();
+        }
+        csv.push('\n');
+
+        for n in 0..=steps {
+            if n > 0 {
+                self.step();
+            }
+            for (i, body) in self.bodies.iter().enumerate() {
+                write!(csv, "{},{}", self.steps, i).unwrap();
+                for v in body.position.iter().chain(&body.velocity) {
+                    write!(csv, ",{}", v).unwrap();
+                }
+                csv.push('\n');
+            }
+        }
+
+        csv
+    }
+}
+
+fn write_vector(f: &mut fmt::Formatter, axes: &[String], values: &[isize]) -> fmt::Result {
+    write!(f, "<")?;
+    for (n, (axis, v)) in axes.iter().zip(values).enumerate() {
+        if n > 0 {
+            write!(f, ", ")?;
+        }
+        write!(f, "{}={:3}", axis, v)?;
+    }
+    write!(f, ">")
+}
+
+/// Shows the bodies like the puzzle description does.
+impl fmt::Display for System {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for body in &self.bodies {
+            write!(f, "pos=")?;
+            write_vector(f, &self.axes, &body.position)?;
+            write!(f, ", vel=")?;
+            write_vector(f, &self.axes, &body.velocity)?;
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MOONS: &str = "
+        <x=-1, y=0, z=2>
+        <x=2, y=-10, z=-7>
+        <x=4, y=-8, z=8>
+        <x=3, y=5, z=-1>
+    ";
+
+    #[test]
+    fn test_step() -> Result<(), Box<dyn Error>> {
+        let mut system = System::parse(MOONS, Rules::default())?;
+        system.step();
+        assert_eq!(
+            system.to_string().lines().next(),
+            Some("pos=<x=  2, y= -1, z=  1>, vel=<x=  3, y= -1, z= -1>")
+        );
+
+        system.run(9);
+        assert_eq!(system.steps(), 10);
+        assert_eq!(system.total_energy(), 179);
+        assert_eq!(system.energy_history().len(), 11);
+        assert_eq!(system.energy_history()[0], 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse() -> Result<(), Box<dyn Error>> {
+        let system = System::parse("<a=1, b=2>\n<a=3, b=-4>", Rules::default())?;
+        assert_eq!(system.axes(), &["a", "b"]);
+        assert_eq!(system.bodies()[1].position, vec![3, -4]);
+
+        assert!(System::parse("<a=1, b=2>\n<a=3>", Rules::default()).is_err());
+        assert!(System::parse("<a=1, b=x>", Rules::default()).is_err());
+        assert!(System::parse("a=1", Rules::default()).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_find_cycle() -> Result<(), Box<dyn Error>> {
+        let system = System::parse(MOONS, Rules::default())?;
+        assert_eq!(
+            system.find_cycle(10_000)?,
+            Cycle {
+                start: 0,
+                period: 2772
+            }
+        );
+        assert!(system.find_cycle(20).is_err());
+
+        // repelling bodies never come back
+        let rules = Rules {
+            gravity: Gravity::Scaled(-1),
+            speed_limit: None,
+        };
+        let system = System::parse(MOONS, rules)?;
+        assert!(system.find_cycle(100_000).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cycle_with_speed_limit() -> Result<(), Box<dyn Error>> {
+        let rules = Rules {
+            gravity: Gravity::Unit,
+            speed_limit: Some(1),
+        };
+        let bodies = vec![Body::new(vec![0]), Body::new(vec![5])];
+        let system = System::new(bodies.clone(), rules)?;
+        let cycle = system.find_cycle(1000)?;
+        assert!(cycle.start > 0);
+
+        // the state at the start of the cycle comes back after one period
+        let mut a = system.clone();
+        a.run(cycle.start);
+        let mut b = a.clone();
+        b.run(cycle.period);
+        assert_eq!(a.bodies(), b.bodies());
+
+        let rules = Rules {
+            speed_limit: Some(-1),
+            ..rules
+        };
+        assert!(System::new(bodies, rules).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_gravity() -> Result<(), Box<dyn Error>> {
+        assert_eq!("unit".parse::<Gravity>()?, Gravity::Unit);
+        assert_eq!("scaled:-2".parse::<Gravity>()?, Gravity::Scaled(-2));
+        assert_eq!("spring:3".parse::<Gravity>()?.pull(-7), -2);
+        assert!("spring:0".parse::<Gravity>().is_err());
+        assert!("scaled".parse::<Gravity>().is_err());
+
+        let bodies = vec![Body::new(vec![0]), Body::new(vec![5])];
+        for divisor in [0, -2] {
+            let rules = Rules {
+                gravity: Gravity::Spring(divisor),
+                speed_limit: None,
+            };
+            assert!(System::new(bodies.clone(), rules).is_err());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_record_trajectory() -> Result<(), Box<dyn Error>> {
+        let bodies = vec![
+            Body::new(vec![0, 0, 0, 0, 0]),
+            Body::new(vec![2, 0, 0, 0, 1]),
+        ];
+        let mut system = System::new(bodies, Rules::default())?;
+        let csv = system.record_trajectory(1);
+        assert_eq!(
+            csv,
+            "step,body,x,y,z,w,d5,vx,vy,vz,vw,vd5
+0,0,0,0,0,0,0,0,0,0,0,0
+0,1,2,0,0,0,1,0,0,0,0,0
+1,0,1,0,0,0,1,1,0,0,0,1
+1,1,1,0,0,0,0,-1,0,0,0,-1
+"
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,197 @@
+//! Reading the block letters some puzzles draw with pixels.
+//!
+//! Puzzles reading letters take the [`RAW`] parameter to return the block art instead.
+
+use crate::input::{Input, Param};
+use crate::lib::grid::{Grid, Point};
+use std::error::Error;
+
+pub const RAW: Param = Param::new(
+    "raw",
+    "false",
+    "return the letters as block art instead of reading them",
+);
+
+/// Whether the block art was requested instead of the letters.
+pub fn wants_raw(i: &Input) -> Result<bool, Box<dyn Error>> {
+    RAW.get(i)
+}
+
+/// Letters of the same height, drawn with `#` for lit pixels.
+/// Glyphs don't include the empty columns around them.
+pub struct Font {
+    pub height: usize,
+    glyphs: &'static [(char, &'static [&'static str])],
+}
+
+/// The font used for most letters drawn by puzzles, letters are four pixels wide.
+pub const FONT_4X6: Font = Font {
+    height: 6,
+    glyphs: &[
+        ('A', &[".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+        ('B', &["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+        ('C', &[".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+        ('E', &["####", "#...", "###.", "#...", "#...", "####"]),
+        ('F', &["####", "#...", "###.", "#...", "#...", "#..."]),
+        ('G', &[".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+        ('H', &["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+        ('I', &["###", ".#.", ".#.", ".#.", ".#.", "###"]),
+        ('J', &["..##", "...#", "...#", "...#", "#..#", ".##."]),
+        ('K', &["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+        ('L', &["#...", "#...", "#...", "#...", "#...", "####"]),
+        ('O', &[".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+        ('P', &["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+        ('R', &["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+        ('S', &[".###", "#...", "#...", ".##.", "...#", "###."]),
+        ('U', &["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+        ('Y', &["#...#", "#...#", ".#.#.", "..#..", "..#..", "..#.."]),
+        ('Z', &["####", "...#", "..#.", ".#..", "#...", "####"]),
+    ],
+};
+
+/// Larger font with letters six pixels wide.
+#[rustfmt::skip]
+pub const FONT_6X10: Font = Font {
+    height: 10,
+    glyphs: &[
+        ('A', &["..##..", ".#..#.", "#....#", "#....#", "#....#", "######", "#....#", "#....#", "#....#", "#....#"]),
+        ('B', &["#####.", "#....#", "#....#", "#....#", "#####.", "#....#", "#....#", "#....#", "#....#", "#####."]),
+        ('C', &[".####.", "#....#", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "#....#", ".####."]),
+        ('E', &["######", "#.....", "#.....", "#.....", "#####.", "#.....", "#.....", "#.....", "#.....", "######"]),
+        ('F', &["######", "#.....", "#.....", "#.....", "#####.", "#.....", "#.....", "#.....", "#.....", "#....."]),
+        ('G', &[".####.", "#....#", "#.....", "#.....", "#.....", "#..###", "#....#", "#....#", "#...##", ".###.#"]),
+        ('H', &["#....#", "#....#", "#....#", "#....#", "######", "#....#", "#....#", "#....#", "#....#", "#....#"]),
+        ('J', &["...###", "....#.", "....#.", "....#.", "....#.", "....#.", "....#.", "#...#.", "#...#.", ".###.."]),
+        ('K', &["#....#", "#...#.", "#..#..", "#.#...", "##....", "##....", "#.#...", "#..#..", "#...#.", "#....#"]),
+        ('L', &["#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "######"]),
+        ('N', &["#....#", "##...#", "##...#", "#.#..#", "#.#..#", "#..#.#", "#..#.#", "#...##", "#...##", "#....#"]),
+        ('P', &["#####.", "#....#", "#....#", "#....#", "#####.", "#.....", "#.....", "#.....", "#.....", "#....."]),
+        ('R', &["#####.", "#....#", "#....#", "#....#", "#####.", "#..#..", "#...#.", "#...#.", "#....#", "#....#"]),
+        ('X', &["#....#", "#....#", ".#..#.", ".#..#.", "..##..", "..##..", ".#..#.", ".#..#.", "#....#", "#....#"]),
+        ('Z', &["######", ".....#", ".....#", "....#.", "...#..", "..#...", ".#....", "#.....", "#.....", "######"]),
+    ],
+};
+
+pub const FONTS: &[&Font] = &[&FONT_4X6, &FONT_6X10];
+
+impl Font {
+    fn find(&self, rows: &[String]) -> Option<char> {
+        self.glyphs
+            .iter()
+            .find(|(_, glyph)| glyph.iter().zip(rows).all(|(a, b)| a == b))
+            .map(|&(c, _)| c)
+    }
+}
+
+/// Read the letters drawn by the lit pixels, letters are separated by empty columns.
+pub fn recognize(pixels: &Grid<bool>) -> Result<String, Box<dyn Error>> {
+    let lit = |x: usize, y: usize| pixels[Point::new(x as isize, y as isize)];
+    let lit_rows: Vec<_> = (0..pixels.height())
+        .filter(|&y| (0..pixels.width()).any(|x| lit(x, y)))
+        .collect();
+    let (top, bottom) = match (lit_rows.first(), lit_rows.last()) {
+        (Some(&top), Some(&bottom)) => (top, bottom),
+        _ => return Ok(String::new()),
+    };
+
+    let height = bottom - top + 1;
+    let font = FONTS
+        .iter()
+        .find(|f| f.height == height)
+        .ok_or_else(|| format!("no font with letters {} pixels high", height))?;
+
+    let is_empty_column = |x: usize| (top..=bottom).all(|y| !lit(x, y));
+    let mut letters = String::new();
+    let mut x = 0;
+    while x < pixels.width() {
+        if is_empty_column(x) {
+            x += 1;
+            continue;
+        }
+
+        let start = x;
+        while x < pixels.width() && !is_empty_column(x) {
+            x += 1;
+        }
+        let rows: Vec<String> = (top..=bottom)
+            .map(|y| {
+                (start..x)
+                    .map(|x| if lit(x, y) { '#' } else { '.' })
+                    .collect()
+            })
+            .collect();
+
+        let letter = font
+            .find(&rows)
+            .ok_or_else(|| format!("unknown letter:\n{}", rows.join("\n")))?;
+        letters.push(letter);
+    }
+
+    Ok(letters)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::Input;
+
+    fn pixels(art: &str) -> Result<Grid<bool>, Box<dyn Error>> {
+        Grid::from_input(&Input::new(art), |c| Some(c == '#'))
+    }
+
+    #[test]
+    fn test_recognize() -> Result<(), Box<dyn Error>> {
+        let art = "
+            ..........................
+            .#..#.###...##..####.#...#
+            .#..#.#..#.#..#.#....#...#
+            .####.###..#....###...#.#.
+            .#..#.#..#.#....#......#..
+            .#..#.#..#.#..#.#......#..
+            .#..#.###...##..####...#..
+            ..........................
+        ";
+        assert_eq!(recognize(&pixels(art)?)?, "HBCEY");
+        assert_eq!(recognize(&pixels("....\n....")?)?, "");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_unknown_letter() -> Result<(), Box<dyn Error>> {
+        let art = "
+            #..#
+            #..#
+            ####
+            #..#
+            #..#
+            ##.#
+        ";
+        assert_eq!(
+            recognize(&pixels(art)?).unwrap_err().to_string(),
+            "unknown letter:\n#..#\n#..#\n####\n#..#\n#..#\n##.#"
+        );
+        assert!(recognize(&pixels("#\n#")?).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_recognize_large() -> Result<(), Box<dyn Error>> {
+        let art = "
+            #....#..#####...######
+            #....#..#....#.......#
+            .#..#...#....#.......#
+            .#..#...#....#......#.
+            ..##....#####......#..
+            ..##....#..#......#...
+            .#..#...#...#....#....
+            .#..#...#...#...#.....
+            #....#..#....#..#.....
+            #....#..#....#..######
+        ";
+        assert_eq!(recognize(&pixels(art)?)?, "XRZ");
+
+        Ok(())
+    }
+}
@@ -0,0 +1,388 @@
+//! Rendering grids as (coloured) text, either once or as an animation in the terminal.
+
+use crate::lib::grid::{Grid, Point, Rect, SparseGrid};
+use colored::{Color, ColoredString, Colorize};
+use std::io;
+use std::thread;
+use std::time::Duration;
+
+/// Appearance of a single cell.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Style {
+    pub ch: char,
+    pub fg: Option<Color>,
+    pub bg: Option<Color>,
+    pub bold: bool,
+    pub dimmed: bool,
+}
+
+impl Style {
+    pub const fn plain(ch: char) -> Self {
+        Self {
+            ch,
+            fg: None,
+            bg: None,
+            bold: false,
+            dimmed: false,
+        }
+    }
+
+    pub const fn fg(mut self, color: Color) -> Self {
+        self.fg = Some(color);
+        self
+    }
+
+    pub const fn bg(mut self, color: Color) -> Self {
+        self.bg = Some(color);
+        self
+    }
+
+    pub const fn bold(mut self) -> Self {
+        self.bold = true;
+        self
+    }
+
+    pub const fn dimmed(mut self) -> Self {
+        self.dimmed = true;
+        self
+    }
+
+    pub fn colored(&self) -> ColoredString {
+        let mut s: ColoredString = self.ch.to_string().as_str().into();
+        if let Some(fg) = self.fg {
+            s = s.color(fg);
+        }
+        if let Some(bg) = self.bg {
+            s = s.on_color(bg);
+        }
+        if self.bold {
+            s = s.bold();
+        }
+        if self.dimmed {
+            s = s.dimmed();
+        }
+
+        s
+    }
+}
+
+/// Single picture of a grid with an optional caption shown below it.
+/// The top left cell of the frame is at the origin, regardless of the bounds it was created from.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Frame {
+    cells: Grid<Style>,
+    pub caption: String,
+}
+
+impl Frame {
+    /// Create a frame covering the bounds by styling every point.
+    pub fn from_fn(bounds: Option<Rect>, style: impl Fn(Point) -> Style) -> Self {
+        let mut cells = match bounds {
+            Some(bounds) => Grid::new(bounds.width(), bounds.height(), Style::plain(' ')),
+            None => Grid::new(0, 0, Style::plain(' ')),
+        };
+        if let Some(bounds) = bounds {
+            for p in cells.points().collect::<Vec<_>>() {
+                cells[p] = style(bounds.min + p);
+            }
+        }
+
+        Self {
+            cells,
+            caption: String::new(),
+        }
+    }
+
+    pub fn from_grid<T>(grid: &Grid<T>, style: impl Fn(&T) -> Style) -> Self {
+        Self::from_fn(Some(grid.bounds()), |p| style(&grid[p]))
+    }
+
+    /// Create a frame covering the bounds of the grid.
+    /// `style` receives `None` for points without a value.
+    pub fn from_sparse<T>(grid: &SparseGrid<T>, style: impl Fn(Option<&T>) -> Style) -> Self {
+        Self::from_fn(grid.bounds(), |p| style(grid.get(p)))
+    }
+
+    pub fn with_caption(mut self, caption: impl Into<String>) -> Self {
+        self.caption = caption.into();
+        self
+    }
+
+    pub fn width(&self) -> usize {
+        self.cells.width()
+    }
+
+    pub fn height(&self) -> usize {
+        self.cells.height()
+    }
+
+    pub fn get(&self, p: Point) -> Option<&Style> {
+        self.cells.get(p)
+    }
+
+    /// Change the style of a single cell, points outside of the frame are ignored.
+    /// Frames have their own coordinates with the origin at the top left cell.
+    pub fn set(&mut self, p: Point, style: Style) {
+        if let Some(cell) = self.cells.get_mut(p) {
+            *cell = style;
+        }
+    }
+
+    /// Render every row (and the caption) as a line.
+    pub fn lines(&self, color: bool) -> Vec<String> {
+        let mut lines: Vec<String> = (0..self.height())
+            .map(|y| {
+                self.cells
+                    .row(y)
+                    .iter()
+                    .map(|style| {
+                        if color {
+                            style.colored().to_string()
+                        } else {
+                            style.ch.to_string()
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+
+        if !self.caption.is_empty() {
+            lines.extend(self.caption.lines().map(String::from));
+        }
+
+        lines
+    }
+
+    /// Render the frame as plain text without any styling.
+    pub fn plain(&self) -> String {
+        self.lines(false)
+            .iter()
+            .map(|l| format!("{}\n", l))
+            .collect()
+    }
+
+    /// Render the frame as text with ANSI colours.
+    pub fn colored(&self) -> String {
+        self.lines(true)
+            .iter()
+            .map(|l| format!("{}\n", l))
+            .collect()
+    }
+}
+
+/// Draws frames to a terminal, only redrawing the lines which changed since the last frame.
+pub struct TerminalRenderer<W: io::Write> {
+    out: W,
+    color: bool,
+    lines: Option<Vec<String>>,
+}
+
+impl TerminalRenderer<io::Stdout> {
+    pub fn stdout() -> Self {
+        Self::new(io::stdout(), true)
+    }
+}
+
+impl<W: io::Write> TerminalRenderer<W> {
+    pub fn new(out: W, color: bool) -> Self {
+        Self {
+            out,
+            color,
+            lines: None,
+        }
+    }
+
+    pub fn draw(&mut self, frame: &Frame) -> io::Result<()> {
+        let lines = frame.lines(self.color);
+        let previous = match self.lines.take() {
+            Some(previous) => previous,
+            None => {
+                // clear screen and hide cursor
+                write!(self.out, "\x1B[2J\x1B[?25l")?;
+                Vec::new()
+            }
+        };
+
+        for (y, line) in lines.iter().enumerate() {
+            if previous.get(y) != Some(line) {
+                write!(self.out, "\x1B[{};1H{}\x1B[K", y + 1, line)?;
+            }
+        }
+        for y in lines.len()..previous.len() {
+            write!(self.out, "\x1B[{};1H\x1B[K", y + 1)?;
+        }
+
+        self.lines = Some(lines);
+        self.out.flush()
+    }
+
+    /// Move the cursor below the last frame and show it again.
+    pub fn finish(&mut self) -> io::Result<()> {
+        if let Some(lines) = self.lines.take() {
+            write!(self.out, "\x1B[{};1H\x1B[?25h", lines.len() + 1)?;
+        }
+
+        self.out.flush()
+    }
+}
+
+/// Shows the cursor again, even if drawing stopped because of an error.
+impl<W: io::Write> Drop for TerminalRenderer<W> {
+    fn drop(&mut self) {
+        let _ = self.finish();
+    }
+}
+
+/// Sequence of frames which can be replayed later.
+#[derive(Clone, Debug, Default)]
+pub struct Recording {
+    frames: Vec<Frame>,
+}
+
+impl Recording {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a frame unless it's the same as the previous one.
+    pub fn push(&mut self, frame: Frame) {
+        if self.frames.last() != Some(&frame) {
+            self.frames.push(frame);
+        }
+    }
+
+    pub fn frames(&self) -> &[Frame] {
+        &self.frames
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Draw all frames with the given number of frames per second.
+    pub fn replay<W: io::Write>(
+        &self,
+        renderer: &mut TerminalRenderer<W>,
+        fps: f64,
+    ) -> io::Result<()> {
+        let delay = if fps > 0.0 {
+            Duration::try_from_secs_f64(1.0 / fps)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?
+        } else {
+            Duration::from_secs(0)
+        };
+
+        for (i, frame) in self.frames.iter().enumerate() {
+            if i > 0 {
+                thread::sleep(delay);
+            }
+            renderer.draw(frame)?;
+        }
+
+        renderer.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::Input;
+    use std::error::Error;
+
+    fn wall_style(wall: &bool) -> Style {
+        if *wall {
+            Style::plain('#').fg(Color::Green)
+        } else {
+            Style::plain('.')
+        }
+    }
+
+    #[test]
+    fn test_frame_from_grid() -> Result<(), Box<dyn Error>> {
+        let grid = Grid::from_input(&Input::new("#..\n.#."), |c| Some(c == '#'))?;
+        let frame = Frame::from_grid(&grid, wall_style).with_caption("Score: 3");
+
+        assert_eq!((frame.width(), frame.height()), (3, 2));
+        assert_eq!(frame.plain(), "#..\n.#.\nScore: 3\n");
+        assert_eq!(frame.get(Point::ZERO).unwrap().fg, Some(Color::Green));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_frame_from_sparse() {
+        let mut grid = SparseGrid::new();
+        assert_eq!(Frame::from_sparse(&grid, |_| Style::plain('#')).plain(), "");
+
+        grid.insert(Point::new(-1, -1), true);
+        grid.insert(Point::new(1, 0), false);
+        let mut frame = Frame::from_sparse(&grid, |wall| match wall {
+            Some(wall) => wall_style(wall),
+            None => Style::plain(' '),
+        });
+        assert_eq!(frame.plain(), "#  \n  .\n");
+
+        frame.set(Point::new(1, 0), Style::plain('@'));
+        frame.set(Point::new(5, 5), Style::plain('@'));
+        assert_eq!(frame.plain(), "#@ \n  .\n");
+    }
+
+    #[test]
+    fn test_terminal_renderer_redraws_changes() -> io::Result<()> {
+        let mut grid = Grid::new(2, 3, false);
+        let mut renderer = TerminalRenderer::new(Vec::new(), false);
+
+        renderer.draw(&Frame::from_grid(&grid, wall_style))?;
+        let out = String::from_utf8(renderer.out.split_off(0)).unwrap();
+        assert_eq!(
+            out,
+            "\x1B[2J\x1B[?25l\x1B[1;1H..\x1B[K\x1B[2;1H..\x1B[K\x1B[3;1H..\x1B[K"
+        );
+
+        grid[Point::new(1, 1)] = true;
+        renderer.draw(&Frame::from_grid(&grid, wall_style))?;
+        let out = String::from_utf8(renderer.out.split_off(0)).unwrap();
+        assert_eq!(out, "\x1B[2;1H.#\x1B[K");
+
+        renderer.finish()?;
+        let out = String::from_utf8(renderer.out.split_off(0)).unwrap();
+        assert_eq!(out, "\x1B[4;1H\x1B[?25h");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_terminal_renderer_finishes_when_dropped() -> io::Result<()> {
+        let mut out = Vec::new();
+        let frame = Frame::from_grid(&Grid::new(1, 2, false), wall_style);
+        TerminalRenderer::new(&mut out, false).draw(&frame)?;
+
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.ends_with("\x1B[3;1H\x1B[?25h"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_recording() -> io::Result<()> {
+        let mut grid = Grid::new(1, 1, false);
+        let mut rec = Recording::new();
+        rec.push(Frame::from_grid(&grid, wall_style));
+        rec.push(Frame::from_grid(&grid, wall_style));
+        grid[Point::ZERO] = true;
+        rec.push(Frame::from_grid(&grid, wall_style));
+        assert_eq!(rec.len(), 2);
+
+        let mut renderer = TerminalRenderer::new(Vec::new(), false);
+        rec.replay(&mut renderer, 1000.0)?;
+        let out = String::from_utf8(renderer.out.split_off(0)).unwrap();
+        assert!(out.contains("\x1B[1;1H.\x1B[K"));
+        assert!(out.ends_with("\x1B[1;1H#\x1B[K\x1B[2;1H\x1B[?25h"));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,336 @@
+//! Generic graph searches over a neighbour function.
+//!
+//! Nodes can be anything that's hashable, the graph is only ever explored through the closure
+//! returning the neighbours of a node. All searches accept multiple start nodes and stop as soon
+//! as a node matching the goal predicate is reached (pass `|_| false` to explore everything).
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use std::hash::Hash;
+use std::ops::Add;
+
+/// Outcome of a search.
+#[derive(Clone, Debug)]
+pub struct Search<N, C> {
+    /// Distance of every reached node from the closest start.
+    pub distances: HashMap<N, C>,
+    /// Node through which each node was reached. Start nodes have no predecessor.
+    pub predecessors: HashMap<N, N>,
+    /// Nodes in the order they were expanded.
+    pub visited: Vec<N>,
+    /// First node matching the goal predicate.
+    pub goal: Option<N>,
+}
+
+impl<N: Clone + Eq + Hash, C: Copy> Search<N, C> {
+    fn new() -> Self {
+        Self {
+            distances: HashMap::new(),
+            predecessors: HashMap::new(),
+            visited: Vec::new(),
+            goal: None,
+        }
+    }
+
+    pub fn distance(&self, node: &N) -> Option<C> {
+        self.distances.get(node).copied()
+    }
+
+    /// Reconstruct the path from a start node to the given node (both inclusive).
+    /// Returns `None` if the node wasn't reached.
+    pub fn path_to(&self, node: &N) -> Option<Vec<N>> {
+        if !self.distances.contains_key(node) {
+            return None;
+        }
+
+        let mut path = vec![node.clone()];
+        let mut current = node;
+        while let Some(prev) = self.predecessors.get(current) {
+            path.push(prev.clone());
+            current = prev;
+        }
+        path.reverse();
+
+        Some(path)
+    }
+
+    pub fn goal_distance(&self) -> Option<C> {
+        self.goal.as_ref().and_then(|goal| self.distance(goal))
+    }
+
+    pub fn goal_path(&self) -> Option<Vec<N>> {
+        self.goal.as_ref().and_then(|goal| self.path_to(goal))
+    }
+}
+
+/// Breadth-first search where every edge has a length of 1.
+pub fn bfs<N, I>(
+    starts: impl IntoIterator<Item = N>,
+    mut neighbours: impl FnMut(&N) -> I,
+    mut goal: impl FnMut(&N) -> bool,
+) -> Search<N, usize>
+where
+    N: Clone + Eq + Hash,
+    I: IntoIterator<Item = N>,
+{
+    let mut search = Search::new();
+    let mut queue = VecDeque::new();
+    for start in starts {
+        if search.distances.insert(start.clone(), 0).is_none() {
+            queue.push_back(start);
+        }
+    }
+
+    while let Some(node) = queue.pop_front() {
+        search.visited.push(node.clone());
+        if goal(&node) {
+            search.goal = Some(node);
+            break;
+        }
+
+        let dist = search.distances[&node] + 1;
+        for next in neighbours(&node) {
+            if search.distances.contains_key(&next) {
+                continue;
+            }
+
+            search.distances.insert(next.clone(), dist);
+            search.predecessors.insert(next.clone(), node.clone());
+            queue.push_back(next);
+        }
+    }
+
+    search
+}
+
+/// Depth-first search.
+/// The distances are the depths in the search tree which aren't necessarily the shortest ones.
+pub fn dfs<N, I>(
+    starts: impl IntoIterator<Item = N>,
+    mut neighbours: impl FnMut(&N) -> I,
+    mut goal: impl FnMut(&N) -> bool,
+) -> Search<N, usize>
+where
+    N: Clone + Eq + Hash,
+    I: IntoIterator<Item = N>,
+{
+    let mut search = Search::new();
+    let mut stack: Vec<(N, Option<N>, usize)> =
+        starts.into_iter().map(|start| (start, None, 0)).collect();
+    stack.reverse();
+
+    while let Some((node, prev, dist)) = stack.pop() {
+        if search.distances.contains_key(&node) {
+            continue;
+        }
+
+        search.distances.insert(node.clone(), dist);
+        if let Some(prev) = prev {
+            search.predecessors.insert(node.clone(), prev);
+        }
+        search.visited.push(node.clone());
+        if goal(&node) {
+            search.goal = Some(node);
+            break;
+        }
+
+        // push in reverse so the first neighbour is explored first
+        let next: Vec<_> = neighbours(&node).into_iter().collect();
+        for next in next.into_iter().rev() {
+            if !search.distances.contains_key(&next) {
+                stack.push((next, Some(node.clone()), dist + 1));
+            }
+        }
+    }
+
+    search
+}
+
+/// Dijkstra's algorithm for weighted edges.
+/// The neighbour function returns each neighbour together with the cost to get there.
+pub fn dijkstra<N, C, I>(
+    starts: impl IntoIterator<Item = N>,
+    neighbours: impl FnMut(&N) -> I,
+    goal: impl FnMut(&N) -> bool,
+) -> Search<N, C>
+where
+    N: Clone + Eq + Hash,
+    C: Copy + Ord + Default + Add<Output = C>,
+    I: IntoIterator<Item = (N, C)>,
+{
+    astar(starts, neighbours, |_| C::default(), goal)
+}
+
+/// A* search for weighted edges.
+/// The heuristic must never overestimate the remaining cost to the goal.
+/// If it isn't consistent, nodes are expanded again when a cheaper path to them turns up.
+/// Only expanded nodes are part of the result.
+pub fn astar<N, C, I>(
+    starts: impl IntoIterator<Item = N>,
+    mut neighbours: impl FnMut(&N) -> I,
+    mut heuristic: impl FnMut(&N) -> C,
+    mut goal: impl FnMut(&N) -> bool,
+) -> Search<N, C>
+where
+    N: Clone + Eq + Hash,
+    C: Copy + Ord + Default + Add<Output = C>,
+    I: IntoIterator<Item = (N, C)>,
+{
+    let mut search = Search::new();
+    let mut best: HashMap<N, C> = HashMap::new();
+
+    // the heap only holds indices into `entries` so nodes don't have to be ordered.
+    // the index also breaks ties in favour of nodes that were found first.
+    let mut entries: Vec<(N, C, Option<N>)> = Vec::new();
+    let mut heap = BinaryHeap::new();
+
+    for start in starts {
+        if best.insert(start.clone(), C::default()).is_none() {
+            heap.push(Reverse((heuristic(&start), entries.len())));
+            entries.push((start, C::default(), None));
+        }
+    }
+
+    while let Some(Reverse((_, index))) = heap.pop() {
+        let (node, cost, prev) = entries[index].clone();
+        // a cheaper path to the node was found after this entry was added
+        if best[&node] < cost {
+            continue;
+        }
+
+        if search.distances.insert(node.clone(), cost).is_none() {
+            search.visited.push(node.clone());
+        }
+        if let Some(prev) = prev {
+            search.predecessors.insert(node.clone(), prev);
+        }
+        if goal(&node) {
+            search.goal = Some(node);
+            break;
+        }
+
+        for (next, step) in neighbours(&node) {
+            let next_cost = cost + step;
+            if best.get(&next).is_some_and(|&c| c <= next_cost) {
+                continue;
+            }
+
+            best.insert(next.clone(), next_cost);
+            heap.push(Reverse((next_cost + heuristic(&next), entries.len())));
+            entries.push((next, next_cost, Some(node.clone())));
+        }
+    }
+
+    search
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a 5x5 grid with a wall in the middle column, open only at the bottom
+    fn open(x: i32, y: i32) -> bool {
+        (0..5).contains(&x) && (0..5).contains(&y) && !(x == 2 && y < 4)
+    }
+
+    fn grid_neighbours(&(x, y): &(i32, i32)) -> Vec<(i32, i32)> {
+        vec![(x, y - 1), (x + 1, y), (x, y + 1), (x - 1, y)]
+            .into_iter()
+            .filter(|&(x, y)| open(x, y))
+            .collect()
+    }
+
+    #[test]
+    fn test_bfs() {
+        let search = bfs(vec![(0, 0)], grid_neighbours, |_| false);
+        assert_eq!(search.distance(&(4, 0)), Some(12));
+        assert_eq!(search.distance(&(2, 0)), None);
+        assert_eq!(search.distances.len(), 21);
+        assert_eq!(search.goal, None);
+
+        let path = search.path_to(&(4, 0)).unwrap();
+        assert_eq!(path.len(), 13);
+        assert_eq!(path[0], (0, 0));
+        assert!(path.contains(&(2, 4)));
+    }
+
+    #[test]
+    fn test_bfs_goal_and_multiple_starts() {
+        let search = bfs(vec![(0, 0), (4, 4)], grid_neighbours, |&p| p == (4, 0));
+        assert_eq!(search.goal, Some((4, 0)));
+        assert_eq!(search.goal_distance(), Some(4));
+        assert_eq!(search.goal_path().unwrap()[0], (4, 4));
+        assert!(search.visited.len() < 21);
+    }
+
+    #[test]
+    fn test_dfs() {
+        let search = dfs(vec![(0, 0)], grid_neighbours, |_| false);
+        assert_eq!(search.visited.len(), 21);
+        assert_eq!(search.visited[..3], [(0, 0), (1, 0), (1, 1)]);
+
+        let search = dfs(vec![(0, 0)], grid_neighbours, |&p| p == (3, 4));
+        let path = search.goal_path().unwrap();
+        assert_eq!(path.last(), Some(&(3, 4)));
+        assert!(path
+            .windows(2)
+            .all(|w| grid_neighbours(&w[0]).contains(&w[1])));
+    }
+
+    #[test]
+    fn test_dijkstra() {
+        // going right is cheap, going down is expensive
+        let weighted = |p: &(i32, i32)| {
+            grid_neighbours(p)
+                .into_iter()
+                .map(|n| (n, if n.1 != p.1 { 3 } else { 1 }))
+                .collect::<Vec<_>>()
+        };
+
+        let search = dijkstra(vec![(0, 0)], weighted, |_| false);
+        assert_eq!(search.distance(&(1, 0)), Some(1));
+        assert_eq!(search.distance(&(0, 4)), Some(12));
+        assert_eq!(search.distance(&(4, 0)), Some(4 + 24));
+
+        let search = dijkstra(vec![(0, 0)], weighted, |&p| p == (1, 4));
+        assert_eq!(search.goal_distance(), Some(13));
+        assert_eq!(search.goal_path().unwrap().len(), 6);
+    }
+
+    #[test]
+    fn test_astar() {
+        let goal = (4, 0);
+        let weighted = |p: &(i32, i32)| {
+            grid_neighbours(p)
+                .into_iter()
+                .map(|n| (n, 1))
+                .collect::<Vec<_>>()
+        };
+        let manhattan = |&(x, y): &(i32, i32)| (goal.0 - x).abs() + (goal.1 - y).abs();
+
+        let search = astar(vec![(0, 0)], weighted, manhattan, |&p| p == goal);
+        assert_eq!(search.goal_distance(), Some(12));
+        assert_eq!(search.goal_path().unwrap().len(), 13);
+
+        let plain = dijkstra(vec![(0, 0)], weighted, |&p| p == goal);
+        assert_eq!(plain.goal_distance(), Some(12));
+        assert!(search.visited.len() <= plain.visited.len());
+    }
+
+    #[test]
+    fn test_astar_inconsistent_heuristic() {
+        // the heuristic of `a` is admissible but makes `b` look better through the direct edge
+        let edges = |n: &char| match n {
+            's' => vec![('a', 1), ('b', 3)],
+            'a' => vec![('b', 1)],
+            'b' => vec![('g', 3)],
+            _ => vec![],
+        };
+        let heuristic = |n: &char| if *n == 'a' { 4 } else { 0 };
+
+        let search = astar(vec!['s'], edges, heuristic, |&n| n == 'g');
+        assert_eq!(search.goal_distance(), Some(5));
+        assert_eq!(search.goal_path(), Some(vec!['s', 'a', 'b', 'g']));
+        assert_eq!(search.visited, vec!['s', 'b', 'a', 'g']);
+    }
+}
@@ -0,0 +1,314 @@
+//! The Space Image Format used by the Mars rover pictures.
+//!
+//! Images are sent as a sequence of digits, one per pixel, split into layers of the same size.
+//! Layers are stacked with the first one in front, transparent pixels show the layers below.
+
+use crate::lib::grid::{Grid, Point};
+use crate::lib::image::{Image, Rgb};
+use std::error::Error;
+use std::fmt;
+
+pub type Pixel = u8;
+
+pub const BLACK: Pixel = 0;
+pub const WHITE: Pixel = 1;
+pub const TRANSPARENT: Pixel = 2;
+
+/// Number of times every digit appears in a layer.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct LayerStats {
+    counts: [usize; 10],
+}
+
+impl LayerStats {
+    pub fn count(&self, pixel: Pixel) -> usize {
+        self.counts.get(pixel as usize).copied().unwrap_or(0)
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SpaceImage {
+    width: usize,
+    height: usize,
+    layers: Vec<Grid<Pixel>>,
+}
+
+impl SpaceImage {
+    /// Create an image from its layers, which all need to have the same size.
+    pub fn new(layers: Vec<Grid<Pixel>>) -> Result<Self, Box<dyn Error>> {
+        let first = layers.first().ok_or("image has no layers")?;
+        let (width, height) = (first.width(), first.height());
+        if let Some(n) = layers
+            .iter()
+            .position(|l| (l.width(), l.height()) != (width, height))
+        {
+            return Err(format!("layer {} isn't {}x{} pixels", n + 1, width, height).into());
+        }
+
+        Ok(Self {
+            width,
+            height,
+            layers,
+        })
+    }
+
+    pub fn parse(text: &str, width: usize, height: usize) -> Result<Self, Box<dyn Error>> {
+        let pixels = text
+            .trim()
+            .chars()
+            .map(|c| c.to_digit(10).map(|d| d as Pixel))
+            .collect::<Option<Vec<_>>>()
+            .ok_or("image contains a pixel which isn't a digit")?;
+
+        let layer_size = width * height;
+        if layer_size == 0 || pixels.is_empty() || pixels.len() % layer_size != 0 {
+            return Err(format!(
+                "{} pixels can't be split into layers of {}x{}",
+                pixels.len(),
+                width,
+                height
+            )
+            .into());
+        }
+
+        let layers = pixels
+            .chunks_exact(layer_size)
+            .map(|layer| Grid::from_cells(width, layer.to_vec()))
+            .collect::<Result<_, _>>()?;
+        Self::new(layers)
+    }
+
+    /// Encode a picture so that the layers stacked on top of each other show it.
+    /// Every pixel is drawn on one of the layers, the layers above are transparent and the ones
+    /// below have the opposite colour.
+    pub fn encode(bitmap: &Grid<bool>, layers: usize) -> Result<Self, Box<dyn Error>> {
+        if layers == 0 {
+            return Err("image needs at least one layer".into());
+        }
+
+        let width = bitmap.width();
+        let layers = (0..layers)
+            .map(|n| {
+                bitmap
+                    .points()
+                    .fold(bitmap.map(|_| TRANSPARENT), |mut layer, p| {
+                        let shown = (p.x as usize + p.y as usize * width) % layers;
+                        let color = if bitmap[p] { WHITE } else { BLACK };
+                        if n == shown {
+                            layer[p] = color;
+                        } else if n > shown {
+                            layer[p] = WHITE - color;
+                        }
+                        layer
+                    })
+            })
+            .collect();
+        Self::new(layers)
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn layers(&self) -> &[Grid<Pixel>] {
+        &self.layers
+    }
+
+    pub fn stats(&self) -> Vec<LayerStats> {
+        self.layers
+            .iter()
+            .map(|layer| {
+                let mut stats = LayerStats::default();
+                for (_, &pixel) in layer.iter() {
+                    if let Some(count) = stats.counts.get_mut(pixel as usize) {
+                        *count += 1;
+                    }
+                }
+                stats
+            })
+            .collect()
+    }
+
+    /// Stack the first `n` layers, pixels which are transparent in all of them stay transparent.
+    pub fn composite_layers(&self, n: usize) -> Grid<Pixel> {
+        let mut result = Grid::new(self.width, self.height, TRANSPARENT);
+        for layer in self.layers.iter().take(n) {
+            for (p, &pixel) in layer.iter() {
+                if result[p] == TRANSPARENT {
+                    result[p] = pixel;
+                }
+            }
+        }
+
+        result
+    }
+
+    pub fn composite(&self) -> Grid<Pixel> {
+        self.composite_layers(self.layers.len())
+    }
+
+    /// The visible white pixels, transparent pixels are treated as black.
+    pub fn bitmap(&self) -> Grid<bool> {
+        self.composite().map(|&p| p == WHITE)
+    }
+
+    /// Draw the visible pixels as squares of `scale` pixels, transparent pixels are gray.
+    pub fn to_image(&self, scale: usize) -> Image {
+        let composite = self.composite();
+        let mut image = Image::new(self.width * scale, self.height * scale, pixel_rgb(BLACK));
+        for y in 0..image.height {
+            for x in 0..image.width {
+                let p = Point::new((x / scale) as isize, (y / scale) as isize);
+                image.set(x, y, pixel_rgb(composite[p]));
+            }
+        }
+
+        image
+    }
+
+    /// Write the visible pixels as plain PBM, where `1` is a black pixel.
+    pub fn to_pbm(&self) -> String {
+        encode_pbm(&self.bitmap())
+    }
+}
+
+/// Serializes the image in the format it's parsed from.
+impl fmt::Display for SpaceImage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for layer in &self.layers {
+            for (_, pixel) in layer.iter() {
+                write!(f, "{}", pixel)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn pixel_rgb(pixel: Pixel) -> Rgb {
+    match pixel {
+        BLACK => [0, 0, 0],
+        WHITE => [255, 255, 255],
+        _ => [128, 128, 128],
+    }
+}
+
+/// Encode white pixels of the bitmap as plain PBM.
+pub fn encode_pbm(bitmap: &Grid<bool>) -> String {
+    let mut out = format!("P1\n{} {}\n", bitmap.width(), bitmap.height());
+    for y in 0..bitmap.height() {
+        let row: Vec<_> = bitmap
+            .row(y)
+            .iter()
+            .map(|&white| if white { "0" } else { "1" })
+            .collect();
+        out.push_str(&row.join(" "));
+        out.push('\n');
+    }
+
+    out
+}
+
+/// Read a plain PBM as a bitmap of white pixels, comments start with `#`.
+pub fn parse_pbm(text: &str) -> Result<Grid<bool>, Box<dyn Error>> {
+    let mut tokens = text
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or(""))
+        .flat_map(str::split_whitespace);
+    if tokens.next() != Some("P1") {
+        return Err("only plain PBM (P1) is supported".into());
+    }
+
+    let mut size = || -> Result<usize, Box<dyn Error>> {
+        let token = tokens.next().ok_or("missing PBM image size")?;
+        token
+            .parse()
+            .map_err(|_| format!("invalid PBM image size: {}", token).into())
+    };
+    let (width, height) = (size()?, size()?);
+
+    // pixels don't need to be separated by whitespace
+    let cells = tokens
+        .flat_map(str::chars)
+        .map(|c| match c {
+            '0' => Ok(true),
+            '1' => Ok(false),
+            _ => Err(format!("invalid PBM pixel: {}", c)),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if cells.len() != width * height {
+        return Err(format!(
+            "expected {} PBM pixels but got {}",
+            width * height,
+            cells.len()
+        )
+        .into());
+    }
+
+    Grid::from_cells(width, cells)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() -> Result<(), Box<dyn Error>> {
+        let image = SpaceImage::parse("123456789012\n", 3, 2)?;
+        assert_eq!(image.layers().len(), 2);
+        assert_eq!(image.layers()[1].row(0), &[7, 8, 9]);
+        assert_eq!(image.to_string(), "123456789012");
+
+        let stats = image.stats();
+        assert_eq!((stats[0].count(0), stats[1].count(0)), (0, 1));
+        assert_eq!(stats[1].count(1), 1);
+
+        assert!(SpaceImage::parse("12345", 3, 2).is_err());
+        assert!(SpaceImage::parse("12345a", 3, 2).is_err());
+        assert!(SpaceImage::parse("", 3, 2).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_composite() -> Result<(), Box<dyn Error>> {
+        let image = SpaceImage::parse("0222112222120000", 2, 2)?;
+        assert_eq!(image.composite().row(0), &[0, 1]);
+        assert_eq!(image.composite().row(1), &[1, 0]);
+        assert_eq!(image.composite_layers(1).row(1), &[2, 2]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_encode() -> Result<(), Box<dyn Error>> {
+        let bitmap = parse_pbm("P1\n# two stripes\n3 2\n010\n1 0 1\n")?;
+        assert_eq!(bitmap.row(0), &[true, false, true]);
+
+        for layers in 1..=4 {
+            let image = SpaceImage::encode(&bitmap, layers)?;
+            assert_eq!(image.layers().len(), layers);
+
+            let decoded = SpaceImage::parse(&image.to_string(), 3, 2)?;
+            assert_eq!(decoded.bitmap(), bitmap);
+            assert_eq!(decoded.to_pbm(), "P1\n3 2\n0 1 0\n1 0 1\n");
+        }
+        assert!(SpaceImage::encode(&bitmap, 0).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_to_image() -> Result<(), Box<dyn Error>> {
+        let image = SpaceImage::parse("2102", 4, 1)?.to_image(2);
+        assert_eq!((image.width, image.height), (8, 2));
+        assert_eq!(image.get(0, 1), Some([128, 128, 128]));
+        assert_eq!(image.get(3, 0), Some([255, 255, 255]));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,765 @@
+//! Springscript programs for the springdroid of day 21.
+//!
+//! Programs can be parsed and validated, compiled from boolean expressions over the sensors
+//! and simulated on a hull locally before sending them to the droid. [`synthesize`] finds a
+//! program on its own by learning from the hulls the droid fell on.
+
+mod synthesis;
+
+pub use synthesis::{parse_failure, synthesize, Oracle, Outcome};
+
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// The droid only accepts this many instructions.
+pub const MAX_INSTRUCTIONS: usize = 15;
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Mode {
+    Walk,
+    Run,
+}
+
+impl Mode {
+    /// Number of ground sensors available (`A`, `B`, ...).
+    pub fn sensors(self) -> u8 {
+        match self {
+            Mode::Walk => 4,
+            Mode::Run => 9,
+        }
+    }
+}
+
+impl fmt::Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Mode::Walk => write!(f, "WALK"),
+            Mode::Run => write!(f, "RUN"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Register {
+    /// Ground sensor looking the given number of tiles ahead minus one (0 is `A`).
+    Sensor(u8),
+    T,
+    J,
+}
+
+impl Register {
+    fn from_char(c: char) -> Option<Self> {
+        match c {
+            'T' => Some(Register::T),
+            'J' => Some(Register::J),
+            'A'..='I' => Some(Register::Sensor(c as u8 - b'A')),
+            _ => None,
+        }
+    }
+
+    fn is_writable(self) -> bool {
+        !matches!(self, Register::Sensor(_))
+    }
+}
+
+impl fmt::Display for Register {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Register::Sensor(s) => write!(f, "{}", (b'A' + s) as char),
+            Register::T => write!(f, "T"),
+            Register::J => write!(f, "J"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Op {
+    And,
+    Or,
+    Not,
+}
+
+/// Instruction `OP X Y` which stores the result in `Y`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Instruction {
+    pub op: Op,
+    pub x: Register,
+    pub y: Register,
+}
+
+impl Instruction {
+    pub const fn new(op: Op, x: Register, y: Register) -> Self {
+        Self { op, x, y }
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let op = match self.op {
+            Op::And => "AND",
+            Op::Or => "OR",
+            Op::Not => "NOT",
+        };
+        write!(f, "{} {} {}", op, self.x, self.y)
+    }
+}
+
+/// Readings of the ground sensors, bit 0 is set if there's ground below `A`.
+pub type Sensors = u16;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Program {
+    pub instructions: Vec<Instruction>,
+    pub mode: Mode,
+}
+
+impl Program {
+    /// Make sure the program is accepted by the droid.
+    pub fn new(instructions: Vec<Instruction>, mode: Mode) -> Result<Self, Box<dyn Error>> {
+        if instructions.len() > MAX_INSTRUCTIONS {
+            return Err(format!(
+                "program has {} instructions but at most {} are allowed",
+                instructions.len(),
+                MAX_INSTRUCTIONS
+            )
+            .into());
+        }
+
+        for (n, instr) in instructions.iter().enumerate() {
+            if let Register::Sensor(s) = instr.x {
+                if s >= mode.sensors() {
+                    return Err(format!(
+                        "instruction {}: register {} isn't available in {} mode",
+                        n + 1,
+                        instr.x,
+                        mode
+                    )
+                    .into());
+                }
+            }
+            if !instr.y.is_writable() {
+                return Err(
+                    format!("instruction {}: can't write to register {}", n + 1, instr.y).into(),
+                );
+            }
+        }
+
+        Ok(Self { instructions, mode })
+    }
+
+    /// Parse one instruction per line, empty lines are ignored.
+    pub fn parse(text: &str, mode: Mode) -> Result<Self, Box<dyn Error>> {
+        let mut instructions = Vec::new();
+        for (n, line) in text.lines().enumerate() {
+            let parts: Vec<_> = line.split_whitespace().collect();
+            if parts.is_empty() {
+                continue;
+            }
+
+            let op = match parts[0] {
+                "AND" => Op::And,
+                "OR" => Op::Or,
+                "NOT" => Op::Not,
+                other => {
+                    return Err(format!("line {}: unknown instruction {}", n + 1, other).into())
+                }
+            };
+            let register = |i: usize| {
+                let mut chars = parts.get(i).map(|p| p.chars()).into_iter().flatten();
+                match (chars.next().and_then(Register::from_char), chars.next()) {
+                    (Some(r), None) => Ok(r),
+                    _ => Err(format!(
+                        "line {}: expected a register as argument {}",
+                        n + 1,
+                        i
+                    )),
+                }
+            };
+            if parts.len() != 3 {
+                return Err(format!("line {}: expected two arguments", n + 1).into());
+            }
+
+            instructions.push(Instruction::new(op, register(1)?, register(2)?));
+        }
+
+        Self::new(instructions, mode)
+    }
+
+    /// Whether the droid jumps with the given sensor readings.
+    pub fn eval(&self, sensors: Sensors) -> bool {
+        let (mut t, mut j) = (false, false);
+        for instr in &self.instructions {
+            let x = match instr.x {
+                Register::Sensor(s) => sensors & (1 << s) != 0,
+                Register::T => t,
+                Register::J => j,
+            };
+            let y = if instr.y == Register::T {
+                &mut t
+            } else {
+                &mut j
+            };
+            *y = match instr.op {
+                Op::And => x && *y,
+                Op::Or => x || *y,
+                Op::Not => !x,
+            };
+        }
+
+        j
+    }
+
+    /// Walk the droid across the hull.
+    /// Returns the position of the hole it fell into if it doesn't make it.
+    pub fn simulate(&self, hull: &Hull) -> Result<(), usize> {
+        let mut pos = 0;
+        while pos < hull.len() {
+            pos += if self.eval(hull.sensors(pos, self.mode)) {
+                4
+            } else {
+                1
+            };
+            if !hull.is_ground(pos) {
+                return Err(pos);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Instructions followed by the command starting the droid, as expected by the droid.
+impl fmt::Display for Program {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for instr in &self.instructions {
+            writeln!(f, "{}", instr)?;
+        }
+        writeln!(f, "{}", self.mode)
+    }
+}
+
+/// Row of tiles the droid walks along, starting at the tile the droid is standing on.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Hull {
+    ground: Vec<bool>,
+}
+
+impl Hull {
+    pub fn len(&self) -> usize {
+        self.ground.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ground.is_empty()
+    }
+
+    /// Everything after the end of the hull is ground.
+    pub fn is_ground(&self, pos: usize) -> bool {
+        self.ground.get(pos).copied().unwrap_or(true)
+    }
+
+    /// Where the droid ends up after walking or jumping, or the hole it fell into.
+    pub fn step(&self, pos: usize, jump: bool) -> Result<usize, usize> {
+        let next = pos + if jump { 4 } else { 1 };
+        if self.is_ground(next) {
+            Ok(next)
+        } else {
+            Err(next)
+        }
+    }
+
+    pub fn sensors(&self, pos: usize, mode: Mode) -> Sensors {
+        (0..mode.sensors()).fold(0, |sensors, s| {
+            if self.is_ground(pos + 1 + s as usize) {
+                sensors | (1 << s)
+            } else {
+                sensors
+            }
+        })
+    }
+}
+
+impl FromStr for Hull {
+    type Err = Box<dyn Error>;
+
+    /// `#` is ground and `.` a hole, the droid (`@`) stands on ground.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let ground = s
+            .trim()
+            .chars()
+            .map(|c| match c {
+                '#' | '@' => Ok(true),
+                '.' => Ok(false),
+                _ => Err(format!("unexpected character {:?} in hull", c)),
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self { ground })
+    }
+}
+
+impl fmt::Display for Hull {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for &ground in &self.ground {
+            write!(f, "{}", if ground { '#' } else { '.' })?;
+        }
+        Ok(())
+    }
+}
+
+/// Boolean expression over the ground sensors.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum Expr {
+    Sensor(u8),
+    Not(Box<Expr>),
+    And(Vec<Expr>),
+    Or(Vec<Expr>),
+}
+
+impl Expr {
+    pub fn eval(&self, sensors: Sensors) -> bool {
+        match self {
+            Expr::Sensor(s) => sensors & (1 << s) != 0,
+            Expr::Not(e) => !e.eval(sensors),
+            Expr::And(es) => es.iter().all(|e| e.eval(sensors)),
+            Expr::Or(es) => es.iter().any(|e| e.eval(sensors)),
+        }
+    }
+
+    /// Highest sensor used by the expression.
+    pub fn max_sensor(&self) -> Option<u8> {
+        match self {
+            Expr::Sensor(s) => Some(*s),
+            Expr::Not(e) => e.max_sensor(),
+            Expr::And(es) | Expr::Or(es) => es.iter().filter_map(Expr::max_sensor).max(),
+        }
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let join = |f: &mut fmt::Formatter, es: &[Expr], sep: &str| {
+            for (i, e) in es.iter().enumerate() {
+                if i > 0 {
+                    write!(f, " {} ", sep)?;
+                }
+                match e {
+                    Expr::And(_) | Expr::Or(_) => write!(f, "({})", e)?,
+                    _ => write!(f, "{}", e)?,
+                }
+            }
+            Ok(())
+        };
+
+        match self {
+            Expr::Sensor(s) => write!(f, "{}", (b'A' + s) as char),
+            Expr::Not(e) => match **e {
+                Expr::And(_) | Expr::Or(_) => write!(f, "!({})", e),
+                _ => write!(f, "!{}", e),
+            },
+            Expr::And(es) => join(f, es, "&"),
+            Expr::Or(es) => join(f, es, "|"),
+        }
+    }
+}
+
+/// Recursive descent parser for expressions like `!(A & B) | C`.
+/// `&` binds stronger than `|`.
+struct ExprParser<'a> {
+    chars: std::iter::Peekable<std::str::CharIndices<'a>>,
+}
+
+impl ExprParser<'_> {
+    fn peek(&mut self) -> Option<char> {
+        while let Some(&(_, c)) = self.chars.peek() {
+            if !c.is_whitespace() {
+                return Some(c);
+            }
+            self.chars.next();
+        }
+        None
+    }
+
+    fn or(&mut self) -> Result<Expr, String> {
+        let mut terms = vec![self.and()?];
+        while self.peek() == Some('|') {
+            self.chars.next();
+            terms.push(self.and()?);
+        }
+
+        Ok(if terms.len() == 1 {
+            terms.remove(0)
+        } else {
+            Expr::Or(terms)
+        })
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut terms = vec![self.unary()?];
+        while self.peek() == Some('&') {
+            self.chars.next();
+            terms.push(self.unary()?);
+        }
+
+        Ok(if terms.len() == 1 {
+            terms.remove(0)
+        } else {
+            Expr::And(terms)
+        })
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        self.peek();
+        match self.chars.next() {
+            Some((_, '!')) => Ok(Expr::Not(Box::new(self.unary()?))),
+            Some((_, '(')) => {
+                let e = self.or()?;
+                match (self.peek(), self.chars.next()) {
+                    (Some(')'), _) => Ok(e),
+                    (_, Some((i, c))) => Err(format!("expected ')' at {} but got {:?}", i, c)),
+                    _ => Err("expected ')' at the end".to_string()),
+                }
+            }
+            Some((_, c @ 'A'..='I')) => Ok(Expr::Sensor(c as u8 - b'A')),
+            Some((i, c)) => Err(format!("unexpected {:?} at {}", c, i)),
+            None => Err("unexpected end of expression".to_string()),
+        }
+    }
+}
+
+impl FromStr for Expr {
+    type Err = Box<dyn Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = ExprParser {
+            chars: s.char_indices().peekable(),
+        };
+        let e = parser.or()?;
+        if let (Some(c), Some((i, _))) = (parser.peek(), parser.chars.next()) {
+            return Err(format!("unexpected {:?} at {}", c, i).into());
+        }
+
+        Ok(e)
+    }
+}
+
+type Code = Vec<Instruction>;
+
+fn concat(code: &[Instruction], more: &[Instruction]) -> Code {
+    code.iter().chain(more).copied().collect()
+}
+
+/// Shortest code found which leaves a value in a register (`pos`) or its negation (`neg`).
+#[derive(Clone, Debug)]
+struct Compiled {
+    pos: Code,
+    neg: Code,
+}
+
+impl Compiled {
+    /// Use the other polarity followed by a `NOT` where that's shorter.
+    fn new(pos: Code, neg: Code, reg: Register) -> Self {
+        let not = Instruction::new(Op::Not, reg, reg);
+        let flipped_pos = (neg.len() + 1 < pos.len()).then(|| concat(&neg, &[not]));
+        let flipped_neg = (pos.len() + 1 < neg.len()).then(|| concat(&pos, &[not]));
+
+        Self {
+            pos: flipped_pos.unwrap_or(pos),
+            neg: flipped_neg.unwrap_or(neg),
+        }
+    }
+
+    fn one(code: Code, positive: bool, reg: Register) -> Self {
+        let flipped = concat(&code, &[Instruction::new(Op::Not, reg, reg)]);
+        if positive {
+            Self::new(code, flipped, reg)
+        } else {
+            Self::new(flipped, code, reg)
+        }
+    }
+
+    fn get(&self, positive: bool) -> &Code {
+        if positive {
+            &self.pos
+        } else {
+            &self.neg
+        }
+    }
+
+    fn negate(self) -> Self {
+        Self {
+            pos: self.neg,
+            neg: self.pos,
+        }
+    }
+
+    fn shortest(self, other: Self) -> Self {
+        Self {
+            pos: if other.pos.len() < self.pos.len() {
+                other.pos
+            } else {
+                self.pos
+            },
+            neg: if other.neg.len() < self.neg.len() {
+                other.neg
+            } else {
+                self.neg
+            },
+        }
+    }
+}
+
+/// Sensor which can be used directly as an operand, and whether the expression negates it.
+fn literal(e: &Expr) -> Option<(u8, bool)> {
+    match e {
+        Expr::Sensor(s) => Some((*s, false)),
+        Expr::Not(inner) => literal(inner).map(|(s, negated)| (s, !negated)),
+        _ => None,
+    }
+}
+
+/// Instruction combining the register with an operand of the same polarity.
+/// In negative polarity the register holds the negation, so De Morgan swaps the operation.
+fn combine_op(op: Op, positive: bool, operand: Register, reg: Register) -> Instruction {
+    let op = match (op, positive) {
+        (Op::And, true) | (Op::Or, false) => Op::And,
+        _ => Op::Or,
+    };
+    Instruction::new(op, operand, reg)
+}
+
+/// Compile the expression into `reg`, using `tmp` for nested expressions.
+/// `clean` means nothing has been written to `reg` yet, so it's still false.
+fn compile_expr(
+    e: &Expr,
+    reg: Register,
+    tmp: Option<Register>,
+    clean: bool,
+) -> Result<Compiled, String> {
+    if let Some((s, negated)) = literal(e) {
+        let x = Register::Sensor(s);
+        let not = vec![Instruction::new(Op::Not, x, reg)];
+        let copy = if clean {
+            vec![Instruction::new(Op::Or, x, reg)]
+        } else {
+            concat(&not, &[Instruction::new(Op::Not, reg, reg)])
+        };
+
+        return Ok(if negated {
+            Compiled::new(not, copy, reg)
+        } else {
+            Compiled::new(copy, not, reg)
+        });
+    }
+
+    match e {
+        Expr::Not(inner) => Ok(compile_expr(inner, reg, tmp, clean)?.negate()),
+        Expr::And(es) => compile_chain(Op::And, es, reg, tmp, clean),
+        Expr::Or(es) => compile_chain(Op::Or, es, reg, tmp, clean),
+        Expr::Sensor(_) => unreachable!("sensors are literals"),
+    }
+}
+
+fn compile_chain(
+    op: Op,
+    es: &[Expr],
+    reg: Register,
+    tmp: Option<Register>,
+    clean: bool,
+) -> Result<Compiled, String> {
+    let (literals, nested): (Vec<&Expr>, Vec<&Expr>) =
+        es.iter().partition(|e| literal(e).is_some());
+    if nested.len() > 1 && tmp.is_none() {
+        return Err("expression needs more than two registers".to_string());
+    }
+
+    // nested expressions go first so they can use the temporary register while it's clean,
+    // literals are grouped by polarity to avoid flipping the register back and forth
+    let mut best: Option<Compiled> = None;
+    for negated_first in [false, true] {
+        let mut order = nested.clone();
+        for negated in [negated_first, !negated_first] {
+            order.extend(
+                literals
+                    .iter()
+                    .filter(|e| literal(e).map(|l| l.1) == Some(negated)),
+            );
+        }
+
+        let mut acc = compile_expr(order[0], reg, tmp, clean)?;
+        for e in &order[1..] {
+            acc = match (literal(e), tmp) {
+                (Some((s, negated)), _) => {
+                    let positive = !negated;
+                    let instr = combine_op(op, positive, Register::Sensor(s), reg);
+                    Compiled::one(concat(acc.get(positive), &[instr]), positive, reg)
+                }
+                (None, Some(tmp)) => {
+                    let mut code = [Vec::new(), Vec::new()];
+                    for (i, positive) in [true, false].iter().copied().enumerate() {
+                        let prefix = acc.get(positive);
+                        let clean = !prefix.iter().any(|instr| instr.y == tmp);
+                        let operand = compile_expr(e, tmp, None, clean)?;
+                        code[i] = concat(prefix, operand.get(positive));
+                        code[i].push(combine_op(op, positive, tmp, reg));
+                    }
+                    let [pos, neg] = code;
+                    Compiled::new(pos, neg, reg)
+                }
+                (None, None) => unreachable!("checked above"),
+            };
+        }
+
+        best = Some(match best {
+            Some(best) => best.shortest(acc),
+            None => acc,
+        });
+    }
+
+    best.ok_or_else(|| "empty expression".to_string())
+}
+
+impl Expr {
+    /// Compile into a short program which jumps exactly when the expression is true.
+    pub fn compile(&self, mode: Mode) -> Result<Program, Box<dyn Error>> {
+        if let Some(s) = self.max_sensor().filter(|&s| s >= mode.sensors()) {
+            return Err(format!(
+                "sensor {} isn't available in {} mode",
+                Register::Sensor(s),
+                mode
+            )
+            .into());
+        }
+
+        let compiled = compile_expr(self, Register::J, Some(Register::T), true)?;
+        Program::new(compiled.pos, mode)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WALK: &str = "
+        OR A T
+        AND B T
+        AND C T
+        NOT T J
+        AND D J
+    ";
+
+    #[test]
+    fn test_parse() -> Result<(), Box<dyn Error>> {
+        let program = Program::parse(WALK, Mode::Walk)?;
+        assert_eq!(program.instructions.len(), 5);
+        assert_eq!(
+            program.instructions[3],
+            Instruction::new(Op::Not, Register::T, Register::J)
+        );
+        assert_eq!(
+            program.to_string(),
+            "OR A T\nAND B T\nAND C T\nNOT T J\nAND D J\nWALK\n"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate() {
+        let error = |text: &str, mode| Program::parse(text, mode).unwrap_err().to_string();
+
+        assert_eq!(
+            error("OR E J", Mode::Walk),
+            "instruction 1: register E isn't available in WALK mode"
+        );
+        assert!(Program::parse("OR E J", Mode::Run).is_ok());
+        assert_eq!(
+            error("NOT A T\nAND T A", Mode::Walk),
+            "instruction 2: can't write to register A"
+        );
+        assert_eq!(
+            error("XOR A J", Mode::Walk),
+            "line 1: unknown instruction XOR"
+        );
+        assert_eq!(error("OR A", Mode::Walk), "line 1: expected two arguments");
+        assert_eq!(
+            error("OR K J", Mode::Walk),
+            "line 1: expected a register as argument 1"
+        );
+        assert_eq!(
+            error(&"NOT A J\n".repeat(16), Mode::Walk),
+            "program has 16 instructions but at most 15 are allowed"
+        );
+    }
+
+    #[test]
+    fn test_parse_expr() -> Result<(), Box<dyn Error>> {
+        let e: Expr = "!(A&B&C) & D & (E|H)".parse()?;
+        assert_eq!(e.to_string(), "!(A & B & C) & D & (E | H)");
+        assert_eq!(e.max_sensor(), Some(7));
+        assert_eq!("A | B & !C".parse::<Expr>()?.to_string(), "A | (B & !C)");
+
+        assert!("A &".parse::<Expr>().is_err());
+        assert!("(A | B".parse::<Expr>().is_err());
+        assert!("A B".parse::<Expr>().is_err());
+        assert!("J".parse::<Expr>().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_compile() -> Result<(), Box<dyn Error>> {
+        let cases = [
+            ("A", Mode::Walk, 1),
+            ("!A", Mode::Walk, 1),
+            ("!A & D", Mode::Walk, 2),
+            ("!(A&B&C) & D", Mode::Walk, 5),
+            ("!(A&B&C) & D & (E|H)", Mode::Run, 8),
+            ("(!A | !B | !C) & D & (E | H)", Mode::Run, 8),
+            ("(A | !B) & (C | !D) & (!E | F)", Mode::Run, 8),
+        ];
+
+        for (text, mode, len) in cases {
+            let e: Expr = text.parse()?;
+            let program = e.compile(mode)?;
+            assert_eq!(program.instructions.len(), len, "{}:\n{}", text, program);
+            for sensors in 0..1 << mode.sensors() {
+                assert_eq!(
+                    program.eval(sensors),
+                    e.eval(sensors),
+                    "{}:\n{}",
+                    text,
+                    program
+                );
+            }
+        }
+
+        assert!("E".parse::<Expr>()?.compile(Mode::Walk).is_err());
+        assert!("(A & B | C & D) & (E & F | G & H)"
+            .parse::<Expr>()?
+            .compile(Mode::Run)
+            .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_simulate() -> Result<(), Box<dyn Error>> {
+        let walk = Program::parse(WALK, Mode::Walk)?;
+        assert_eq!(walk.simulate(&"#####.###########".parse()?), Ok(()));
+        assert_eq!(walk.simulate(&"#####..#.########".parse()?), Ok(()));
+
+        // jumps as soon as it can and lands right before a gap
+        let hull: Hull = "###########.#.##.####".parse()?;
+        assert_eq!(hull.sensors(0, Mode::Walk), 0b1111);
+        assert_eq!(hull.sensors(8, Mode::Walk), 0b1011);
+        assert_eq!(walk.simulate(&hull), Err(13));
+
+        let run = "!(A&B&C) & D & (E|H)".parse::<Expr>()?.compile(Mode::Run)?;
+        assert_eq!(run.simulate(&hull), Ok(()));
+        assert_eq!(Program::parse("", Mode::Walk)?.simulate(&hull), Err(11));
+
+        Ok(())
+    }
+}
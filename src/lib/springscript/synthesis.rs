@@ -0,0 +1,389 @@
+//! Finding springscript programs from the hulls the droid fell on.
+//!
+//! Every failure adds a hull to the constraints. The jump decisions which get the droid across
+//! all known hulls are found by backtracking, then formulas over the sensors are enumerated by
+//! size until one makes the same decisions and compiles to a valid program.
+
+use super::{Expr, Hull, Mode, Program, Sensors};
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+
+/// Give up if the droid keeps finding new hulls to fall on.
+const MAX_ATTEMPTS: usize = 100;
+
+/// Number of alternative decision tables formulas are checked against.
+const MAX_TABLES: usize = 64;
+
+/// Largest formula tried, counted in sensors used.
+const MAX_LEAVES: usize = 8;
+
+/// What happened after the droid ran a program.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Outcome {
+    /// The droid made it across and reported the amount of hull damage.
+    Survived(i64),
+    /// The droid fell into a hole of the hull.
+    Fell(Hull),
+}
+
+/// Runs programs on the real hull, usually by asking the springdroid.
+pub trait Oracle {
+    fn check(&mut self, program: &Program) -> Result<Outcome, Box<dyn Error>>;
+}
+
+/// Find a program which gets the droid across, starting with the already known hulls.
+/// New hulls the droid falls on are added to `hulls`.
+pub fn synthesize(
+    oracle: &mut impl Oracle,
+    mode: Mode,
+    hulls: &mut Vec<Hull>,
+) -> Result<(Program, i64), Box<dyn Error>> {
+    for _ in 0..MAX_ATTEMPTS {
+        let program = find_program(hulls, mode)?;
+        if let Some(hull) = hulls.iter().find(|h| program.simulate(h).is_err()) {
+            return Err(format!("program doesn't cross known hull {}:\n{}", hull, program).into());
+        }
+
+        match oracle.check(&program)? {
+            Outcome::Survived(damage) => return Ok((program, damage)),
+            Outcome::Fell(hull) if hulls.contains(&hull) => {
+                return Err(format!("droid fell on known hull {} with\n{}", hull, program).into())
+            }
+            Outcome::Fell(hull) => hulls.push(hull),
+        }
+    }
+
+    Err(format!("no program found after {} attempts", MAX_ATTEMPTS).into())
+}
+
+/// Get the hull from the pictures the droid prints when it falls.
+/// Only the first picture is needed, it shows the hull with the droid at its start.
+pub fn parse_failure(output: &str) -> Result<Hull, Box<dyn Error>> {
+    let is_picture = |line: &&str| !line.is_empty() && line.chars().all(|c| "#.@".contains(c));
+    let picture: Vec<_> = output
+        .lines()
+        .map(str::trim)
+        .skip_while(|line| !is_picture(line))
+        .take_while(is_picture)
+        .collect();
+
+    let (hull, rows) = picture.split_last().ok_or("no hull in output")?;
+    let start = rows
+        .iter()
+        .find_map(|row| row.find('@'))
+        .ok_or("no droid in output")?;
+
+    // the droid is drawn in the hull when it falls into a hole
+    hull.get(start..)
+        .ok_or("droid is beyond the hull")?
+        .replace('@', ".")
+        .parse()
+}
+
+/// Bits for a sequence of sensor readings.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+struct Bits(Vec<u64>);
+
+impl Bits {
+    fn new(len: usize) -> Self {
+        Self(vec![0; len.div_ceil(64)])
+    }
+
+    fn set(&mut self, i: usize) {
+        self.0[i / 64] |= 1 << (i % 64);
+    }
+
+    fn combine(&self, other: &Self, f: impl Fn(u64, u64) -> u64) -> Self {
+        Self(
+            self.0
+                .iter()
+                .zip(&other.0)
+                .map(|(&a, &b)| f(a, b))
+                .collect(),
+        )
+    }
+}
+
+/// Jump decisions for the sensor readings which come up while crossing the hulls.
+struct Table {
+    readings: Bits,
+    jumps: Bits,
+}
+
+impl Table {
+    fn matches(&self, jumps: &Bits) -> bool {
+        let diff = jumps.combine(&self.jumps, |a, b| a ^ b);
+        diff.combine(&self.readings, |a, b| a & b)
+            .0
+            .iter()
+            .all(|&w| w == 0)
+    }
+}
+
+/// Collect decision tables which get the droid across all hulls, walking is tried first.
+fn find_tables(
+    hulls: &[Hull],
+    mode: Mode,
+    hull: usize,
+    pos: usize,
+    table: &mut HashMap<Sensors, bool>,
+    found: &mut Vec<HashMap<Sensors, bool>>,
+) {
+    if found.len() >= MAX_TABLES {
+        return;
+    }
+    if hull == hulls.len() {
+        found.push(table.clone());
+        return;
+    }
+    if pos >= hulls[hull].len() {
+        return find_tables(hulls, mode, hull + 1, 0, table, found);
+    }
+
+    let reading = hulls[hull].sensors(pos, mode);
+    let choices = match table.get(&reading) {
+        Some(&jump) => vec![jump],
+        None => vec![false, true],
+    };
+    for jump in choices {
+        let next = match hulls[hull].step(pos, jump) {
+            Ok(next) => next,
+            Err(_) => continue,
+        };
+
+        let decided = table.insert(reading, jump).is_some();
+        find_tables(hulls, mode, hull, next, table, found);
+        if !decided {
+            table.remove(&reading);
+        }
+    }
+}
+
+/// Combine two formulas, flattening nested chains of the same operation.
+fn join(and: bool, a: &Expr, b: &Expr) -> Expr {
+    let mut terms = Vec::new();
+    for e in [a, b] {
+        match e {
+            Expr::And(es) if and => terms.extend(es.iter().cloned()),
+            Expr::Or(es) if !and => terms.extend(es.iter().cloned()),
+            _ => terms.push(e.clone()),
+        }
+    }
+
+    if and {
+        Expr::And(terms)
+    } else {
+        Expr::Or(terms)
+    }
+}
+
+/// Find the smallest formula consistent with one of the decision tables for the hulls.
+/// Formulas are only distinguished by their decisions, so only one of each is kept.
+fn find_program(hulls: &[Hull], mode: Mode) -> Result<Program, Box<dyn Error>> {
+    let mut found = Vec::new();
+    find_tables(hulls, mode, 0, 0, &mut HashMap::new(), &mut found);
+    if found.is_empty() {
+        return Err(format!("no way to cross all {} hulls in {} mode", hulls.len(), mode).into());
+    }
+
+    let mut readings: Vec<Sensors> = found.iter().flat_map(|t| t.keys().copied()).collect();
+    readings.sort_unstable();
+    readings.dedup();
+
+    let tables: Vec<_> = found
+        .iter()
+        .map(|t| {
+            let mut table = Table {
+                readings: Bits::new(readings.len()),
+                jumps: Bits::new(readings.len()),
+            };
+            for (i, r) in readings.iter().enumerate() {
+                if let Some(&jump) = t.get(r) {
+                    table.readings.set(i);
+                    if jump {
+                        table.jumps.set(i);
+                    }
+                }
+            }
+            table
+        })
+        .collect();
+
+    let accept = |e: &Expr, jumps: &Bits| {
+        if tables.iter().any(|t| t.matches(jumps)) {
+            e.compile(mode).ok()
+        } else {
+            None
+        }
+    };
+
+    let mut seen = HashSet::new();
+    // formulas by number of sensors used, negations only appear on sensors
+    let mut levels: Vec<Vec<(Expr, Bits)>> = vec![Vec::new(), Vec::new()];
+    for s in 0..mode.sensors() {
+        for negated in [false, true] {
+            let mut jumps = Bits::new(readings.len());
+            for (i, r) in readings.iter().enumerate() {
+                if (r & (1 << s) != 0) != negated {
+                    jumps.set(i);
+                }
+            }
+            if !seen.insert(jumps.clone()) {
+                continue;
+            }
+
+            let e = if negated {
+                Expr::Not(Box::new(Expr::Sensor(s)))
+            } else {
+                Expr::Sensor(s)
+            };
+            if let Some(program) = accept(&e, &jumps) {
+                return Ok(program);
+            }
+            levels[1].push((e, jumps));
+        }
+    }
+
+    for leaves in 2..=MAX_LEAVES {
+        let mut level = Vec::new();
+        for left in 1..=leaves / 2 {
+            let right = leaves - left;
+            for (i, (a, a_jumps)) in levels[left].iter().enumerate() {
+                let skip = if left == right { i + 1 } else { 0 };
+                for (b, b_jumps) in &levels[right][skip..] {
+                    for and in [true, false] {
+                        let jumps = if and {
+                            a_jumps.combine(b_jumps, |a, b| a & b)
+                        } else {
+                            a_jumps.combine(b_jumps, |a, b| a | b)
+                        };
+                        if !seen.insert(jumps.clone()) {
+                            continue;
+                        }
+
+                        let e = join(and, a, b);
+                        if let Some(program) = accept(&e, &jumps) {
+                            return Ok(program);
+                        }
+                        level.push((e, jumps));
+                    }
+                }
+            }
+        }
+        levels.push(level);
+    }
+
+    Err(format!(
+        "no formula with at most {} sensors crosses all {} hulls",
+        MAX_LEAVES,
+        hulls.len()
+    )
+    .into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Pretends to be the droid by simulating the program on a fixed set of hulls.
+    struct FakeDroid {
+        hulls: Vec<Hull>,
+        runs: usize,
+    }
+
+    impl FakeDroid {
+        fn new(hulls: &[&str]) -> Result<Self, Box<dyn Error>> {
+            let hulls = hulls.iter().map(|h| h.parse()).collect::<Result<_, _>>()?;
+            Ok(Self { hulls, runs: 0 })
+        }
+    }
+
+    impl Oracle for FakeDroid {
+        fn check(&mut self, program: &Program) -> Result<Outcome, Box<dyn Error>> {
+            self.runs += 1;
+            match self.hulls.iter().find(|h| program.simulate(h).is_err()) {
+                Some(hull) => Ok(Outcome::Fell(hull.clone())),
+                None => Ok(Outcome::Survived(19_358_870)),
+            }
+        }
+    }
+
+    const WALK_HULLS: &[&str] = &[
+        "#####.###########",
+        "#####...#########",
+        "#####..#.########",
+        "#####.#..########",
+        "#####.##.########",
+    ];
+
+    const RUN_HULLS: &[&str] = &[
+        "#####.###.#.##...",
+        "#####.#.##..####.",
+        "#######.#.##.###.",
+        "#####.#.##...#.#.",
+        "#####.#.##...####",
+        "#####.#.##.######",
+    ];
+
+    const FAILURE: &str = "
+Walking...
+
+
+Didn't make it across:
+
+.................
+.................
+@................
+#####.#..########
+
+.................
+.................
+.@...............
+#####.#..########
+
+.................
+.................
+.................
+#####@#..########
+
+";
+
+    #[test]
+    fn test_parse_failure() -> Result<(), Box<dyn Error>> {
+        assert_eq!(parse_failure(FAILURE)?.to_string(), "#####.#..########");
+        assert!(parse_failure("Walking...\n").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_synthesize_walk() -> Result<(), Box<dyn Error>> {
+        let mut droid = FakeDroid::new(WALK_HULLS)?;
+        let mut hulls = Vec::new();
+        let (program, damage) = synthesize(&mut droid, Mode::Walk, &mut hulls)?;
+
+        assert_eq!(damage, 19_358_870);
+        assert_eq!(droid.runs, hulls.len() + 1);
+        assert!(droid.hulls.iter().all(|h| program.simulate(h).is_ok()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_synthesize_run() -> Result<(), Box<dyn Error>> {
+        let mut droid = FakeDroid::new(RUN_HULLS)?;
+        let known = "!(A & B & C) & D & (E | H)".parse::<Expr>()?;
+        let known = known.compile(Mode::Run)?;
+        assert!(droid.hulls.iter().all(|h| known.simulate(h).is_ok()));
+
+        let mut hulls = Vec::new();
+        let (program, _) = synthesize(&mut droid, Mode::Run, &mut hulls)?;
+        assert!(droid.hulls.iter().all(|h| program.simulate(h).is_ok()));
+
+        // the program for the first part falls on every one of them
+        let walk = "!(A & B & C) & D".parse::<Expr>()?.compile(Mode::Walk)?;
+        assert!(droid.hulls.iter().all(|h| walk.simulate(h).is_err()));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,244 @@
+//! Keyboard input for interactive puzzles.
+
+use std::io::{self, Read, Write};
+#[cfg(unix)]
+use std::mem;
+use std::sync::mpsc;
+use std::thread;
+
+/// Key pressed on the keyboard.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Key {
+    Up,
+    Down,
+    Left,
+    Right,
+    Escape,
+    Tab,
+    Backspace,
+    Enter,
+    /// Ctrl-C, which doesn't send an interrupt in raw mode.
+    Interrupt,
+    /// Ctrl-D
+    EndOfInput,
+    Char(char),
+}
+
+/// Split the bytes read from the terminal into keys.
+/// Unknown escape sequences are dropped.
+pub fn parse_keys(bytes: &[u8]) -> Vec<Key> {
+    let mut keys = Vec::new();
+    let text = String::from_utf8_lossy(bytes);
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        let key = match c {
+            '\x1B' if chars.peek() == Some(&'[') => {
+                chars.next();
+                // skip the parameters of sequences like page up (`ESC [ 5 ~`)
+                while chars.peek().is_some_and(|c| ('0'..='?').contains(c)) {
+                    chars.next();
+                }
+                match chars.next() {
+                    Some('A') => Key::Up,
+                    Some('B') => Key::Down,
+                    Some('C') => Key::Right,
+                    Some('D') => Key::Left,
+                    _ => continue,
+                }
+            }
+            '\x1B' => Key::Escape,
+            '\t' => Key::Tab,
+            '\x7F' | '\x08' => Key::Backspace,
+            '\r' | '\n' => Key::Enter,
+            '\x03' => Key::Interrupt,
+            '\x04' => Key::EndOfInput,
+            c => Key::Char(c),
+        };
+        keys.push(key);
+    }
+
+    keys
+}
+
+/// Puts the terminal into raw mode until it's dropped.
+/// Keys are available immediately without waiting for enter and aren't echoed.
+/// Only supported on unix, enabling it fails elsewhere.
+pub struct RawMode {
+    #[cfg(unix)]
+    original: libc::termios,
+}
+
+impl RawMode {
+    #[cfg(unix)]
+    pub fn enable() -> io::Result<Self> {
+        // SAFETY: termios is a plain C struct which tcgetattr fills in
+        let mut attrs: libc::termios = unsafe { mem::zeroed() };
+        if unsafe { libc::tcgetattr(libc::STDIN_FILENO, &mut attrs) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let original = attrs;
+        attrs.c_lflag &= !(libc::ICANON | libc::ECHO | libc::ISIG);
+        attrs.c_cc[libc::VMIN] = 1;
+        attrs.c_cc[libc::VTIME] = 0;
+        if unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &attrs) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(Self { original })
+    }
+
+    #[cfg(not(unix))]
+    pub fn enable() -> io::Result<Self> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "raw mode is only supported on unix terminals",
+        ))
+    }
+}
+
+#[cfg(unix)]
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        unsafe {
+            libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &self.original);
+        }
+    }
+}
+
+/// Read keys from stdin on a separate thread so they can be polled without blocking.
+/// The thread stops once the receiver is dropped and another key is pressed.
+pub fn spawn_key_reader() -> mpsc::Receiver<Key> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let mut buf = [0; 32];
+        loop {
+            let n = match io::stdin().read(&mut buf) {
+                Ok(0) | Err(_) => return,
+                Ok(n) => n,
+            };
+            for key in parse_keys(&buf[..n]) {
+                if tx.send(key).is_err() {
+                    return;
+                }
+            }
+        }
+    });
+
+    rx
+}
+
+/// Complete the line as far as possible using the candidates starting with it.
+/// Returns the completed line and all matching candidates.
+pub fn complete<'a>(line: &str, candidates: &'a [String]) -> (String, Vec<&'a str>) {
+    let matches: Vec<&str> = candidates
+        .iter()
+        .map(String::as_str)
+        .filter(|c| c.starts_with(line))
+        .collect();
+
+    let completed = match matches.split_first() {
+        Some((first, rest)) => rest.iter().fold(first.to_string(), |prefix, m| {
+            prefix
+                .chars()
+                .zip(m.chars())
+                .take_while(|(a, b)| a == b)
+                .map(|(a, _)| a)
+                .collect()
+        }),
+        None => line.to_string(),
+    };
+
+    (completed, matches)
+}
+
+/// Read a line in raw mode with tab completion from the candidates.
+/// Returns `None` if the input ended or was interrupted.
+pub fn read_line(
+    keys: &mpsc::Receiver<Key>,
+    prompt: &str,
+    candidates: &[String],
+) -> io::Result<Option<String>> {
+    let mut out = io::stdout();
+    let mut line = String::new();
+    write!(out, "{}", prompt)?;
+    out.flush()?;
+
+    for key in keys.iter() {
+        match key {
+            Key::Enter => {
+                writeln!(out)?;
+                return Ok(Some(line));
+            }
+            Key::Interrupt | Key::EndOfInput => {
+                writeln!(out)?;
+                return Ok(None);
+            }
+            Key::Backspace if !line.is_empty() => {
+                line.pop();
+                write!(out, "\x08 \x08")?;
+            }
+            Key::Tab => {
+                let (completed, matches) = complete(&line, candidates);
+                if matches.len() > 1 && completed == line {
+                    writeln!(out)?;
+                    writeln!(out, "{}", matches.join("  "))?;
+                }
+                line = completed;
+                write!(out, "\r\x1B[K{}{}", prompt, line)?;
+            }
+            Key::Char(c) if !c.is_control() => {
+                line.push(c);
+                write!(out, "{}", c)?;
+            }
+            _ => (),
+        }
+        out.flush()?;
+    }
+
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_keys() {
+        assert_eq!(
+            parse_keys(b"a\x1B[D\x1B[C\x1B[A\x1B[B"),
+            vec![Key::Char('a'), Key::Left, Key::Right, Key::Up, Key::Down]
+        );
+        assert_eq!(
+            parse_keys(b"\x1B\x03\r\x1B[5~\t\x7F\x04"),
+            vec![
+                Key::Escape,
+                Key::Interrupt,
+                Key::Enter,
+                Key::Tab,
+                Key::Backspace,
+                Key::EndOfInput
+            ]
+        );
+    }
+
+    #[test]
+    fn test_complete() {
+        let candidates: Vec<String> = vec!["north", "take mug", "take mutex", "take food"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+
+        assert_eq!(
+            complete("n", &candidates),
+            ("north".to_string(), vec!["north"])
+        );
+        assert_eq!(
+            complete("take m", &candidates),
+            ("take mu".to_string(), vec!["take mug", "take mutex"])
+        );
+        assert_eq!(complete("take", &candidates).0, "take ");
+        assert_eq!(complete("drop", &candidates), ("drop".to_string(), vec![]));
+    }
+}
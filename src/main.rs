@@ -1,10 +1,20 @@
+use std::path::Path;
+use std::time::Duration;
 use std::{error, fs, io};
 
-use chrono::{Datelike, TimeZone, Utc};
+use chrono::Utc;
 use clap::{App, Arg};
 
-use input::Input;
+use calendar::Calendar;
+use fetch::{Fetcher, UreqClient};
+use input::{Input, InputDir};
+use lib::image::{self, Image};
+use lib::ocr;
+use lib::render::TerminalRenderer;
 
+mod calendar;
+mod examples;
+mod fetch;
 mod input;
 mod lib;
 mod puzzles;
@@ -21,6 +31,14 @@ fn get_app<'a, 'b>() -> App<'a, 'b> {
                 .help("Set the day (Defaults to the current day)")
                 .takes_value(true),
         )
+        .arg(
+            Arg::with_name("year")
+                .short("y")
+                .long("year")
+                .value_name("YEAR")
+                .default_value("2019")
+                .help("Set the year of the event used to determine the current day"),
+        )
         .arg(
             Arg::with_name("part")
                 .short("p")
@@ -30,15 +48,106 @@ fn get_app<'a, 'b>() -> App<'a, 'b> {
                 .default_value("both")
                 .help("Which part of the day to solve"),
         )
+        .arg(
+            Arg::with_name("inputs")
+                .long("inputs")
+                .value_name("DIR")
+                .default_value("inputs")
+                .help("Directory to look for puzzle inputs in"),
+        )
+        .arg(
+            Arg::with_name("input-name")
+                .long("input-name")
+                .value_name("PATTERN")
+                .default_value(InputDir::DEFAULT_PATTERN)
+                .help("File name of the inputs in the input directory ({year} and {day} are replaced)"),
+        )
+        .arg(
+            Arg::with_name("session")
+                .long("session")
+                .value_name("FILE")
+                .default_value(".aoc_session")
+                .help("File containing the session cookie used to download missing inputs (Overridden by AOC_SESSION)"),
+        )
+        .arg(
+            Arg::with_name("extract-examples")
+                .long("extract-examples")
+                .value_name("HTML")
+                .help("Extract the examples from a saved puzzle description and store them as fixtures for the day"),
+        )
+        .arg(
+            Arg::with_name("fixtures")
+                .long("fixtures")
+                .value_name("DIR")
+                .default_value("fixtures")
+                .help("Directory to store extracted examples in"),
+        )
+        .arg(
+            Arg::with_name("param")
+                .long("param")
+                .value_name("NAME=VALUE")
+                .multiple(true)
+                .number_of_values(1)
+                .help("Override a parameter of the puzzle (e.g. to run the smaller examples)"),
+        )
+        .arg(
+            Arg::with_name("raw")
+                .long("raw")
+                .help("Print letters drawn by the puzzle as block art instead of reading them (Same as --param raw=true, ignored by days without letters)"),
+        )
+        .arg(
+            Arg::with_name("visualize")
+                .long("visualize")
+                .help("Replay a visualization of the puzzle in the terminal instead of printing the solution"),
+        )
+        .arg(
+            Arg::with_name("play")
+                .long("play")
+                .help("Play the puzzle interactively in the terminal (Only some days can be played)"),
+        )
+        .arg(
+            Arg::with_name("fps")
+                .long("fps")
+                .value_name("FPS")
+                .default_value("30")
+                .help("Frames per second used to replay visualizations"),
+        )
+        .arg(
+            Arg::with_name("export-gif")
+                .long("export-gif")
+                .value_name("FILE")
+                .help("Export the visualization of a part as animated GIF (Uses the second part unless --part is first)"),
+        )
+        .arg(
+            Arg::with_name("export-png")
+                .long("export-png")
+                .value_name("FILE")
+                .help("Export the last frame of the visualization of a part as PNG"),
+        )
+        .arg(
+            Arg::with_name("scale")
+                .long("scale")
+                .value_name("PIXELS")
+                .default_value("4")
+                .help("Size of a cell in exported images"),
+        )
         .arg(
             Arg::with_name("INPUT")
-                .help("Sets the input file to use")
-                .index(1)
-                .default_value("STDIN"),
+                .help("Sets the input file to use (Use '-' to read from STDIN) [default: file in the input directory]")
+                .index(1),
         )
 }
 
-fn get_day(value: Option<&str>) -> Result<u8, Box<dyn error::Error>> {
+fn get_calendar(value: Option<&str>) -> Result<Calendar, Box<dyn error::Error>> {
+    let year = value
+        .unwrap_or("2019")
+        .parse()
+        .map_err(|_| "couldn't parse year value")?;
+
+    Calendar::new(year)
+}
+
+fn get_day(value: Option<&str>, calendar: &Calendar) -> Result<u8, Box<dyn error::Error>> {
     if let Some(raw) = value {
         if let Ok(day) = raw.parse() {
             return Ok(day);
@@ -47,13 +156,7 @@ fn get_day(value: Option<&str>) -> Result<u8, Box<dyn error::Error>> {
         return Err("couldn't parse day value".into());
     }
 
-    // TODO use correct timezone
-    let now = Utc::now().date();
-    if now >= Utc.ymd(2019, 12, 1) && now <= Utc.ymd(2019, 12, 25) {
-        return Ok(now.day() as u8);
-    }
-
-    Err("not in the required day range".into())
+    calendar.current_day(Utc::now())
 }
 
 type Part = u8;
@@ -70,15 +173,63 @@ fn get_part(value: Option<&str>) -> Part {
     }
 }
 
-fn get_input(value: Option<&str>) -> io::Result<Input> {
-    let fp = value.unwrap_or("STDIN");
-    if fp == "STDIN" {
-        println!("Provide the puzzle input (Use two newlines to stop reading):");
-        return read_until_two_newlines(io::stdin().lock())
-            .and_then(|s| Ok(Input::new(s.as_str())));
+fn get_fetcher(value: Option<&str>) -> io::Result<Option<Fetcher<UreqClient>>> {
+    let session = fetch::read_session(value.unwrap_or(".aoc_session"))?;
+
+    Ok(session.map(|s| Fetcher::new(UreqClient::new(), fetch::BASE_URL, &s)))
+}
+
+fn get_input(
+    value: Option<&str>,
+    dir: &InputDir,
+    fetcher: Option<&Fetcher<UreqClient>>,
+    year: i32,
+    day: u8,
+) -> Result<Input, Box<dyn error::Error>> {
+    match value {
+        Some("-") => {
+            println!("Provide the puzzle input (Press Ctrl-D to stop reading):");
+            Ok(Input::from_reader(&mut io::stdin().lock())?)
+        }
+        Some(fp) => Ok(fs::File::open(fp).and_then(|mut file| Input::from_reader(&mut file))?),
+        None => match fetcher {
+            Some(fetcher) => fetcher.load_or_fetch(dir, year, day),
+            None => dir.load(year, day)?.ok_or_else(|| {
+                format!(
+                    "no input found at {} (Use '-' to read from STDIN or provide a session to download it)",
+                    dir.path_for(year, day).display()
+                )
+                .into()
+            }),
+        },
+    }
+}
+
+fn extract_examples(
+    html_path: &str,
+    fixtures: &str,
+    day: u8,
+) -> Result<usize, Box<dyn error::Error>> {
+    let html = fs::read_to_string(html_path)?;
+    let examples = examples::extract_examples(&html);
+    examples::write_fixtures(Path::new(fixtures), day, &examples)?;
+
+    Ok(examples.len())
+}
+
+fn set_params<'a>(
+    input: &mut Input,
+    values: impl Iterator<Item = &'a str>,
+) -> Result<(), Box<dyn error::Error>> {
+    for raw in values {
+        let mut parts = raw.splitn(2, '=').map(str::trim);
+        match (parts.next(), parts.next()) {
+            (Some(name), Some(value)) if !name.is_empty() => input.set_param(name, value),
+            _ => return Err(format!("invalid parameter {} (expected NAME=VALUE)", raw).into()),
+        }
     }
 
-    fs::File::open(fp).and_then(|mut file| Input::from_reader(&mut file))
+    Ok(())
 }
 
 fn get_output(result: Result<String, Box<dyn error::Error>>) -> String {
@@ -108,56 +259,189 @@ fn solve_puzzle(day: u8, part: Part, input: Input) {
     }
 }
 
+fn get_fps(value: Option<&str>) -> Result<f64, Box<dyn error::Error>> {
+    // the delay between frames has to fit into a duration
+    let valid =
+        |fps: f64| fps.is_finite() && fps > 0.0 && Duration::try_from_secs_f64(1.0 / fps).is_ok();
+    match value.unwrap_or("30").parse() {
+        Ok(fps) if valid(fps) => Ok(fps),
+        _ => Err("fps must be a positive number".into()),
+    }
+}
+
+fn visualize_puzzle(day: u8, part: Part, input: Input, fps: f64) {
+    let parts = [(FIRST_PART, false), (SECOND_PART, true)];
+    for &(_, second) in parts.iter().filter(|(p, _)| part & p != 0) {
+        let rec = match puzzles::visualize(day, second, &input) {
+            Some(Ok(rec)) => rec,
+            Some(Err(e)) => {
+                println!("{}", e);
+                return;
+            }
+            None => {
+                println!("day {} has no visualization", day);
+                return;
+            }
+        };
+
+        if let Err(e) = rec.replay(&mut TerminalRenderer::stdout(), fps) {
+            println!("couldn't replay visualization: {}", e);
+            return;
+        }
+    }
+}
+
+fn get_scale(value: Option<&str>) -> Result<usize, Box<dyn error::Error>> {
+    match value.unwrap_or("4").parse() {
+        Ok(scale) if scale > 0 => Ok(scale),
+        _ => Err("scale must be a positive integer".into()),
+    }
+}
+
+/// Where and how to export a visualization.
+struct Export<'a> {
+    gif: Option<&'a str>,
+    png: Option<&'a str>,
+    scale: usize,
+    fps: f64,
+}
+
+fn export_visualization(
+    day: u8,
+    part: Part,
+    input: Input,
+    export: &Export,
+) -> Result<(), Box<dyn error::Error>> {
+    // only a single part can be exported, prefer the second one unless asked otherwise
+    let second = part & SECOND_PART != 0;
+    let rec = puzzles::visualize(day, second, &input)
+        .ok_or_else(|| format!("day {} has no visualization", day))??;
+    let images = Image::from_recording(&rec, export.scale);
+
+    if let Some(path) = export.png {
+        let last = images.last().ok_or("visualization has no frames")?;
+        fs::write(path, image::encode_png(last))?;
+        println!("wrote {}", path);
+    }
+
+    if let Some(path) = export.gif {
+        fs::write(path, image::encode_gif(&images, export.fps)?)?;
+        println!("wrote {} frames to {}", images.len(), path);
+    }
+
+    Ok(())
+}
+
 fn main() {
     let matches = get_app().get_matches();
 
-    let day;
-    match get_day(matches.value_of("day")) {
-        Ok(d) => day = d,
+    let calendar = match get_calendar(matches.value_of("year")) {
+        Ok(c) => c,
         Err(e) => {
             println!("{}", e);
             return;
         }
-    }
+    };
+
+    let day = match get_day(matches.value_of("day"), &calendar) {
+        Ok(d) => d,
+        Err(e) => {
+            println!("{}", e);
+            return;
+        }
+    };
 
-    if day == 0 || day > 25 {
-        println!("day must be between 1 and 25 (both inclusive)");
+    if day == 0 || day > calendar.days() {
+        println!(
+            "day must be between 1 and {} (both inclusive)",
+            calendar.days()
+        );
         return;
     }
 
     let part = get_part(matches.value_of("part"));
 
-    let input;
-    match get_input(matches.value_of("INPUT")) {
-        Ok(i) => input = i,
+    if let Some(html_path) = matches.value_of("extract-examples") {
+        let fixtures = matches.value_of("fixtures").unwrap_or("fixtures");
+        match extract_examples(html_path, fixtures, day) {
+            Ok(count) => println!("extracted {} examples for day {}", count, day),
+            Err(e) => println!("couldn't extract examples: {}", e),
+        }
+        return;
+    }
+
+    let input_dir = InputDir::new(
+        matches.value_of("inputs").unwrap_or("inputs"),
+        matches
+            .value_of("input-name")
+            .unwrap_or(InputDir::DEFAULT_PATTERN),
+    );
+
+    let fetcher = match get_fetcher(matches.value_of("session")) {
+        Ok(f) => f,
         Err(e) => {
-            println!("couldn't read input file: {}", e);
+            println!("couldn't read session file: {}", e);
             return;
         }
-    }
+    };
 
-    solve_puzzle(day, part, input);
-}
+    let mut input = match get_input(
+        matches.value_of("INPUT"),
+        &input_dir,
+        fetcher.as_ref(),
+        calendar.year(),
+        day,
+    ) {
+        Ok(i) => i,
+        Err(e) => {
+            println!("couldn't read input: {}", e);
+            return;
+        }
+    };
 
-fn read_until_two_newlines(reader: impl io::BufRead) -> io::Result<String> {
-    let mut s = String::new();
-    let mut exit_next = false;
+    let mut params: Vec<_> = matches.values_of("param").into_iter().flatten().collect();
+    // days without letters to read have nothing to show as block art
+    if matches.is_present("raw") && puzzles::has_param(day, ocr::RAW.name) {
+        params.push("raw=true");
+    }
+    if let Err(e) =
+        set_params(&mut input, params.into_iter()).and_then(|_| puzzles::check_params(day, &input))
+    {
+        println!("{}", e);
+        return;
+    }
 
-    for line in reader.lines() {
-        let line = line?;
-        s.push_str(line.as_str());
-        s.push('\n');
+    if matches.is_present("play") {
+        match puzzles::play(day, &input) {
+            Some(Ok(result)) => println!("{}", result),
+            Some(Err(e)) => println!("{}", e),
+            None => println!("day {} can't be played", day),
+        }
+        return;
+    }
 
-        if line.is_empty() {
-            if exit_next {
-                break;
-            }
+    if matches.is_present("export-gif") || matches.is_present("export-png") {
+        let export = get_fps(matches.value_of("fps")).and_then(|fps| {
+            Ok(Export {
+                gif: matches.value_of("export-gif"),
+                png: matches.value_of("export-png"),
+                scale: get_scale(matches.value_of("scale"))?,
+                fps,
+            })
+        });
+        if let Err(e) = export.and_then(|export| export_visualization(day, part, input, &export)) {
+            println!("couldn't export visualization: {}", e);
+        }
+        return;
+    }
 
-            exit_next = true;
-        } else {
-            exit_next = false;
+    if matches.is_present("visualize") {
+        match get_fps(matches.value_of("fps")) {
+            Ok(fps) => visualize_puzzle(day, part, input, fps),
+            Err(e) => println!("{}", e),
         }
+        return;
     }
 
-    Ok(s)
+    solve_puzzle(day, part, input);
 }
@@ -1,99 +1,49 @@
 use crate::input::Input;
+use crate::lib::grid::{Direction, Point};
 use std::collections::{HashMap, HashSet};
 use std::error::Error;
-use std::ops;
 
-#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
-struct Position(isize, isize);
+fn parse_segment(seg: &str) -> Result<(Direction, isize), Box<dyn Error>> {
+    let dir = seg
+        .chars()
+        .next()
+        .and_then(Direction::from_char)
+        .ok_or_else(|| format!("invalid segment: {}", seg))?;
+    let len = seg[1..].parse()?;
 
-impl Position {
-    const ZERO: Position = Position(0, 0);
-
-    fn from_dir(d: char) -> Position {
-        match d {
-            'R' => Position(1, 0),
-            'D' => Position(0, -1),
-            'L' => Position(-1, 0),
-            'U' => Position(0, 1),
-            _ => unreachable!(),
-        }
-    }
-
-    fn from_seg(seg: &str) -> Position {
-        let pos = Position::from_dir(seg.chars().nth(0).unwrap());
-        let mag = seg[1..].parse().unwrap();
-        pos * mag
-    }
-
-    fn as_direction(self) -> Position {
-        self / self.magnitude()
-    }
-
-    fn magnitude(&self) -> isize {
-        self.0.abs() + self.1.abs()
-    }
+    Ok((dir, len))
 }
 
-impl ops::Add for Position {
-    type Output = Self;
-
-    fn add(self, rhs: Self) -> Self::Output {
-        Self(self.0 + rhs.0, self.1 + rhs.1)
-    }
-}
-
-impl ops::Mul<isize> for Position {
-    type Output = Self;
-
-    fn mul(self, rhs: isize) -> Self::Output {
-        Self(self.0 * rhs, self.1 * rhs)
-    }
-}
-
-impl ops::Div<isize> for Position {
-    type Output = Self;
-
-    fn div(self, rhs: isize) -> Self::Output {
-        Self(self.0 / rhs, self.1 / rhs)
-    }
-}
-
-fn iter_steps_in_dir(pos: Position, dir: Position) -> impl Iterator<Item = Position> {
-    let mag = dir.magnitude();
-    let dir = dir.as_direction();
-
-    (1..=mag).map(move |i| pos + dir * i)
-}
+type Wire = Vec<(Direction, isize)>;
 
-fn iter_wire_positions<'a>(wire: &'a [&str]) -> impl Iterator<Item = Position> + 'a {
-    let mut current = Position::ZERO;
+fn iter_wire_positions(wire: &[(Direction, isize)]) -> impl Iterator<Item = Point> + '_ {
+    let mut current = Point::ZERO;
 
-    wire.iter()
-        .map(|seg| Position::from_seg(seg))
-        .flat_map(move |seg| {
-            let it = iter_steps_in_dir(current, seg);
-            current = current + seg;
-            it
-        })
+    wire.iter().flat_map(move |&(dir, len)| {
+        let start = current;
+        current += dir.offset() * len;
+        (1..=len).map(move |i| start + dir.offset() * i)
+    })
 }
 
-fn get_positions(wire: &[&str]) -> HashSet<Position> {
+fn get_positions(wire: &[(Direction, isize)]) -> HashSet<Point> {
     iter_wire_positions(wire).collect::<HashSet<_>>()
 }
 
-fn get_wires(i: &Input) -> Vec<Vec<&str>> {
-    i.map_lines(|l| l.split(',').collect()).collect()
+fn get_wires(i: &Input) -> Result<Vec<Wire>, Box<dyn Error>> {
+    i.map_lines(|l| l.split(',').map(parse_segment).collect())
+        .collect()
 }
 
 pub fn first(i: &Input) -> Result<String, Box<dyn Error>> {
-    let wires = get_wires(i);
+    let wires = get_wires(i)?;
 
     let mut seen = HashSet::new();
     let mut distance = std::isize::MAX;
 
     for wire in wires {
         for pos in get_positions(&wire) {
-            let pos_dist = pos.magnitude();
+            let pos_dist = pos.manhattan();
 
             if !seen.insert(pos) && pos_dist < distance {
                 distance = pos_dist;
@@ -105,7 +55,7 @@ pub fn first(i: &Input) -> Result<String, Box<dyn Error>> {
 }
 
 pub fn second(i: &Input) -> Result<String, Box<dyn Error>> {
-    let wires = get_wires(i);
+    let wires = get_wires(i)?;
 
     let mut positions = HashMap::new();
     let mut distance = std::usize::MAX;
@@ -1,5 +1,5 @@
 use crate::input::Input;
-use std::cmp;
+use crate::lib::search;
 use std::collections::HashMap;
 use std::error::Error;
 
@@ -64,35 +64,30 @@ pub fn first(i: &Input) -> Result<String, Box<dyn Error>> {
     Ok(depths.values().sum::<usize>().to_string())
 }
 
-fn get_path<'a>(orbits: &OrbitMap<'a>, start: Object<'a>) -> Vec<Object<'a>> {
-    let mut path = vec![start];
+type LinkMap<'a> = HashMap<Object<'a>, Vec<Object<'a>>>;
 
-    let mut current = start;
-    while let Some(next) = orbits.get(current) {
-        path.push(next);
-        current = next;
+/// Connect every object to the objects it orbits and is orbited by.
+fn build_link_map<'a>(orbits: &OrbitMap<'a>) -> LinkMap<'a> {
+    let mut links: LinkMap = HashMap::new();
+    for (&satellite, &center) in orbits.iter() {
+        links.entry(satellite).or_default().push(center);
+        links.entry(center).or_default().push(satellite);
     }
 
-    path.reverse();
-
-    path
+    links
 }
 
 pub fn second(i: &Input) -> Result<String, Box<dyn Error>> {
     let orbits = build_orbit_map(orbits_from_input(i)?);
 
-    let path_to_you = get_path(&orbits, "YOU");
-    let path_to_santa = get_path(&orbits, "SAN");
-
-    let min_len = cmp::min(path_to_you.len(), path_to_santa.len());
-    let common = (0..min_len)
-        .take_while(|&i| path_to_you[i] == path_to_santa[i])
-        .last()
-        .ok_or_else(|| "transfer impossible")?;
-    let you_to_common = path_to_you.len() - common - 2;
-    let santa_to_common = path_to_santa.len() - common - 2;
+    let you = orbits.get("YOU").ok_or("YOU isn't orbiting anything")?;
+    let santa = orbits.get("SAN").ok_or("SAN isn't orbiting anything")?;
 
-    Ok((you_to_common + santa_to_common).to_string())
+    let links = build_link_map(&orbits);
+    search::bfs(vec![*you], |obj| links[obj].clone(), |&obj| obj == *santa)
+        .goal_distance()
+        .ok_or_else(|| "transfer impossible".into())
+        .map(|d| d.to_string())
 }
 
 #[cfg(test)]
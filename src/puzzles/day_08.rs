@@ -1,143 +1,122 @@
-use crate::input::Input;
-use colored::{ColoredString, Colorize};
+use crate::input::{Input, Param};
+use crate::lib::grid::Grid;
+use crate::lib::image::encode_png;
+use crate::lib::ocr;
+use crate::lib::render::{Frame, Recording, Style};
+use crate::lib::sif::{self, Pixel, SpaceImage};
+use colored::Color;
 use std::error::Error;
-
-type Pixel = usize;
-type Row = Vec<Pixel>;
-type Layer = Vec<Row>;
-type Image = Vec<Layer>;
-
-fn read_layer(pixels: &[Pixel], width: usize, height: usize) -> Layer {
-    pixels
-        .chunks_exact(width)
-        .map(|row| row.to_vec())
-        .take(height)
-        .collect::<Vec<_>>()
+use std::fs;
+
+const WIDTH: Param = Param::new("width", "25", "Width of the image in pixels");
+const HEIGHT: Param = Param::new("height", "6", "Height of the image in pixels");
+const PNG: Param = Param::new("png", "", "file to write the decoded image to as PNG");
+const PBM: Param = Param::new("pbm", "", "file to write the decoded image to as plain PBM");
+const ENCODE: Param = Param::new(
+    "encode",
+    "",
+    "plain PBM file to encode as space image, it's written to the file named by sif",
+);
+const LAYERS: Param = Param::new("layers", "100", "Number of layers of the encoded image");
+const SIF: Param = Param::new("sif", "", "file to write the encoded image to");
+
+pub const PARAMS: &[Param] = &[WIDTH, HEIGHT, PNG, PBM, ENCODE, LAYERS, SIF, ocr::RAW];
+
+fn parse_image(i: &Input, width: usize, height: usize) -> Result<SpaceImage, Box<dyn Error>> {
+    SpaceImage::parse(&i.raw, width, height)
 }
 
-fn read_image(pixels: &[Pixel], width: usize, height: usize) -> Image {
-    let mut image = Vec::new();
-
-    let pixels_per_layer = width * height;
-
-    let mut index = 0;
-    while index < pixels.len() {
-        let layer = read_layer(&pixels[index..], width, height);
-        image.push(layer);
-        index += pixels_per_layer;
-    }
-
-    image
-}
-
-fn parse_input(i: &Input) -> Option<Vec<Pixel>> {
-    i.raw
-        .trim()
-        .chars()
-        .map(|c| c.to_digit(10).map(|v| v as usize))
-        .collect()
+fn calc_first_part(i: &Input, width: usize, height: usize) -> Result<usize, Box<dyn Error>> {
+    let image = parse_image(i, width, height)?;
+    let stats = image.stats();
+    let layer = stats
+        .iter()
+        .min_by_key(|s| s.count(0))
+        .ok_or("image has no layers")?;
+
+    Ok(layer.count(1) * layer.count(2))
 }
 
-fn image_from_input(i: &Input, width: usize, height: usize) -> Option<Image> {
-    parse_input(&i).map(|pixels| read_image(&pixels, width, height))
+pub fn first(i: &Input) -> Result<String, Box<dyn Error>> {
+    calc_first_part(i, WIDTH.get(i)?, HEIGHT.get(i)?).map(|v| v.to_string())
 }
 
-fn calc_first_part(i: &Input, width: usize, height: usize) -> Result<usize, Box<dyn Error>> {
-    let image = image_from_input(i, width, height).ok_or("invalid image input")?;
-
-    let mut lowest_zero = std::usize::MAX;
-    let mut score = 0;
-
-    for layer in image {
-        let mut count0 = 0;
-        let mut count1 = 0;
-        let mut count2 = 0;
-
-        for pixel in layer.iter().flatten() {
-            match pixel {
-                0 => count0 += 1,
-                1 => count1 += 1,
-                2 => count2 += 1,
-                _ => (),
-            }
-        }
-
-        if count0 < lowest_zero {
-            lowest_zero = count0;
-            score = count1 * count2;
-        }
+fn pixel_style(pixel: &Pixel) -> Style {
+    let style = Style::plain(std::char::from_digit(*pixel as u32, 10).unwrap_or('?'));
+    match *pixel {
+        sif::BLACK => style.bg(Color::Black).dimmed(),
+        sif::WHITE => style.bg(Color::White),
+        _ => style,
     }
-
-    Ok(score)
 }
 
-pub fn first(i: &Input) -> Result<String, Box<dyn Error>> {
-    calc_first_part(i, 25, 6).map(|v| v.to_string())
+fn render_layer(layer: &Grid<Pixel>) -> Frame {
+    Frame::from_grid(layer, pixel_style)
 }
 
-const COLOR_TRANSPARENT: usize = 2;
-
-fn merge_layers(img: Image, width: usize, height: usize) -> Layer {
-    let mut final_layer: Layer = Vec::with_capacity(height);
-
-    for row_index in 0..height {
-        let mut row = Vec::with_capacity(width);
-        for width_index in 0..width {
-            let mut color = COLOR_TRANSPARENT;
-            for layer in img.iter() {
-                color = layer[row_index][width_index];
-                if color != COLOR_TRANSPARENT {
-                    break;
-                }
-            }
-
-            row.push(color);
-        }
-
-        final_layer.push(row);
-    }
+/// Write the decoded image to the files named by the export parameters.
+fn export(i: &Input, image: &SpaceImage) -> Result<(), Box<dyn Error>> {
+    PNG.write_file(i, || encode_png(&image.to_image(1)))?;
+    PBM.write_file(i, || image.to_pbm())?;
 
-    final_layer
+    Ok(())
 }
 
-fn write_layer(writer: &mut impl std::fmt::Write, layer: Layer) -> Result<(), Box<dyn Error>> {
-    for row in layer {
-        for pixel in row {
-            let mut text: ColoredString = pixel.to_string().as_str().into();
-            match pixel {
-                0 => text = text.on_black().dimmed(),
-                1 => text = text.on_white(),
-                _ => (),
-            }
-
-            write!(writer, "{}", text)?;
-        }
+/// Encode the picture named by the parameter and write it to the file named by `sif`.
+fn encode(i: &Input) -> Result<(), Box<dyn Error>> {
+    let path: String = ENCODE.get(i)?;
+    if path.is_empty() {
+        return Ok(());
+    }
 
-        writer.write_char('\n')?;
+    let text = fs::read_to_string(&path)
+        .map_err(|e| format!("couldn't read {} {}: {}", ENCODE.name, path, e))?;
+    let image = SpaceImage::encode(&sif::parse_pbm(&text)?, LAYERS.get(i)?)?;
+    if !SIF.write_file(i, || image.to_string())? {
+        return Err(format!("{} needs a file to write the image to in {}", ENCODE.name, SIF.name).into());
     }
 
     Ok(())
 }
 
 fn calc_second(i: &Input, width: usize, height: usize) -> Result<String, Box<dyn Error>> {
-    let image = image_from_input(i, width, height).ok_or("invalid image input")?;
-    let layer = merge_layers(image, width, height);
-
-    let mut out = String::from("\n");
-    if let Err(e) = write_layer(&mut out, layer) {
-        return Err(e);
+    encode(i)?;
+    let image = parse_image(i, width, height)?;
+    export(i, &image)?;
+    if ocr::wants_raw(i)? {
+        return Ok(format!("\n{}", render_layer(&image.composite()).colored()));
     }
 
-    Ok(out)
+    ocr::recognize(&image.bitmap())
 }
 
 pub fn second(i: &Input) -> Result<String, Box<dyn Error>> {
-    calc_second(i, 25, 6)
+    calc_second(i, WIDTH.get(i)?, HEIGHT.get(i)?)
+}
+
+/// Show every layer on its own for the first part.
+/// For the second part the layers are stacked one after another.
+pub fn visualize(i: &Input, second: bool) -> Result<Recording, Box<dyn Error>> {
+    let image = parse_image(i, WIDTH.get(i)?, HEIGHT.get(i)?)?;
+
+    let mut rec = Recording::new();
+    for n in 0..image.layers().len() {
+        let (layer, caption) = if second {
+            (image.composite_layers(n + 1), format!("Layers 1-{}", n + 1))
+        } else {
+            (image.layers()[n].clone(), format!("Layer {}", n + 1))
+        };
+
+        rec.push(render_layer(&layer).with_caption(caption));
+    }
+
+    Ok(rec)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::input::temp_path;
 
     #[test]
     fn test_first() -> Result<(), Box<dyn Error>> {
@@ -145,4 +124,75 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_second() -> Result<(), Box<dyn Error>> {
+        let art = "
+            .##..#..#.
+            #..#.#..#.
+            #....####.
+            #....#..#.
+            #..#.#..#.
+            .##..#..#.
+        ";
+        let bitmap = Grid::from_input(&Input::new(art), |c| Some(c == '#'))?;
+        let image = SpaceImage::encode(&bitmap, 3)?;
+        let text = image.to_string();
+        assert_eq!(calc_second(&Input::new(&text), 10, 6)?, "CH");
+
+        let raw = calc_second(&Input::new(&text).with_param("raw", "true"), 10, 6)?;
+        assert_eq!(raw.lines().count(), 7);
+
+        let pbm = temp_path("image.pbm");
+        let input = Input::new(&text).with_param("pbm", &pbm.to_string_lossy());
+        calc_second(&input, 10, 6)?;
+        let exported = fs::read_to_string(&pbm)?;
+        fs::remove_file(&pbm)?;
+        assert_eq!(sif::parse_pbm(&exported)?, bitmap);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_encode() -> Result<(), Box<dyn Error>> {
+        let (pbm, image) = (temp_path("picture.pbm"), temp_path("picture.sif"));
+        fs::write(&pbm, "P1\n# H\n3 2\n010\n000\n")?;
+        let input = Input::new("0222112222120000")
+            .with_param("encode", &pbm.to_string_lossy())
+            .with_param("layers", "4");
+        let missing_sif = encode(&input);
+
+        encode(&input.with_param("sif", &image.to_string_lossy()))?;
+        let encoded = SpaceImage::parse(&fs::read_to_string(&image)?, 3, 2)?;
+        fs::remove_file(&pbm)?;
+        fs::remove_file(&image)?;
+
+        assert!(missing_sif.is_err());
+        assert_eq!(encoded.layers().len(), 4);
+        let white: Vec<_> = encoded.bitmap().iter().map(|(_, &w)| w).collect();
+        assert_eq!(white, vec![true, false, true, true, true, true]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_visualize() -> Result<(), Box<dyn Error>> {
+        let input = Input::new("0222112222120000")
+            .with_param("width", "2")
+            .with_param("height", "2");
+
+        let rec = visualize(&input, true)?;
+        let frames: Vec<_> = rec.frames().iter().map(|f| f.plain()).collect();
+        assert_eq!(
+            frames,
+            vec![
+                "02\n22\nLayers 1-1\n",
+                "01\n22\nLayers 1-2\n",
+                "01\n12\nLayers 1-3\n",
+                "01\n10\nLayers 1-4\n",
+            ]
+        );
+
+        Ok(())
+    }
 }
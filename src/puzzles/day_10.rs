@@ -1,15 +1,34 @@
-use crate::input::Input;
-use std::cmp;
-use std::collections::HashSet;
+use crate::input::{Input, Param};
+use std::cmp::{self, Ordering};
+use std::collections::{HashMap, HashSet};
 use std::error::Error;
 
+const VISIBILITY: Param = Param::new(
+    "visibility",
+    "",
+    "file to write the map with the number of asteroids every asteroid can see to",
+);
+
+pub const PARAMS: &[Param] = &[VISIBILITY];
+
 /// Represents coordinates
 type Coords = (usize, usize);
 type RelCoords = (isize, isize);
 
-/// Get the angle between the direction and straight up.
-fn rel_angle(rel: RelCoords) -> f32 {
-    (rel.0 as f32).atan2(rel.1 as f32)
+/// Whether the direction points to the left half, straight down included.
+/// Straight up belongs to the right half where the laser starts.
+fn is_left_half(rel: RelCoords) -> bool {
+    rel.0 < 0 || (rel.0 == 0 && rel.1 > 0)
+}
+
+/// Compare directions by their clockwise angle from straight up (y grows downwards).
+/// Only integer arithmetic is used so nearly collinear directions are still told apart.
+fn cmp_angle(a: RelCoords, b: RelCoords) -> Ordering {
+    is_left_half(a).cmp(&is_left_half(b)).then_with(|| {
+        // b is clockwise of a if the cross product is positive
+        let cross = a.0 * b.1 - a.1 * b.0;
+        0.cmp(&cross)
+    })
 }
 
 fn coords_to_rel(c: Coords) -> RelCoords {
@@ -131,69 +150,108 @@ fn parse_input(i: &Input) -> (AsteroidMap, Coords) {
     (map, (max_x + 1, max_y + 1))
 }
 
+/// Count the asteroids every asteroid can see.
+fn visibility_map(map: &AsteroidMap, size: Coords) -> HashMap<Coords, usize> {
+    map.iter()
+        .map(|&pos| (pos, ray_cast_from_pos(map, size, pos).count()))
+        .collect()
+}
+
+/// Draw the map with the number of visible asteroids in place of every asteroid.
+fn format_visibility_map(visible: &HashMap<Coords, usize>, size: Coords) -> String {
+    let cell_width = visible
+        .values()
+        .map(|count| count.to_string().len())
+        .max()
+        .unwrap_or(1);
+
+    let mut out = String::new();
+    for y in 0..size.1 {
+        let row: Vec<_> = (0..size.0)
+            .map(|x| match visible.get(&(x, y)) {
+                Some(count) => format!("{:>1$}", count, cell_width),
+                None => format!("{:>1$}", ".", cell_width),
+            })
+            .collect();
+        out.push_str(&row.join(" "));
+        out.push('\n');
+    }
+
+    out
+}
+
 /// Find the coordinates of the asteroid with the most visible other asteroids.
 /// Returns the amount of other asteroids and its position.
 fn find_most_visible_asteroids(map: &AsteroidMap, size: Coords) -> (usize, Coords) {
-    let mut max = 0;
-    let mut max_pos = (0, 0);
-
-    for &pos in map {
-        let c = ray_cast_from_pos(map, size, pos).count();
-        if c > max {
-            max = c;
-            max_pos = pos;
-        }
-    }
-
-    (max, max_pos)
+    let visible = visibility_map(map, size);
+    visible
+        .iter()
+        // break ties by position so the result doesn't depend on the hash order
+        .max_by_key(|&(&(x, y), &count)| (count, cmp::Reverse((y, x))))
+        .map_or((0, (0, 0)), |(&pos, &count)| (count, pos))
 }
 
 pub fn first(i: &Input) -> Result<String, Box<dyn Error>> {
     let (map, size) = parse_input(i);
-    let (count, _) = find_most_visible_asteroids(&map, size);
+    let visible = visibility_map(&map, size);
+
+    VISIBILITY.write_file(i, || format_visibility_map(&visible, size))?;
 
+    let count = visible.values().max().copied().unwrap_or(0);
     Ok(count.to_string())
 }
 
-/// Get the coordinates of the nth asteroid destroyed by a laser rotation clockwise.
-fn get_nth_lasered_asteroid(
-    map: &mut AsteroidMap,
-    size: Coords,
-    start: Coords,
-    n: usize,
-) -> Coords {
-    let mut hits: Vec<_>;
-    let mut total = 0;
-
-    // loop through as many rotations of the laser as required to reach a total of n hits
-    loop {
-        hits = ray_cast_from_pos(map, size, start).collect();
-        total += hits.len();
-        if total >= n {
-            break;
-        }
+/// An asteroid hit by the laser and the rotation of the laser (starting at 1) which hit it.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct Vaporized {
+    pos: Coords,
+    rotation: usize,
+}
 
-        for pos in hits {
-            map.remove(&pos);
-        }
+/// Get every asteroid in the order the laser at `station` vaporizes them.
+/// The laser starts pointing up and rotates clockwise, hitting the closest asteroid in every
+/// direction per rotation.
+fn vaporization_order(map: &AsteroidMap, station: Coords) -> Vec<Vaporized> {
+    let mut by_direction: HashMap<RelCoords, Vec<Coords>> = HashMap::new();
+    for &pos in map.iter().filter(|&&pos| pos != station) {
+        let dir = get_direction(sub_coords(pos, station));
+        by_direction.entry(dir).or_default().push(pos);
     }
 
-    // sort all hits by their angle from 12 o'clock
-    hits.sort_by(|&p1, &p2| {
-        let a1 = rel_angle(sub_coords(p1, start));
-        let a2 = rel_angle(sub_coords(p2, start));
+    let mut order = Vec::with_capacity(map.len());
+    for (&dir, asteroids) in &mut by_direction {
+        asteroids.sort_by_key(|&pos| {
+            let rel = sub_coords(pos, station);
+            rel.0.abs() + rel.1.abs()
+        });
+        order.extend(asteroids.iter().enumerate().map(|(n, &pos)| {
+            (
+                dir,
+                Vaporized {
+                    pos,
+                    rotation: n + 1,
+                },
+            )
+        }));
+    }
 
-        a1.partial_cmp(&a2).unwrap()
+    order.sort_by(|(a_dir, a), (b_dir, b)| {
+        a.rotation
+            .cmp(&b.rotation)
+            .then_with(|| cmp_angle(*a_dir, *b_dir))
     });
-
-    hits[total - n]
+    order.into_iter().map(|(_, v)| v).collect()
 }
 
 pub fn second(i: &Input) -> Result<String, Box<dyn Error>> {
-    let (mut map, size) = parse_input(i);
+    let (map, size) = parse_input(i);
     let (_, pos) = find_most_visible_asteroids(&map, size);
 
-    let last_asteroid = get_nth_lasered_asteroid(&mut map, size, pos, 200);
+    let order = vaporization_order(&map, pos);
+    let last_asteroid = order
+        .get(199)
+        .ok_or_else(|| format!("only {} asteroids are vaporized", order.len()))?
+        .pos;
 
     Ok((100 * last_asteroid.0 + last_asteroid.1).to_string())
 }
@@ -309,9 +367,66 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_cmp_angle() {
+        let mut dirs = vec![(-1, -1), (0, 1), (1, 0), (-1, 0), (1, -1), (0, -1), (1, 1), (-1, 1)];
+        dirs.sort_by(|&a, &b| cmp_angle(a, b));
+        assert_eq!(
+            dirs,
+            vec![(0, -1), (1, -1), (1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0), (-1, -1)]
+        );
+
+        // these differ by less than the precision of an f32 angle
+        let (a, b) = ((1, -100_000_000), (1, -99_999_999));
+        assert_eq!(cmp_angle(a, b), Ordering::Less);
+        assert_eq!(cmp_angle(b, a), Ordering::Greater);
+        assert_eq!(cmp_angle(a, a), Ordering::Equal);
+    }
+
+    #[test]
+    fn test_vaporization_order() {
+        let (map, _) = parse_input(&Input::new(
+            "
+            .#....#####...#..
+            ##...##.#####..##
+            ##...#...#.#####.
+            ..#.....#...###..
+            ..#.#.....#....##
+            ",
+        ));
+        let order = vaporization_order(&map, (8, 3));
+        let first: Vec<_> = order.iter().take(9).map(|v| v.pos).collect();
+        assert_eq!(
+            first,
+            vec![(8, 1), (9, 0), (9, 1), (10, 0), (9, 2), (11, 1), (12, 1), (11, 2), (15, 1)]
+        );
+        assert_eq!(order.len(), map.len() - 1);
+        assert_eq!(order.last(), Some(&Vaporized { pos: (14, 3), rotation: 3 }));
+        assert!(order.windows(2).all(|w| w[0].rotation <= w[1].rotation));
+    }
+
+    #[test]
+    fn test_visibility_map() {
+        let (map, size) = parse_input(&Input::new(
+            "
+            .#..#
+            .....
+            #####
+            ....#
+            ...##
+            ",
+        ));
+        let visible = visibility_map(&map, size);
+        assert_eq!(
+            format_visibility_map(&visible, size),
+            ". 7 . . 7\n. . . . .\n6 7 7 7 5\n. . . . 7\n. . . 8 7\n"
+        );
+        assert_eq!(find_most_visible_asteroids(&map, size), (8, (3, 4)));
+    }
+
     #[test]
     fn test_laser() -> Result<(), Box<dyn Error>> {
-        let (mut map, size) = parse_input(&Input::new(
+        let (map, _) = parse_input(&Input::new(
             "
             .#..##.###...#######
             ##.############..##.
@@ -335,10 +450,11 @@ mod tests {
             ###.##.####.##.#..##
             ",
         ));
-        assert_eq!(
-            get_nth_lasered_asteroid(&mut map, size, (11, 13), 200),
-            (8, 2)
-        );
+        let order = vaporization_order(&map, (11, 13));
+        assert_eq!(order.len(), 299);
+        assert_eq!(order[0].pos, (11, 12));
+        assert_eq!(order[199].pos, (8, 2));
+        assert_eq!(order[298], Vaporized { pos: (11, 1), rotation: 12 });
 
         Ok(())
     }
@@ -1,12 +1,28 @@
-use crate::input::Input;
+use crate::input::{Input, Param};
+use crate::lib::grid::{Grid, Point, Rect, SparseGrid};
 use crate::lib::intcode::{Code, Machine};
-use colored::{ColoredString, Colorize};
-use std::collections::HashMap;
+use crate::lib::ocr;
+use crate::lib::render::{Frame, Recording, Style};
+use colored::Color as TermColor;
+use std::collections::{HashMap, HashSet};
 use std::error::Error;
 use std::fmt::Write;
+use std::fs;
+
+const CANVAS: Param = Param::new(
+    "canvas",
+    "",
+    "text file with the panels to start on (# white, . black, ^ robot on a black panel)",
+);
+const TIMELINE: Param = Param::new(
+    "timeline",
+    "",
+    "file to write the paint and move events of the robot to as CSV",
+);
+
+pub const PARAMS: &[Param] = &[CANVAS, TIMELINE, ocr::RAW];
 
 type Vec2 = (isize, isize);
-type Rect = (Vec2, Vec2);
 type Color = u8;
 
 const BLACK: Color = 0;
@@ -25,28 +41,138 @@ fn vec2_rot(v: Vec2, rots: i8) -> Vec2 {
     (v.0 * c - v.1 * s, v.0 * s + v.1 * c)
 }
 
+fn color_name(color: Color) -> &'static str {
+    match color {
+        BLACK => "black",
+        WHITE => "white",
+        _ => "unknown",
+    }
+}
+
+fn direction_name(direction: Vec2) -> &'static str {
+    match direction {
+        (0, 1) => "up",
+        (1, 0) => "right",
+        (0, -1) => "down",
+        _ => "left",
+    }
+}
+
+/// Panels painted before the robot starts and where the robot starts.
+struct Canvas {
+    colors: HashMap<Vec2, Color>,
+    start: Vec2,
+}
+
+impl Canvas {
+    /// The robot starts on a single panel of the given color.
+    fn single(color: Color) -> Self {
+        let mut colors = HashMap::new();
+        colors.insert((0, 0), color);
+
+        Self {
+            colors,
+            start: (0, 0),
+        }
+    }
+
+    /// Parse rows of panels, the robot starts at `^` or at the top left panel.
+    fn parse(text: &str) -> Result<Self, Box<dyn Error>> {
+        let mut colors = HashMap::new();
+        let mut start = (0, 0);
+        for (row, line) in text.lines().map(str::trim).filter(|l| !l.is_empty()).enumerate() {
+            for (column, c) in line.chars().enumerate() {
+                // the y axis of the robot points upwards
+                let position = (column as isize, -(row as isize));
+                let color = match c {
+                    '#' => WHITE,
+                    '.' => BLACK,
+                    '^' => {
+                        start = position;
+                        BLACK
+                    }
+                    _ => return Err(format!("unexpected character {:?} in canvas", c).into()),
+                };
+                colors.insert(position, color);
+            }
+        }
+
+        Ok(Self { colors, start })
+    }
+
+    /// Load the canvas given as parameter, or start on a single panel.
+    fn from_param(i: &Input, color: Color) -> Result<Self, Box<dyn Error>> {
+        let path: String = CANVAS.get(i)?;
+        if path.is_empty() {
+            return Ok(Self::single(color));
+        }
+
+        let text = fs::read_to_string(&path)
+            .map_err(|e| format!("couldn't read canvas {}: {}", path, e))?;
+        Self::parse(&text)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Event {
+    Paint { position: Vec2, color: Color },
+    Move { position: Vec2, direction: Vec2 },
+}
+
 struct PaintRobot {
     machine: Machine,
     direction: Vec2,
     position: Vec2,
     pub colors: HashMap<Vec2, Color>,
+    pub events: Vec<Event>,
 }
 
 impl PaintRobot {
-    fn new(machine: Machine, start_panel: Color) -> PaintRobot {
-        let mut colors = HashMap::new();
-        colors.insert((0, 0), start_panel);
-
+    fn new(machine: Machine, canvas: Canvas) -> PaintRobot {
         PaintRobot {
             machine,
-            colors,
+            colors: canvas.colors,
             direction: (0, 1),
-            position: (0, 0),
+            position: canvas.start,
+            events: Vec::new(),
         }
     }
 
     fn from_input(i: &Input, start_panel: Color) -> Result<PaintRobot, Box<dyn Error>> {
-        Machine::from_input(i).map(|m| Self::new(m, start_panel))
+        let canvas = Canvas::from_param(i, start_panel)?;
+        Machine::from_input(i).map(|m| Self::new(m, canvas))
+    }
+
+    /// Panels painted at least once.
+    fn painted(&self) -> usize {
+        let painted: HashSet<_> = self
+            .events
+            .iter()
+            .filter_map(|e| match e {
+                Event::Paint { position, .. } => Some(position),
+                _ => None,
+            })
+            .collect();
+
+        painted.len()
+    }
+
+    /// Events as CSV, steps start at one and every step paints and then moves.
+    fn timeline(&self) -> String {
+        let mut csv = String::from("step,event,x,y,value\n");
+        for (n, event) in self.events.iter().enumerate() {
+            let step = n / 2 + 1;
+            let (name, (x, y), value) = match *event {
+                Event::Paint { position, color } => ("paint", position, color_name(color)),
+                Event::Move {
+                    position,
+                    direction,
+                } => ("move", position, direction_name(direction)),
+            };
+            writeln!(csv, "{},{},{},{},{}", step, name, x, y, value).unwrap();
+        }
+
+        csv
     }
 
     /// Rotates the current direction rots times 90° anti-clockwise.
@@ -71,90 +197,137 @@ impl PaintRobot {
         Ok((color, rots))
     }
 
-    fn run(&mut self) -> Result<(), Box<dyn Error>> {
+    /// Run the robot until it's done painting.
+    /// If a recording is given, a frame is added after every step.
+    fn run(&mut self, mut rec: Option<&mut Recording>) -> Result<(), Box<dyn Error>> {
         self.machine.start();
 
         while !self.machine.is_done() {
+            if let Some(rec) = rec.as_mut() {
+                rec.push(render_panels(
+                    &self.colors,
+                    Some((self.position, self.direction)),
+                ));
+            }
+
             let color = self.colors.get(&self.position).copied().unwrap_or(BLACK);
             self.machine.send(color as Code)?;
 
             let (new_color, rots) = self.get_output()?;
             self.colors.insert(self.position, new_color);
+            self.events.push(Event::Paint {
+                position: self.position,
+                color: new_color,
+            });
             self.rotate(-rots);
             self.step();
+            self.events.push(Event::Move {
+                position: self.position,
+                direction: self.direction,
+            });
         }
 
         Ok(())
     }
 }
 
-pub fn first(i: &Input) -> Result<String, Box<dyn Error>> {
-    let mut robot = PaintRobot::from_input(i, BLACK)?;
-    robot.run()?;
+/// Let the robot paint and export its timeline if requested.
+fn paint(i: &Input, start_panel: Color) -> Result<PaintRobot, Box<dyn Error>> {
+    let mut robot = PaintRobot::from_input(i, start_panel)?;
+    robot.run(None)?;
 
-    Ok(robot.colors.len().to_string())
-}
+    TIMELINE.write_file(i, || robot.timeline())?;
 
-fn get_bounds<T>(map: &HashMap<Vec2, T>) -> Rect {
-    let (mut tl_x, mut tl_y) = (std::isize::MAX, std::isize::MAX);
-    let (mut br_x, mut br_y) = (std::isize::MIN, std::isize::MIN);
+    Ok(robot)
+}
 
-    for &(x, y) in map.keys() {
-        if x < tl_x {
-            tl_x = x;
-        }
-        if x > br_x {
-            br_x = x;
-        }
+pub fn first(i: &Input) -> Result<String, Box<dyn Error>> {
+    Ok(paint(i, BLACK)?.painted().to_string())
+}
 
-        if y < tl_y {
-            tl_y = y;
-        }
-        if y > br_y {
-            br_y = y;
-        }
+fn panel_style(color: Option<&Color>) -> Style {
+    let color = color.copied().unwrap_or(BLACK);
+    let style = Style::plain(char::from(b'0' + color));
+    match color {
+        BLACK => style.fg(TermColor::Black).bg(TermColor::Black),
+        WHITE => style.fg(TermColor::White).bg(TermColor::White),
+        _ => style,
     }
-
-    ((tl_x, tl_y), (br_x, br_y))
 }
 
-fn format_color_map(colors: &HashMap<Vec2, Color>, bounds: Rect) -> String {
-    let (tl, br) = bounds;
+fn robot_style(direction: Vec2) -> Style {
+    let c = match direction {
+        (0, 1) => '^',
+        (1, 0) => '>',
+        (0, -1) => 'v',
+        _ => '<',
+    };
 
-    let mut s = String::new();
+    Style::plain(c).fg(TermColor::Red).bold()
+}
 
-    for y in (tl.1..=br.1).rev() {
-        for x in tl.0..=br.0 {
-            let color = colors.get(&(x, y)).copied().unwrap_or(BLACK);
+/// Render the painted panels, optionally with the robot on top.
+fn render_panels(colors: &HashMap<Vec2, Color>, robot: Option<(Vec2, Vec2)>) -> Frame {
+    // the robot uses a y axis pointing upwards, frames have it pointing downwards
+    let to_point = |(x, y): Vec2| Point::new(x, -y);
 
-            let mut text: ColoredString = color.to_string().as_str().into();
-            match color {
-                BLACK => text = text.on_black().black(),
-                WHITE => text = text.on_white().white(),
-                _ => (),
-            }
-            write!(&mut s, "{}", text).unwrap();
+    let mut panels: SparseGrid<Color> = colors.iter().map(|(&v, &c)| (to_point(v), c)).collect();
+    if let Some((position, _)) = robot {
+        let p = to_point(position);
+        if !panels.contains(p) {
+            panels.insert(p, BLACK);
         }
+    }
+
+    let mut frame = Frame::from_sparse(&panels, panel_style);
+    if let (Some((position, direction)), Some(bounds)) = (robot, panels.bounds()) {
+        frame.set(to_point(position) - bounds.min, robot_style(direction));
+    }
+
+    frame
+}
 
-        s.write_char('\n').unwrap();
+/// White panels as pixels, the top left pixel is the top left white panel.
+fn white_pixels(colors: &HashMap<Vec2, Color>) -> Grid<bool> {
+    let white: Vec<_> = colors
+        .iter()
+        .filter(|&(_, &c)| c == WHITE)
+        .map(|(&(x, y), _)| Point::new(x, -y))
+        .collect();
+
+    let bounds = match Rect::from_points(white.iter().copied()) {
+        Some(bounds) => bounds,
+        None => return Grid::new(0, 0, false),
+    };
+    let mut pixels = Grid::new(bounds.width(), bounds.height(), false);
+    for p in white {
+        pixels[p - bounds.min] = true;
     }
 
-    s
+    pixels
 }
 
 pub fn second(i: &Input) -> Result<String, Box<dyn Error>> {
-    let mut robot = PaintRobot::from_input(i, WHITE)?;
-    robot.run()?;
+    let robot = paint(i, WHITE)?;
+    if ocr::wants_raw(i)? {
+        return Ok(format!("\n{}", render_panels(&robot.colors, None).colored()));
+    }
 
-    let colors = robot.colors;
-    let bounds = get_bounds(&colors);
+    ocr::recognize(&white_pixels(&robot.colors))
+}
 
-    Ok(format!("\n{}", format_color_map(&colors, bounds)))
+pub fn visualize(i: &Input, second: bool) -> Result<Recording, Box<dyn Error>> {
+    let mut robot = PaintRobot::from_input(i, if second { WHITE } else { BLACK })?;
+    let mut rec = Recording::new();
+    robot.run(Some(&mut rec))?;
+
+    Ok(rec)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::input::temp_path;
 
     #[test]
     fn test_vec_rot() {
@@ -170,4 +343,84 @@ mod tests {
         assert_eq!(vec2_rot((0, -1), -1), (-1, 0));
         assert_eq!(vec2_rot((-1, 0), -1), (0, 1));
     }
+
+    /// Straight line program which reads the panel color before printing every pair.
+    fn program(outputs: &[(Code, Code)]) -> String {
+        let mut code = Vec::new();
+        for (color, turn) in outputs {
+            code.push(format!("3,100,104,{},104,{}", color, turn));
+        }
+        code.push("99".to_string());
+        code.join(",")
+    }
+
+    #[test]
+    fn test_events() -> Result<(), Box<dyn Error>> {
+        let timeline = temp_path("timeline.csv");
+        // paint white and turn left, paint black and turn right, paint the first panel again
+        let input = Input::new(&program(&[(1, 0), (0, 1), (0, 1), (1, 1), (1, 0)]))
+            .with_param("timeline", &timeline.to_string_lossy());
+
+        assert_eq!(first(&input)?, "4");
+        let csv = fs::read_to_string(&timeline)?;
+        fs::remove_file(&timeline)?;
+        assert_eq!(
+            csv,
+            "step,event,x,y,value
+1,paint,0,0,white
+1,move,-1,0,left
+2,paint,-1,0,black
+2,move,-1,1,up
+3,paint,-1,1,black
+3,move,0,1,right
+4,paint,0,1,white
+4,move,0,0,down
+5,paint,0,0,white
+5,move,1,0,right
+"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_canvas() -> Result<(), Box<dyn Error>> {
+        let canvas = Canvas::parse(
+            "
+            #..
+            .^#
+            ",
+        )?;
+        assert_eq!(canvas.start, (1, -1));
+        assert_eq!(canvas.colors[&(0, 0)], WHITE);
+        assert_eq!(canvas.colors[&(1, -1)], BLACK);
+        assert_eq!(canvas.colors[&(2, -1)], WHITE);
+        assert!(Canvas::parse("#x").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_second_reads_letters() -> Result<(), Box<dyn Error>> {
+        let path = temp_path("canvas.txt");
+        fs::write(
+            &path,
+            "
+            #....#..#
+            #....#..#
+            #....####
+            #....#..#
+            #..^.#..#
+            ####.#..#
+            ",
+        )?;
+
+        // the robot only paints the panel it starts on black again
+        let input = Input::new(&program(&[(0, 1)])).with_param("canvas", &path.to_string_lossy());
+        let result = second(&input);
+        fs::remove_file(&path)?;
+        assert_eq!(result?, "LH");
+
+        Ok(())
+    }
 }
@@ -1,242 +1,199 @@
-use crate::input::Input;
+use crate::input::{Input, Param};
+use crate::lib::grid::{Point, Rect};
+use crate::lib::nbody::{Rules, System};
+use crate::lib::render::{Frame, Recording, Style};
+use colored::Color;
 use std::error::Error;
-use std::fmt;
-use std::ops;
-
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
-struct Vec3 {
-    x: isize,
-    y: isize,
-    z: isize,
-}
-
-impl Vec3 {
-    const ZERO: Vec3 = Vec3::new(0, 0, 0);
-
-    const fn new(x: isize, y: isize, z: isize) -> Self {
-        Self { x, y, z }
-    }
-
-    fn from_string(mut s: &str) -> Result<Self, Box<dyn Error>> {
-        s = s.trim_start_matches('<').trim_end_matches('>');
-        let parts = s
-            .split(',')
-            .map(|s| {
-                let raw = s.trim().split('=').last().ok_or_else(|| "invalid format")?;
-                raw.parse().map_err(|_| "parse error")
-            })
-            .take(3)
-            .collect::<Result<Vec<isize>, _>>()?;
-
-        if parts.len() != 3 {
-            return Err("invalid format".into());
-        }
 
-        Ok(Self::new(parts[0], parts[1], parts[2]))
-    }
-
-    fn abs_x(&self) -> usize {
-        self.x.abs() as usize
-    }
-
-    fn abs_y(&self) -> usize {
-        self.y.abs() as usize
-    }
-
-    fn abs_z(&self) -> usize {
-        self.z.abs() as usize
-    }
-
-    fn abs_component_sum(&self) -> usize {
-        self.abs_x() + self.abs_y() + self.abs_z()
-    }
+const STEPS: Param = Param::new("steps", "1000", "Number of steps to simulate");
+const GRAVITY: Param = Param::new(
+    "gravity",
+    "unit",
+    "how moons pull each other (unit, scaled:STRENGTH or spring:DIVISOR)",
+);
+const SPEED_LIMIT: Param = Param::new(
+    "speed_limit",
+    "",
+    "largest speed of a moon along an axis (unlimited if empty)",
+);
+const MAX_STEPS: Param = Param::new(
+    "max_steps",
+    "10000000",
+    "give up looking for a repeated state after this many steps along an axis",
+);
+const TRAJECTORY: Param = Param::new(
+    "trajectory",
+    "",
+    "file to write the positions and velocities of the moons during the first part to as CSV",
+);
+
+pub const PARAMS: &[Param] = &[STEPS, GRAVITY, SPEED_LIMIT, MAX_STEPS, TRAJECTORY];
+
+fn parse_rules(i: &Input) -> Result<Rules, Box<dyn Error>> {
+    let limit: String = SPEED_LIMIT.get(i)?;
+    let speed_limit = if limit.is_empty() {
+        None
+    } else {
+        let limit = limit
+            .parse()
+            .map_err(|_| format!("invalid value for parameter speed_limit: {}", limit))?;
+        Some(limit)
+    };
+
+    Ok(Rules {
+        gravity: GRAVITY.get(i)?,
+        speed_limit,
+    })
+}
+
+fn parse_input(i: &Input) -> Result<System, Box<dyn Error>> {
+    System::parse(&i.raw, parse_rules(i)?)
 }
 
-impl fmt::Display for Vec3 {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "<x=")?;
-        self.x.fmt(f)?;
-        write!(f, ", y=")?;
-        self.y.fmt(f)?;
-        write!(f, ", z=")?;
-        self.z.fmt(f)?;
-
-        write!(f, ">")?;
+pub fn first(i: &Input) -> Result<String, Box<dyn Error>> {
+    let mut system = parse_input(i)?;
+    let steps = STEPS.get(i)?;
 
-        Ok(())
+    if !TRAJECTORY.write_file(i, || system.record_trajectory(steps))? {
+        system.run(steps);
     }
-}
 
-impl ops::AddAssign for Vec3 {
-    fn add_assign(&mut self, rhs: Self) {
-        self.x += rhs.x;
-        self.y += rhs.y;
-        self.z += rhs.z;
-    }
+    Ok(system.total_energy().to_string())
 }
 
-#[derive(Clone, Debug, Eq, PartialEq)]
-struct Moon {
-    position: Vec3,
-    velocity: Vec3,
-}
-
-impl Moon {
-    fn new(pos: Vec3) -> Self {
-        Self {
-            position: pos,
-            velocity: Vec3::ZERO,
+/// Number of steps until the moons are in a state they've already been in.
+pub fn second(i: &Input) -> Result<String, Box<dyn Error>> {
+    let cycle = parse_input(i)?.find_cycle(MAX_STEPS.get(i)?)?;
+    Ok((cycle.start + cycle.period).to_string())
+}
+
+const CANVAS_WIDTH: usize = 60;
+const CANVAS_HEIGHT: usize = 30;
+/// Number of previous positions drawn behind each moon.
+const TRAIL_LENGTH: usize = 8;
+const MOON_COLORS: [Color; 4] = [Color::Red, Color::Green, Color::Yellow, Color::Cyan];
+
+/// Scale a coordinate from the range `min..=max` to a cell index below `size`.
+fn scale(v: isize, min: isize, max: isize, size: usize) -> isize {
+    (v - min) * (size as isize - 1) / (max - min).max(1)
+}
+
+/// Show the moons from above (x and y) for the first part and from the side (x and z) for the second.
+/// The canvas covers all positions during the simulation so the view doesn't move.
+pub fn visualize(i: &Input, second: bool) -> Result<Recording, Box<dyn Error>> {
+    let mut system = parse_input(i)?;
+    let dimensions = system.axes().len();
+    if dimensions == 0 {
+        return Err("moons have no position".into());
+    }
+    let axis = if second { 2 } else { 1 }.min(dimensions - 1);
+    let project = |v: &[isize]| (v[0], v[axis]);
+
+    let mut history = vec![system.bodies().to_vec()];
+    for _ in 0..STEPS.get(i)? {
+        system.step();
+        history.push(system.bodies().to_vec());
+    }
+
+    let points = history
+        .iter()
+        .flatten()
+        .map(|m| project(&m.position))
+        .map(|(a, b)| Point::new(a, b));
+    let bounds = Rect::from_points(points).ok_or("no moons to show")?;
+    let to_cell = |v: &[isize]| {
+        let (a, b) = project(v);
+        Point::new(
+            scale(a, bounds.min.x, bounds.max.x, CANVAS_WIDTH),
+            scale(b, bounds.min.y, bounds.max.y, CANVAS_HEIGHT),
+        )
+    };
+
+    let mut rec = Recording::new();
+    for (s, moons) in history.iter().enumerate() {
+        let canvas = Rect::with_size(CANVAS_WIDTH, CANVAS_HEIGHT);
+        let mut frame = Frame::from_fn(Some(canvas), |_| Style::plain(' '));
+
+        for past in history[s.saturating_sub(TRAIL_LENGTH)..s].iter() {
+            for (m, color) in past.iter().zip(MOON_COLORS.iter().cycle()) {
+                frame.set(to_cell(&m.position), Style::plain('.').fg(*color).dimmed());
+            }
+        }
+        for (m, color) in moons.iter().zip(MOON_COLORS.iter().cycle()) {
+            frame.set(to_cell(&m.position), Style::plain('O').fg(*color).bold());
         }
-    }
-
-    fn potential_energy(&self) -> usize {
-        self.position.abs_component_sum()
-    }
-
-    fn kinetic_energy(&self) -> usize {
-        self.velocity.abs_component_sum()
-    }
-
-    fn total_energy(&self) -> usize {
-        self.potential_energy() * self.kinetic_energy()
-    }
-
-    fn apply_gravity(ma: &mut Moon, mb: &mut Moon) {
-        let (a_pos, b_pos) = (ma.position, mb.position);
-        let (a_vel, b_vel) = (&mut ma.velocity, &mut mb.velocity);
-
-        let x = (a_pos.x - b_pos.x).signum();
-        a_vel.x -= x;
-        b_vel.x += x;
-
-        let y = (a_pos.y - b_pos.y).signum();
-        a_vel.y -= y;
-        b_vel.y += y;
-
-        let z = (a_pos.z - b_pos.z).signum();
-        a_vel.z -= z;
-        b_vel.z += z;
-    }
-
-    fn apply_velocity(&mut self) {
-        self.position += self.velocity;
-    }
-}
 
-impl fmt::Display for Moon {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "(pos={:3}, vel={:3})", self.position, self.velocity)
+        let caption = format!("Step {}: total energy {}", s, system.energy_history()[s]);
+        rec.push(frame.with_caption(caption));
     }
-}
 
-fn parse_input(i: &Input) -> Result<Vec<Moon>, Box<dyn Error>> {
-    i.map_lines(|l| Vec3::from_string(l).map(Moon::new))
-        .collect()
+    Ok(rec)
 }
 
-fn step(moons: &mut Vec<Moon>) {
-    for i in 1..=moons.len() {
-        let (left, right) = moons.split_at_mut(i);
-        let mut moon_a = left.last_mut().unwrap();
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::temp_path;
+    use std::fs;
 
-        for mut moon_b in right.iter_mut() {
-            Moon::apply_gravity(&mut moon_a, &mut moon_b);
-        }
+    const MOONS: &str = "
+        <x=-1, y=0, z=2>
+        <x=2, y=-10, z=-7>
+        <x=4, y=-8, z=8>
+        <x=3, y=5, z=-1>
+    ";
 
-        moon_a.apply_velocity();
-    }
-}
-fn calc_total_energy(moons: &[Moon]) -> usize {
-    moons.iter().map(Moon::total_energy).sum()
-}
+    #[test]
+    fn test_first() -> Result<(), Box<dyn Error>> {
+        let input = Input::new(MOONS).with_param("steps", "10");
+        assert_eq!(first(&input)?, "179");
 
-fn steps(moons: &mut Vec<Moon>, n: usize) -> usize {
-    for _ in 0..n {
-        step(moons);
+        Ok(())
     }
 
-    calc_total_energy(moons)
-}
-
-pub fn first(i: &Input) -> Result<String, Box<dyn Error>> {
-    let mut moons = parse_input(i)?;
-
-    Ok(steps(&mut moons, 1000).to_string())
-}
-
-fn calc_lcm(a: isize, b: isize) -> isize {
-    fn calc_gcd(a: isize, b: isize) -> isize {
-        if b == 0 {
-            return a;
-        }
-        calc_gcd(b, a % b)
+    #[test]
+    fn test_invalid_rules() {
+        let input = |param, value| Input::new(MOONS).with_param(param, value);
+        assert!(second(&input("speed_limit", "-1")).is_err());
+        assert!(second(&input("speed_limit", "fast")).is_err());
+        assert!(second(&input("gravity", "scaled:-1").with_param("max_steps", "1000")).is_err());
     }
-    (a * b).abs() / calc_gcd(a, b)
-}
-
-type AxisTuples = Vec<(isize, isize)>;
 
-fn get_state_tuples(moons: &[Moon]) -> (AxisTuples, AxisTuples, AxisTuples) {
-    let mut x = Vec::with_capacity(moons.len());
-    let mut y = Vec::with_capacity(moons.len());
-    let mut z = Vec::with_capacity(moons.len());
+    #[test]
+    fn test_trajectory() -> Result<(), Box<dyn Error>> {
+        let path = temp_path("trajectory.csv");
+        let input = Input::new(MOONS)
+            .with_param("steps", "10")
+            .with_param("trajectory", &path.to_string_lossy());
+        let result = first(&input);
+        let csv = fs::read_to_string(&path)?;
+        fs::remove_file(&path)?;
+
+        assert_eq!(result?, "179");
+        assert_eq!(csv.lines().count(), 1 + 11 * 4);
+        assert_eq!(csv.lines().nth(1), Some("0,0,-1,0,2,0,0,0"));
 
-    for m in moons {
-        x.push((m.position.x, m.velocity.x));
-        y.push((m.position.y, m.velocity.y));
-        z.push((m.position.z, m.velocity.z));
+        Ok(())
     }
 
-    (x, y, z)
-}
-
-fn find_total_period(moons: &mut Vec<Moon>) -> usize {
-    let (init_x, init_y, init_z) = get_state_tuples(&moons);
-
-    let mut periods = Vec3::ZERO;
-
-    let mut s = 0;
-    while periods.x == 0 || periods.y == 0 || periods.z == 0 {
-        step(moons);
-        s += 1;
-
-        let (x, y, z) = get_state_tuples(&moons);
-        if periods.x == 0 && x == init_x {
-            periods.x = s;
-        }
+    #[test]
+    fn test_visualize() -> Result<(), Box<dyn Error>> {
+        let input = Input::new(MOONS).with_param("steps", "10");
 
-        if periods.y == 0 && y == init_y {
-            periods.y = s;
-        }
+        for second in [false, true] {
+            let rec = visualize(&input, second)?;
+            assert_eq!(rec.len(), 11);
 
-        if periods.z == 0 && z == init_z {
-            periods.z = s;
+            let last = &rec.frames()[10];
+            assert_eq!((last.width(), last.height()), (CANVAS_WIDTH, CANVAS_HEIGHT));
+            assert_eq!(last.caption, "Step 10: total energy 179");
+            assert!(last.plain().matches('O').count() <= 4);
         }
-    }
-
-    calc_lcm(periods.x, calc_lcm(periods.y, periods.z)) as usize
-}
-
-pub fn second(i: &Input) -> Result<String, Box<dyn Error>> {
-    let mut moons = parse_input(i)?;
-    Ok(find_total_period(&mut moons).to_string())
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
 
-    #[test]
-    fn test_first() -> Result<(), Box<dyn Error>> {
-        let mut moons = parse_input(&Input::new(
-            "
-            <x=-1, y=0, z=2>
-            <x=2, y=-10, z=-7>
-            <x=4, y=-8, z=8>
-            <x=3, y=5, z=-1>
-            ",
-        ))?;
-        assert_eq!(steps(&mut moons, 10), 179);
+        // the first moon starts at the smallest x
+        let rec = visualize(&input, false)?;
+        let first = &rec.frames()[0];
+        assert_eq!(first.get(Point::new(0, 19)).map(|s| s.ch), Some('O'));
 
         Ok(())
     }
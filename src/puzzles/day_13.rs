@@ -1,11 +1,17 @@
-use crate::input::Input;
-use crate::lib::intcode::Machine;
-use colored::Colorize;
-use std::collections::{HashMap, HashSet};
+use crate::input::{Input, Param};
+use crate::lib::grid::{Point, SparseGrid};
+use crate::lib::intcode::{Code, Machine};
+use crate::lib::render::{Frame, Recording, Style, TerminalRenderer};
+use crate::lib::terminal::{self, Key, RawMode};
+use colored::Color;
 use std::error::Error;
-use std::io;
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+const SPEED: Param = Param::new("speed", "8", "Game ticks per second in play mode");
+
+pub const PARAMS: &[Param] = &[SPEED];
 
-type Position = (isize, isize);
 type TileID = u8;
 
 const EMPTY: TileID = 0;
@@ -14,120 +20,326 @@ const BLOCK: TileID = 2;
 const HOR_PADDLE: TileID = 3;
 const BALL: TileID = 4;
 
-fn render_tile(w: &mut impl io::Write, tile: TileID) -> io::Result<()> {
-    let s = match tile {
-        EMPTY => " ".on_black(),
-        WALL => "+".green(),
-        BLOCK => "#".white().on_white(),
-        HOR_PADDLE => "-".bold().red(),
-        BALL => "o".blue(),
-        _ => "?".red(),
-    };
-
-    write!(w, "{}", s)
+fn tile_style(tile: Option<&TileID>) -> Style {
+    match tile.copied().unwrap_or(EMPTY) {
+        EMPTY => Style::plain(' ').bg(Color::Black),
+        WALL => Style::plain('+').fg(Color::Green),
+        BLOCK => Style::plain('#').fg(Color::White).bg(Color::White),
+        HOR_PADDLE => Style::plain('-').fg(Color::Red).bold(),
+        BALL => Style::plain('o').fg(Color::Blue),
+        _ => Style::plain('?').fg(Color::Red),
+    }
 }
 
-type Map = HashMap<Position, TileID>;
+type Map = SparseGrid<TileID>;
 
-fn print_map(map: &Map, size: Position, dirty_lines: &mut HashSet<isize>) {
-    let stdout = io::stdout();
-    let mut w = stdout.lock();
+fn render_frame(map: &Map, score: Code) -> Frame {
+    Frame::from_sparse(map, tile_style).with_caption(format!("Score: {}", score))
+}
 
-    for &y in dirty_lines.iter() {
-        print!("\x1B[{};0H", y + 1);
+type GameState = (Map, Code);
 
-        for x in 0..size.0 {
-            let tile = map.get(&(x, y)).copied().unwrap_or(EMPTY);
-            render_tile(&mut w, tile).unwrap();
+/// Arcade cabinet together with what's on its screen.
+/// Cloning it takes a snapshot of the whole game.
+#[derive(Clone)]
+struct Game {
+    m: Machine,
+    map: Map,
+    score: Code,
+    ball_x: Code,
+    paddle_x: Code,
+}
+
+impl Game {
+    fn new(m: Machine) -> Self {
+        Self {
+            m,
+            map: Map::new(),
+            score: 0,
+            ball_x: 0,
+            paddle_x: 0,
         }
     }
-    dirty_lines.clear();
-}
 
-type GameState = (Map, Position, isize);
+    /// Run until the game waits for the joystick.
+    /// Returns false once the game is over.
+    fn advance(&mut self) -> Result<bool, Box<dyn Error>> {
+        loop {
+            while self.m.output.len() < 3 {
+                if !self.m.run_once()? {
+                    return Ok(!self.m.is_done());
+                }
+            }
 
-fn run_game(m: &mut Machine, render: bool) -> Result<GameState, Box<dyn Error>> {
-    if render {
-        // clear screen and disable cursor
-        print!("\x1B[2J\x1B[?25l");
-    }
+            let out = self.m.take_output();
+            let (x, y) = (out[0], out[1]);
+            if x == -1 {
+                self.score = out[2];
+                continue;
+            }
 
-    let mut map = Map::new();
-    let mut max_pos: Position = (0, 0);
-    let mut score = 0;
+            let tile = out[2] as TileID;
+            match tile {
+                BALL => self.ball_x = x,
+                HOR_PADDLE => self.paddle_x = x,
+                _ => (),
+            }
 
-    let mut ball_x: isize = 0;
-    let mut paddle_x: isize = 0;
+            self.map.insert(Point::new(x, y), tile);
+        }
+    }
 
-    let mut dirty_lines = HashSet::new();
+    /// Move the joystick, -1 is left and 1 is right.
+    fn tilt(&mut self, joystick: Code) {
+        self.m.input.push_back(joystick);
+    }
 
-    'outer: loop {
-        while m.output.len() < 3 {
-            if !m.run_once()? {
-                if m.is_done() {
-                    break 'outer;
-                }
-                let input = (ball_x - paddle_x).signum();
-                m.input.push_back(input);
+    /// Keep the paddle below the ball.
+    fn autopilot(&self) -> Code {
+        (self.ball_x - self.paddle_x).signum()
+    }
 
-                if render {
-                    print_map(&map, (max_pos.0 + 1, max_pos.1 + 1), &mut dirty_lines);
-                    std::thread::sleep(std::time::Duration::from_millis(25));
-                }
-            }
-        }
+    fn blocks(&self) -> usize {
+        self.map.iter().filter(|(_, &tile)| tile == BLOCK).count()
+    }
 
-        let out = m.take_output();
-        let (x, y) = (out[0], out[1]);
-        if x == -1 {
-            score = out[2];
-            continue;
-        }
+    fn frame(&self) -> Frame {
+        render_frame(&self.map, self.score)
+    }
+}
 
-        let tile = out[2] as TileID;
-        match tile {
-            BALL => ball_x = x,
-            HOR_PADDLE => paddle_x = x,
-            _ => (),
-        }
+/// Run the game until it's over.
+/// If a recording is given, a frame is added every time the game waits for input.
+fn run_game(m: Machine, mut rec: Option<&mut Recording>) -> Result<GameState, Box<dyn Error>> {
+    let mut game = Game::new(m);
+    while game.advance()? {
+        game.tilt(game.autopilot());
 
-        if x > max_pos.0 {
-            max_pos.0 = x;
-        }
-        if y > max_pos.1 {
-            max_pos.1 = y;
+        if let Some(rec) = rec.as_mut() {
+            rec.push(game.frame());
         }
+    }
 
-        map.insert((x, y), tile);
-        if render {
-            dirty_lines.insert(y);
-        }
+    if let Some(rec) = rec {
+        rec.push(game.frame());
     }
 
-    Ok((map, (max_pos.0 + 1, max_pos.1 + 1), score))
+    Ok((game.map, game.score))
 }
 
 pub fn first(i: &Input) -> Result<String, Box<dyn Error>> {
-    let mut m = Machine::from_input(i)?;
-    let (map, _, _) = run_game(&mut m, false)?;
+    let m = Machine::from_input(i)?;
+    let (map, _) = run_game(m, None)?;
 
     Ok(map
-        .values()
-        .filter(|&&tile| tile == BLOCK)
+        .iter()
+        .filter(|(_, &tile)| tile == BLOCK)
         .count()
         .to_string())
 }
 
-fn play_game(m: &mut Machine, render: bool) -> Result<GameState, Box<dyn Error>> {
+/// Insert coins so the game can be played instead of only showing the screen.
+fn insert_coins(m: &mut Machine) {
     m.start();
     m.write(0, 2);
+}
 
-    run_game(m, render)
+fn play_game(mut m: Machine, rec: Option<&mut Recording>) -> Result<GameState, Box<dyn Error>> {
+    insert_coins(&mut m);
+    run_game(m, rec)
 }
 
 pub fn second(i: &Input) -> Result<String, Box<dyn Error>> {
-    let mut m = Machine::from_input(i)?;
-    let (_, _, score) = play_game(&mut m, false)?;
+    let m = Machine::from_input(i)?;
+    let (_, score) = play_game(m, None)?;
 
     Ok(score.to_string())
 }
+
+pub fn visualize(i: &Input, second: bool) -> Result<Recording, Box<dyn Error>> {
+    let m = Machine::from_input(i)?;
+    let mut rec = Recording::new();
+    if second {
+        play_game(m, Some(&mut rec))?;
+    } else {
+        run_game(m, Some(&mut rec))?;
+    }
+
+    Ok(rec)
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Command {
+    Move(Code),
+    Pause,
+    Save,
+    Restore,
+    Quit,
+}
+
+const CONTROLS: &str = "<-/-> or a/d move, p pause, s save, r restore, q quit";
+
+fn key_command(key: Key) -> Option<Command> {
+    let cmd = match key {
+        Key::Left | Key::Char('a') => Command::Move(-1),
+        Key::Right | Key::Char('d') => Command::Move(1),
+        Key::Down | Key::Char(' ') => Command::Move(0),
+        Key::Char('p') => Command::Pause,
+        Key::Char('s') => Command::Save,
+        Key::Char('r') => Command::Restore,
+        Key::Char('q') | Key::Escape | Key::Interrupt => Command::Quit,
+        _ => return None,
+    };
+
+    Some(cmd)
+}
+
+/// Wait for the end of the tick and collect the commands given until then.
+/// While paused it waits for the next command instead.
+fn wait_for_commands(keys: &Receiver<Key>, tick: Duration, paused: bool) -> Vec<Command> {
+    let deadline = Instant::now() + tick;
+    let mut commands = Vec::new();
+    loop {
+        let key = if paused && commands.is_empty() {
+            keys.recv().map_err(|_| RecvTimeoutError::Disconnected)
+        } else {
+            keys.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+        };
+
+        match key {
+            Ok(key) => commands.extend(key_command(key)),
+            Err(RecvTimeoutError::Timeout) => return commands,
+            // without a keyboard there's no way to control the game
+            Err(RecvTimeoutError::Disconnected) => {
+                commands.push(Command::Quit);
+                return commands;
+            }
+        }
+    }
+}
+
+/// Play the game on the terminal using the keyboard as joystick.
+pub fn play(i: &Input) -> Result<String, Box<dyn Error>> {
+    let speed: f64 = SPEED.get(i)?;
+    if !speed.is_finite() || speed <= 0.0 {
+        return Err("speed must be positive".into());
+    }
+    let tick = Duration::try_from_secs_f64(1.0 / speed)
+        .map_err(|_| format!("speed is too slow: {}", speed))?;
+
+    let mut m = Machine::from_input(i)?;
+    insert_coins(&mut m);
+    let mut game = Game::new(m);
+    let mut snapshot: Option<Game> = None;
+    let mut paused = false;
+    let mut status = CONTROLS.to_string();
+
+    let raw = RawMode::enable()?;
+    let keys = terminal::spawn_key_reader();
+    let mut renderer = TerminalRenderer::stdout();
+
+    let mut over = !game.advance()?;
+    while !over {
+        let caption = format!("Score: {}\n{}", game.score, status);
+        renderer.draw(&game.frame().with_caption(caption))?;
+
+        // the joystick returns to neutral unless a direction was pressed during the tick
+        let mut joystick = 0;
+        let mut quit = false;
+        for cmd in wait_for_commands(&keys, tick, paused) {
+            match cmd {
+                Command::Move(dir) => joystick = dir,
+                Command::Pause => {
+                    paused = !paused;
+                    status = if paused {
+                        "Paused, press p to continue".to_string()
+                    } else {
+                        CONTROLS.to_string()
+                    };
+                }
+                Command::Save => {
+                    snapshot = Some(game.clone());
+                    status = format!("Saved at score {}", game.score);
+                }
+                Command::Restore => match &snapshot {
+                    Some(saved) => {
+                        game = saved.clone();
+                        status = format!("Restored to score {}", game.score);
+                    }
+                    None => status = "Nothing saved yet".to_string(),
+                },
+                Command::Quit => quit = true,
+            }
+        }
+        if quit {
+            break;
+        }
+        if paused {
+            continue;
+        }
+
+        game.tilt(joystick);
+        over = !game.advance()?;
+    }
+
+    renderer.draw(&game.frame().with_caption(format!("Score: {}", game.score)))?;
+    renderer.finish()?;
+    drop(raw);
+
+    let result = if !over {
+        "Quit"
+    } else if game.blocks() == 0 {
+        "You won"
+    } else {
+        "Game over"
+    };
+    Ok(format!("{} with a score of {}", result, game.score))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_game() -> Result<(), Box<dyn Error>> {
+        // draws a wall, a block and the paddle, sets the score and reads the joystick once
+        let mut m = Machine::new(vec![
+            104, 0, 104, 0, 104, 1, 104, 1, 104, 0, 104, 2, 104, 2, 104, 0, 104, 3, 104, -1, 104,
+            0, 104, 42, 3, 100, 99,
+        ]);
+        m.start();
+        let mut game = Game::new(m);
+
+        assert!(game.advance()?);
+        assert_eq!(game.score, 42);
+        assert_eq!(game.blocks(), 1);
+        assert_eq!(game.paddle_x, 2);
+        assert_eq!(game.frame().plain(), "+#-\nScore: 42\n");
+
+        let snapshot = game.clone();
+        game.tilt(-1);
+        assert!(!game.advance()?);
+
+        game = snapshot;
+        assert!(!game.m.is_done());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_key_command() {
+        assert_eq!(key_command(Key::Left), Some(Command::Move(-1)));
+        assert_eq!(key_command(Key::Char('d')), Some(Command::Move(1)));
+        assert_eq!(key_command(Key::Interrupt), Some(Command::Quit));
+        assert_eq!(key_command(Key::Char('x')), None);
+    }
+
+    #[test]
+    fn test_play_invalid_speed() {
+        // the speed is checked before the terminal is touched
+        for speed in ["0", "-1", "inf", "NaN", "1e-300"] {
+            let input = Input::new("99").with_param("speed", speed);
+            assert!(play(&input).is_err(), "speed {}", speed);
+        }
+    }
+}
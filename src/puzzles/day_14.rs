@@ -1,14 +1,30 @@
-use crate::input::Input;
+use crate::input::{Input, Param};
 use std::collections::HashMap;
 use std::error::Error;
 use std::fmt;
 use std::fmt::Write;
-use std::ops;
 use std::str::FromStr;
 
-fn div_ceil(a: usize, b: usize) -> usize {
-    (a - 1) / b + 1
-}
+const ORE_CARGO: Param = Param::new(
+    "ore",
+    "1000000000000",
+    "amount of ore available to produce fuel in the second part",
+);
+const REPORT: Param = Param::new(
+    "report",
+    "",
+    "file to write the ore used and the chemicals left over by producing one fuel to",
+);
+const DOT: Param = Param::new(
+    "dot",
+    "",
+    "file to write the reactions to as Graphviz graph",
+);
+
+pub const PARAMS: &[Param] = &[ORE_CARGO, REPORT, DOT];
+
+const ORE: &str = "ORE";
+const FUEL: &str = "FUEL";
 
 #[derive(Clone, Debug)]
 struct ChemicalQty {
@@ -22,12 +38,6 @@ impl ChemicalQty {
     }
 }
 
-impl ops::MulAssign<usize> for ChemicalQty {
-    fn mul_assign(&mut self, rhs: usize) {
-        self.amount *= rhs;
-    }
-}
-
 impl fmt::Display for ChemicalQty {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{} {}", self.amount, self.name)
@@ -73,23 +83,6 @@ struct Reaction {
     output: ChemicalQty,
 }
 
-impl Reaction {
-    fn scale_to_produce(&mut self, amount: usize) {
-        let current_amount = self.output.amount;
-        if current_amount >= amount {
-            return;
-        }
-
-        let factor = div_ceil(amount, current_amount);
-
-        for c in self.input.iter_mut() {
-            *c *= factor;
-        }
-
-        self.output *= factor;
-    }
-}
-
 impl fmt::Display for Reaction {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let inp = join_iter(&mut self.input.iter().map(|c| c.to_string()), ", ")?;
@@ -115,89 +108,233 @@ impl FromStr for Reaction {
     }
 }
 
-type ChemicalMap = HashMap<String, usize>;
+/// Ore used to produce some fuel and the chemicals which were produced but not needed.
+#[derive(Debug)]
+struct Production {
+    fuel: usize,
+    ore: usize,
+    leftovers: Vec<ChemicalQty>,
+}
+
+impl fmt::Display for Production {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{} {} => {} {}", self.ore, ORE, self.fuel, FUEL)?;
+        if self.leftovers.is_empty() {
+            writeln!(f, "nothing left over")
+        } else {
+            let leftovers = join_iter(&mut self.leftovers.iter(), ", ")?;
+            writeln!(f, "left over: {}", leftovers)
+        }
+    }
+}
+
+/// Whether a chemical is being looked at or done while sorting the reactions.
+#[derive(Clone, Copy, Eq, PartialEq)]
+enum Mark {
+    Visiting,
+    Done,
+}
 
+/// Reactions sorted so that every chemical comes before the chemicals it's made of.
 #[derive(Debug)]
 struct Reactions {
     reactions: Vec<Reaction>,
+    by_output: HashMap<String, usize>,
+    order: Vec<String>,
 }
 
 impl Reactions {
-    fn new(reactions: Vec<Reaction>) -> Self {
-        Reactions { reactions }
+    fn new(reactions: Vec<Reaction>) -> Result<Self, Box<dyn Error>> {
+        let mut by_output = HashMap::with_capacity(reactions.len());
+        for (n, r) in reactions.iter().enumerate() {
+            if r.output.name == ORE {
+                return Err(format!("{} can't be produced: {}", ORE, r).into());
+            }
+            if r.output.amount == 0 {
+                return Err(format!("reaction doesn't produce anything: {}", r).into());
+            }
+            if by_output.insert(r.output.name.clone(), n).is_some() {
+                return Err(format!("multiple reactions produce {}", r.output.name).into());
+            }
+        }
+
+        for r in &reactions {
+            if let Some(c) = r
+                .input
+                .iter()
+                .find(|c| c.name != ORE && !by_output.contains_key(&c.name))
+            {
+                return Err(format!("no reaction produces {} needed for {}", c.name, r).into());
+            }
+        }
+
+        let mut reactions = Self {
+            reactions,
+            by_output,
+            order: Vec::new(),
+        };
+        reactions.sort()?;
+        Ok(reactions)
     }
 
     fn from_input(i: &Input) -> Result<Self, Box<dyn Error>> {
         i.map_lines(str::parse)
             .collect::<Result<Vec<_>, _>>()
-            .map(Self::new)
+            .and_then(Self::new)
     }
 
     fn reaction_for_chemical(&self, name: &str) -> Option<&Reaction> {
-        self.reactions.iter().find(|r| r.output.name == name)
+        self.by_output.get(name).map(|&n| &self.reactions[n])
     }
 
-    fn ore_required_for(
-        &mut self,
-        remaining_chemicals: &mut ChemicalMap,
-        chemical: &ChemicalQty,
-    ) -> Option<usize> {
-        let name = &chemical.name;
-        let mut needed = chemical.amount;
-
-        if name == "ORE" {
-            return Some(needed);
-        }
+    /// Sort the chemicals topologically, failing if a chemical is needed to produce itself.
+    fn sort(&mut self) -> Result<(), Box<dyn Error>> {
+        fn visit<'a>(
+            reactions: &'a Reactions,
+            name: &'a str,
+            marks: &mut HashMap<&'a str, Mark>,
+            path: &mut Vec<&'a str>,
+            order: &mut Vec<String>,
+        ) -> Result<(), Box<dyn Error>> {
+            match marks.get(name) {
+                Some(Mark::Done) => return Ok(()),
+                Some(Mark::Visiting) => {
+                    let start = path.iter().position(|&n| n == name).unwrap_or(0);
+                    let cycle = join_iter(&mut path[start..].iter().chain(&[name]), " -> ")?;
+                    return Err(format!("reactions form a cycle: {}", cycle).into());
+                }
+                None => {}
+            }
 
-        if let Some(already_have) = remaining_chemicals.get_mut(name) {
-            if *already_have >= needed {
-                *already_have -= needed;
-                return Some(0);
+            marks.insert(name, Mark::Visiting);
+            path.push(name);
+            if let Some(r) = reactions.reaction_for_chemical(name) {
+                for c in &r.input {
+                    visit(reactions, &c.name, marks, path, order)?;
+                }
             }
+            path.pop();
+            marks.insert(name, Mark::Done);
+            order.push(name.to_string());
 
-            needed -= *already_have;
-            *already_have = 0;
+            Ok(())
         }
 
-        let mut reaction = self.reaction_for_chemical(name)?.clone();
-        reaction.scale_to_produce(needed);
+        let mut marks = HashMap::new();
+        let mut order = Vec::new();
+        for r in &self.reactions {
+            visit(self, &r.output.name, &mut marks, &mut Vec::new(), &mut order)?;
+        }
 
-        let mut total_ore = 0;
-        for c in reaction.input.iter() {
-            let req = self.ore_required_for(remaining_chemicals, c)?;
-            total_ore += req;
+        order.reverse();
+        self.order = order;
+        Ok(())
+    }
+
+    /// Produce the amount of a chemical from ore.
+    /// Chemicals are handled in topological order so all that's needed of one is known before
+    /// its reaction runs, which means it runs only once.
+    fn produce(&self, name: &str, amount: usize) -> Result<Production, Box<dyn Error>> {
+        if self.reaction_for_chemical(name).is_none() {
+            return Err(format!("no reaction produces {}", name).into());
         }
 
-        let leftover = reaction.output.amount - needed;
-        if leftover > 0 {
-            remaining_chemicals.insert(reaction.output.name, leftover);
+        let overflow = || format!("too much needed to produce {} {}", amount, name);
+        let mut needed: HashMap<&str, usize> = HashMap::new();
+        needed.insert(name, amount);
+        let mut leftovers = Vec::new();
+        for chemical in &self.order {
+            let reaction = match self.reaction_for_chemical(chemical) {
+                Some(r) => r,
+                None => continue,
+            };
+            let amount = match needed.get(chemical.as_str()) {
+                Some(&amount) => amount,
+                None => continue,
+            };
+
+            let runs = amount.div_ceil(reaction.output.amount);
+            let leftover = (reaction.output.amount - amount % reaction.output.amount)
+                % reaction.output.amount;
+            if leftover > 0 {
+                leftovers.push(ChemicalQty::new(chemical.clone(), leftover));
+            }
+            for c in &reaction.input {
+                let total = needed.entry(&c.name).or_default();
+                *total = runs
+                    .checked_mul(c.amount)
+                    .and_then(|n| n.checked_add(*total))
+                    .ok_or_else(overflow)?;
+            }
         }
-        Some(total_ore)
+
+        Ok(Production {
+            fuel: amount,
+            ore: needed.get(ORE).copied().unwrap_or(0),
+            leftovers,
+        })
     }
 
-    fn ore_per_fuel(&mut self) -> Option<usize> {
-        self.ore_required_for(
-            &mut HashMap::new(),
-            &ChemicalQty::new(String::from("FUEL"), 1),
-        )
+    fn ore_for_fuel(&self, fuel: usize) -> Result<usize, Box<dyn Error>> {
+        self.produce(FUEL, fuel).map(|p| p.ore)
     }
 
-    fn fuel_for_ore(&mut self, total_ore: usize) -> Option<usize> {
-        let mut fuel = 1;
-        let mut chem = ChemicalQty::new(String::from("FUEL"), fuel);
+    /// Find the most fuel the ore is enough for with a binary search.
+    /// Leftovers only ever help, so producing one fuel at a time gives a lower bound.
+    fn fuel_for_ore(&self, total_ore: usize) -> Result<usize, Box<dyn Error>> {
+        let per_fuel = self.ore_for_fuel(1)?;
+        if per_fuel == 0 {
+            return Err("fuel can be produced without ore".into());
+        }
+
+        // the only error left is needing more ore than can be counted
+        let enough = |fuel| self.ore_for_fuel(fuel).is_ok_and(|ore| ore <= total_ore);
+
+        let mut low = total_ore / per_fuel;
+        // a fuel usually takes at least one ore, reactions making several fuel from little ore
+        // need the bound to grow instead
+        let mut high = match total_ore.checked_add(1) {
+            Some(cap) if !enough(cap) => cap,
+            _ => low.max(1),
+        };
+        while enough(high) {
+            low = high;
+            high = high
+                .checked_mul(2)
+                .ok_or_else(|| format!("{} ore make too much fuel to count", total_ore))?;
+        }
 
-        loop {
-            chem.amount = fuel + 1;
-            let ore = self.ore_required_for(&mut HashMap::new(), &chem)?;
-            if ore > total_ore {
-                break;
+        // the ore is enough for `low` but not for `high` fuel
+        while high - low > 1 {
+            let mid = low + (high - low) / 2;
+            if enough(mid) {
+                low = mid;
             } else {
-                let ratio = total_ore as f64 / ore as f64;
-                fuel = ((fuel + 1) as f64 * ratio).floor() as usize;
+                high = mid;
             }
         }
 
-        Some(fuel)
+        Ok(low)
+    }
+
+    /// Graph of the reactions with an edge from every input to the output of a reaction.
+    fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph reactions {\n");
+        writeln!(dot, "    \"{}\" [shape=box];", ORE).unwrap();
+        for r in &self.reactions {
+            writeln!(dot, "    \"{}\" [label=\"{}\"];", r.output.name, r.output).unwrap();
+            for c in &r.input {
+                writeln!(
+                    dot,
+                    "    \"{}\" -> \"{}\" [label=\"{}\"];",
+                    c.name, r.output.name, c.amount
+                )
+                .unwrap();
+            }
+        }
+        dot.push_str("}\n");
+
+        dot
     }
 }
 
@@ -209,21 +346,21 @@ impl fmt::Display for Reactions {
 }
 
 pub fn first(i: &Input) -> Result<String, Box<dyn Error>> {
-    let mut reactions = Reactions::from_input(i)?;
+    let reactions = Reactions::from_input(i)?;
+    DOT.write_file(i, || reactions.to_dot())?;
 
-    reactions
-        .ore_per_fuel()
-        .ok_or_else(|| "no way to produce fuel".into())
-        .map(|ore| ore.to_string())
+    let production = reactions.produce(FUEL, 1)?;
+    REPORT.write_file(i, || production.to_string())?;
+
+    Ok(production.ore.to_string())
 }
 
 pub fn second(i: &Input) -> Result<String, Box<dyn Error>> {
-    let mut reactions = Reactions::from_input(i)?;
+    let reactions = Reactions::from_input(i)?;
 
     reactions
-        .fuel_for_ore(1_000_000_000_000)
-        .ok_or_else(|| "no way to produce fuel".into())
-        .map(|ore| ore.to_string())
+        .fuel_for_ore(ORE_CARGO.get(i)?)
+        .map(|fuel| fuel.to_string())
 }
 
 #[cfg(test)]
@@ -312,6 +449,88 @@ mod tests {
         Ok(())
     }
 
+    const SIMPLE: &str = "
+        9 ORE => 2 A
+        8 ORE => 3 B
+        7 ORE => 5 C
+        3 A, 4 B => 1 AB
+        5 B, 7 C => 1 BC
+        4 C, 1 A => 1 CA
+        2 AB, 3 BC, 4 CA => 1 FUEL
+    ";
+
+    #[test]
+    fn test_order() -> Result<(), Box<dyn Error>> {
+        let reactions = Reactions::from_input(&Input::new(SIMPLE))?;
+        let position = |name: &str| reactions.order.iter().position(|n| n == name);
+        assert_eq!(position(FUEL), Some(0));
+        assert_eq!(position(ORE), Some(reactions.order.len() - 1));
+        assert!(position("AB") < position("A"));
+        assert!(position("CA") < position("C"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_reactions() {
+        let error = |text: &str| {
+            Reactions::from_input(&Input::new(text))
+                .unwrap_err()
+                .to_string()
+        };
+
+        assert_eq!(
+            error("1 ORE => 1 A\n2 A, 1 B => 1 FUEL"),
+            "no reaction produces B needed for 2 A, 1 B => 1 FUEL"
+        );
+        assert_eq!(
+            error("1 ORE => 1 A\n2 ORE => 1 A"),
+            "multiple reactions produce A"
+        );
+        assert_eq!(
+            error("1 ORE => 0 FUEL"),
+            "reaction doesn't produce anything: 1 ORE => 0 FUEL"
+        );
+        assert_eq!(
+            error("1 C => 1 FUEL\n1 ORE, 1 B => 1 A\n1 A => 1 B\n1 B => 1 C"),
+            "reactions form a cycle: B -> A -> B"
+        );
+        assert!(Reactions::from_input(&Input::new("1 ORE => 1 A"))
+            .and_then(|r| r.produce(FUEL, 1))
+            .is_err());
+    }
+
+    #[test]
+    fn test_leftovers() -> Result<(), Box<dyn Error>> {
+        let reactions = Reactions::from_input(&Input::new(SIMPLE))?;
+        let production = reactions.produce(FUEL, 1)?;
+        assert_eq!(production.ore, 165);
+        assert_eq!(
+            production.to_string(),
+            "165 ORE => 1 FUEL\nleft over: 3 C, 1 B\n"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_to_dot() -> Result<(), Box<dyn Error>> {
+        let reactions = Reactions::from_input(&Input::new("9 ORE => 2 A\n3 A => 1 FUEL"))?;
+        assert_eq!(
+            reactions.to_dot(),
+            r#"digraph reactions {
+    "ORE" [shape=box];
+    "A" [label="2 A"];
+    "ORE" -> "A" [label="9"];
+    "FUEL" [label="1 FUEL"];
+    "A" -> "FUEL" [label="3"];
+}
+"#
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn test_second() -> Result<(), Box<dyn Error>> {
         assert_eq!(
@@ -356,6 +575,16 @@ mod tests {
             "460664",
         );
 
+        // one ore is enough for several fuel
+        let cheap = "1 ORE => 10 FUEL";
+        assert_eq!(second(&Input::new(cheap).with_param("ore", "5"))?, "50");
+        let huge = Input::new(cheap).with_param("ore", &usize::MAX.to_string());
+        assert!(second(&huge).is_err());
+
+        // needing more ore than can be counted is just not enough ore
+        let huge = Input::new("10 ORE => 1 FUEL").with_param("ore", &usize::MAX.to_string());
+        assert_eq!(second(&huge)?, (usize::MAX / 10).to_string());
+
         Ok(())
     }
 }
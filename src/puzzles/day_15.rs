@@ -1,68 +1,63 @@
 use crate::input::Input;
+use crate::lib::grid::{Direction, Point, SparseGrid};
 use crate::lib::intcode::{Code, Machine};
+use crate::lib::render::{Frame, Recording, Style};
+use crate::lib::search;
+use colored::Color;
 use std::collections::{HashMap, VecDeque};
 use std::error::Error;
 
-type Position = (isize, isize);
-const CARDINALS: [Position; 4] = [(0, 1), (0, -1), (1, 0), (-1, 0)];
+/// Movement command understood by the repair droid (north, south, west, east).
+type Command = u8;
 
-fn pos_add(p1: Position, p2: Position) -> Position {
-    (p1.0 + p2.0, p1.1 + p2.1)
+fn iter_commands() -> impl Iterator<Item = Command> {
+    1..=4
 }
 
-fn surrounding_positions(pos: Position) -> impl Iterator<Item = Position> {
-    CARDINALS.iter().map(move |&dir| pos_add(pos, dir))
-}
-
-type Direction = u8;
-
-fn iter_dirs() -> impl Iterator<Item = Direction> {
-    (1..=4)
-}
-
-fn dir_as_pos(dir: Direction) -> Position {
-    let i = if dir == 0 {
-        CARDINALS.len()
-    } else {
-        ((dir - 1) % 4) as usize
+fn command_offset(cmd: Command) -> Point {
+    let dir = match cmd {
+        1 => Direction::Up,
+        2 => Direction::Down,
+        3 => Direction::Left,
+        _ => Direction::Right,
     };
 
-    CARDINALS[i]
+    dir.offset()
 }
 
 type PosType = u8;
 const WALL: PosType = 0;
 const OXYGEN: PosType = 2;
 
-fn try_move(m: &mut Machine, dir: Direction) -> Result<PosType, Box<dyn Error>> {
-    m.send(dir as Code)?;
+fn try_move(m: &mut Machine, cmd: Command) -> Result<PosType, Box<dyn Error>> {
+    m.send(cmd as Code)?;
     m.take_output()
         .last()
         .ok_or_else(|| "got no response".into())
         .map(|&o| o as PosType)
 }
 
-type Map = HashMap<Position, PosType>;
+type Map = SparseGrid<PosType>;
 
 fn try_dir(
     m: &mut Machine,
     map: &mut Map,
-    pos: Position,
-    dir: Direction,
-) -> Result<(bool, Position), Box<dyn Error>> {
-    let next_pos = pos_add(pos, dir_as_pos(dir));
+    pos: Point,
+    cmd: Command,
+) -> Result<(bool, Point), Box<dyn Error>> {
+    let next_pos = pos + command_offset(cmd);
     // we already know what's at the next position
-    if map.contains_key(&next_pos) {
+    if map.contains(next_pos) {
         return Ok((false, next_pos));
     }
 
-    let pos_type = try_move(m, dir)?;
+    let pos_type = try_move(m, cmd)?;
     map.insert(next_pos, pos_type);
 
     Ok((pos_type != WALL, next_pos))
 }
 
-fn flood_fill(mut m: Machine, map: &mut Map, pos: Position) -> Result<(), Box<dyn Error>> {
+fn flood_fill(mut m: Machine, map: &mut Map, pos: Point) -> Result<(), Box<dyn Error>> {
     m.start();
 
     let mut queue = VecDeque::new();
@@ -71,8 +66,8 @@ fn flood_fill(mut m: Machine, map: &mut Map, pos: Position) -> Result<(), Box<dy
     while let Some((m, pos)) = queue.pop_front() {
         let mut mc = m.clone();
 
-        for dir in iter_dirs() {
-            let (ok, next_pos) = try_dir(&mut mc, map, pos, dir)?;
+        for cmd in iter_commands() {
+            let (ok, next_pos) = try_dir(&mut mc, map, pos, cmd)?;
             if ok {
                 queue.push_back((mc, next_pos));
                 // we only need to replace mc if we actually moved.
@@ -86,31 +81,24 @@ fn flood_fill(mut m: Machine, map: &mut Map, pos: Position) -> Result<(), Box<dy
 
 fn build_map(m: Machine) -> Result<Map, Box<dyn Error>> {
     let mut map = Map::new();
-    flood_fill(m, &mut map, (0, 0))?;
+    flood_fill(m, &mut map, Point::ZERO)?;
     Ok(map)
 }
 
-fn find_oxygen(map: &Map) -> Option<Position> {
-    map.iter().find(|(_, &v)| v == OXYGEN).map(|(&k, _)| k)
+fn find_oxygen(map: &Map) -> Option<Point> {
+    map.find(|&v| v == OXYGEN)
 }
 
-fn all_distances(map: &Map, start: Position) -> HashMap<Position, usize> {
-    let mut distances = HashMap::new();
-    let mut queue = VecDeque::new();
-    queue.push_back((start, 0));
-
-    while let Some((pos, dist)) = queue.pop_front() {
-        distances.insert(pos, dist);
-        surrounding_positions(pos)
-            .filter(|p| map.get(p).map(|&v| v != WALL).unwrap_or(false))
-            .filter(|p| !distances.contains_key(p))
-            .for_each(|p| queue.push_back((p, dist + 1)));
-    }
+fn open_neighbours(map: &Map, pos: Point) -> impl Iterator<Item = Point> + '_ {
+    pos.neighbours4()
+        .filter(move |&p| map.get(p).map(|&v| v != WALL).unwrap_or(false))
+}
 
-    distances
+fn all_distances(map: &Map, start: Point) -> HashMap<Point, usize> {
+    search::bfs(vec![start], |&pos| open_neighbours(map, pos), |_| false).distances
 }
 
-fn build_distances(m: Machine) -> Result<HashMap<Position, usize>, Box<dyn Error>> {
+fn build_distances(m: Machine) -> Result<HashMap<Point, usize>, Box<dyn Error>> {
     let map = build_map(m)?;
     find_oxygen(&map)
         .ok_or_else(|| "location not found".into())
@@ -121,7 +109,7 @@ pub fn first(i: &Input) -> Result<String, Box<dyn Error>> {
     let m = Machine::from_input(i)?;
     let distances = build_distances(m)?;
     distances
-        .get(&(0, 0))
+        .get(&Point::ZERO)
         .ok_or_else(|| "no path found".into())
         .map(|v| v.to_string())
 }
@@ -132,15 +120,134 @@ pub fn second(i: &Input) -> Result<String, Box<dyn Error>> {
     Ok(distances.values().max().unwrap().to_string())
 }
 
+fn tile_style(tile: Option<&PosType>) -> Style {
+    match tile {
+        None => Style::plain(' '),
+        Some(&WALL) => Style::plain('#').fg(Color::White),
+        Some(&OXYGEN) => Style::plain('O').fg(Color::Cyan).bold(),
+        Some(_) => Style::plain('.').dimmed(),
+    }
+}
+
+/// Draw the map, `visible` decides which points have been discovered so far.
+fn render_map(map: &Map, visible: impl Fn(Point) -> bool) -> Frame {
+    Frame::from_fn(map.bounds(), |p| {
+        if p == Point::ZERO {
+            Style::plain('D').fg(Color::Green).bold()
+        } else if visible(p) {
+            tile_style(map.get(p))
+        } else {
+            Style::plain(' ')
+        }
+    })
+}
+
+/// Reveal the map in the order the droid discovers it and finally show the way to the oxygen system.
+fn exploration_frames(map: &Map) -> Result<Recording, Box<dyn Error>> {
+    let oxygen = find_oxygen(map).ok_or("location not found")?;
+    let distances = all_distances(map, Point::ZERO);
+    let max_distance = distances.values().copied().max().unwrap_or_default();
+
+    let mut rec = Recording::new();
+    for step in 0..=max_distance {
+        let explored = |p: Point| distances.get(&p).is_some_and(|&d| d <= step);
+        let frame = render_map(map, |p| explored(p) || p.neighbours4().any(explored));
+        rec.push(frame.with_caption(format!("Step {}", step)));
+    }
+
+    let search = search::bfs(vec![Point::ZERO], |&pos| open_neighbours(map, pos), |&p| {
+        p == oxygen
+    });
+    let path = search.goal_path().ok_or("no path found")?;
+    let min = map.bounds().ok_or("map is empty")?.min;
+    let mut frame = render_map(map, |_| true);
+    for &p in &path[1..path.len() - 1] {
+        frame.set(p - min, Style::plain('*').fg(Color::Yellow));
+    }
+    rec.push(frame.with_caption(format!("Oxygen system is {} steps away", path.len() - 1)));
+
+    Ok(rec)
+}
+
+/// Show the oxygen spreading from the oxygen system, one minute per frame.
+fn oxygen_frames(map: &Map) -> Result<Recording, Box<dyn Error>> {
+    let oxygen = find_oxygen(map).ok_or("location not found")?;
+    let distances = all_distances(map, oxygen);
+    let max_distance = distances.values().copied().max().unwrap_or_default();
+
+    let mut rec = Recording::new();
+    for minute in 0..=max_distance {
+        let frame = Frame::from_fn(map.bounds(), |p| match distances.get(&p) {
+            Some(&d) if d <= minute => Style::plain('O').fg(Color::Cyan).bg(Color::Blue),
+            _ => tile_style(map.get(p)),
+        });
+        let filled = distances.values().filter(|&&d| d <= minute).count();
+        rec.push(frame.with_caption(format!("Minute {}: {} locations filled", minute, filled)));
+    }
+
+    Ok(rec)
+}
+
+pub fn visualize(i: &Input, second: bool) -> Result<Recording, Box<dyn Error>> {
+    let map = build_map(Machine::from_input(i)?)?;
+    if second {
+        oxygen_frames(&map)
+    } else {
+        exploration_frames(&map)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
-    fn test_dir_as_pos() {
-        assert_eq!(dir_as_pos(1), (0, 1));
-        assert_eq!(dir_as_pos(2), (0, -1));
-        assert_eq!(dir_as_pos(3), (1, 0));
-        assert_eq!(dir_as_pos(4), (-1, 0));
+    fn test_command_offset() {
+        assert_eq!(command_offset(1), Point::new(0, -1));
+        assert_eq!(command_offset(2), Point::new(0, 1));
+        assert_eq!(command_offset(3), Point::new(-1, 0));
+        assert_eq!(command_offset(4), Point::new(1, 0));
+    }
+
+    fn example_map() -> Map {
+        // the droid starts at the origin, which is the second row of the map
+        SparseGrid::from_lines(
+            [" ##   ", "#..## ", "#.#..#", "#.O.# ", " ###  "],
+            |c| match c {
+                '#' => Some(WALL),
+                '.' => Some(1),
+                'O' => Some(OXYGEN),
+                _ => None,
+            },
+        )
+        .iter()
+        .map(|(p, &v)| (p - Point::new(1, 1), v))
+        .collect()
+    }
+
+    #[test]
+    fn test_exploration_frames() -> Result<(), Box<dyn Error>> {
+        let rec = exploration_frames(&example_map())?;
+        assert_eq!(rec.len(), 8);
+        assert_eq!(rec.frames()[0].plain(), " #    \n#D.   \n .    \n      \n      \nStep 0\n");
+        assert_eq!(
+            rec.frames()[7].plain(),
+            " ##   \n#D.## \n#*#..#\n#*O.# \n ###  \nOxygen system is 3 steps away\n"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_oxygen_frames() -> Result<(), Box<dyn Error>> {
+        let rec = oxygen_frames(&example_map())?;
+        assert_eq!(rec.len(), 5);
+        assert_eq!(rec.frames()[1].caption, "Minute 1: 3 locations filled");
+        assert_eq!(
+            rec.frames()[4].plain(),
+            " ##   \n#OO## \n#O#OO#\n#OOO# \n ###  \nMinute 4: 8 locations filled\n"
+        );
+
+        Ok(())
     }
 }
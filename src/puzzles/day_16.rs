@@ -1,8 +1,24 @@
-use crate::input::Input;
+use crate::input::{Input, Param};
 use std::cmp;
 use std::error::Error;
 use std::fmt::Write;
-use std::iter;
+
+const PHASES: Param = Param::new("phases", "100", "Number of phases to run");
+const REPEAT: Param = Param::new(
+    "repeat",
+    "10000",
+    "Number of times the signal is repeated for the second part",
+);
+const PATTERN: Param = Param::new(
+    "pattern",
+    "0,1,0,-1",
+    "Base pattern, comma separated (the first value is skipped once)",
+);
+
+pub const PARAMS: &[Param] = &[PHASES, REPEAT, PATTERN];
+
+/// Number of digits in the message.
+const MESSAGE_LEN: usize = 8;
 
 type Digit = u8;
 type DigitList = Vec<Digit>;
@@ -19,6 +35,20 @@ fn signal_from_input(i: &Input) -> Result<DigitList, Box<dyn Error>> {
         .collect::<Result<_, _>>()
 }
 
+fn pattern_from_input(i: &Input) -> Result<Vec<isize>, Box<dyn Error>> {
+    let raw: String = PATTERN.get(i)?;
+    let pattern = raw
+        .split(',')
+        .map(|v| {
+            v.trim()
+                .parse()
+                .map_err(|_| format!("invalid pattern value: {}", v))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(pattern)
+}
+
 fn signal_to_string(signal: &[Digit]) -> String {
     let mut s = String::with_capacity(signal.len());
     signal
@@ -28,6 +58,10 @@ fn signal_to_string(signal: &[Digit]) -> String {
     s
 }
 
+fn message(signal: &[Digit]) -> String {
+    signal_to_string(&signal[..cmp::min(MESSAGE_LEN, signal.len())])
+}
+
 #[derive(Debug, Eq, PartialEq)]
 struct PatternRange {
     m: isize,
@@ -35,25 +69,30 @@ struct PatternRange {
     end: usize,
 }
 
-fn get_pattern(n: usize) -> impl Iterator<Item = PatternRange> {
-    let mut pos = 0;
-    let mut m = 1;
-
-    iter::from_fn(move || {
-        let range = PatternRange {
+/// Blocks of the pattern for the `n`th digit where the multiplier isn't zero.
+/// Every value of the base pattern is repeated `n` times and the very first value is skipped.
+fn get_pattern(n: usize, base: &[isize]) -> impl Iterator<Item = PatternRange> + '_ {
+    base.iter()
+        .cycle()
+        .enumerate()
+        .map(move |(k, &m)| PatternRange {
             m,
-            start: pos + n - 1,
-            end: pos + 2 * n - 1,
-        };
-
-        pos += 2 * n;
-        m *= -1;
-
-        Some(range)
-    })
+            start: (k * n).saturating_sub(1),
+            end: (k + 1) * n - 1,
+        })
+        .filter(|r| r.m != 0 && r.start < r.end)
 }
 
-fn run_phase(signal: &mut DigitList) {
+/// Run a phase for the digits starting at `skip`, `signal` only contains those digits.
+/// The digits before `skip` are only needed if the base pattern starts with a value other than
+/// zero, so `skip` has to be zero for those.
+///
+/// Every digit is a sum of blocks of the signal, which the prefix sums give in constant time.
+/// The `n`th digit has `len / n` blocks, so a phase takes `O(len * log(len / skip))`. Beyond the
+/// middle of the signal that's a single block for every digit.
+fn run_phase(signal: &mut [Digit], skip: usize, base: &[isize]) {
+    debug_assert!(skip == 0 || base.first() == Some(&0));
+
     let partial_sum = signal
         .iter()
         .chain(Some(&0u8))
@@ -64,14 +103,15 @@ fn run_phase(signal: &mut DigitList) {
         })
         .collect::<Vec<_>>();
 
+    let end = skip + signal.len();
     for i in 0..signal.len() {
-        let sum: isize = get_pattern(i + 1)
-            .take_while(|r| r.start < signal.len())
+        let sum: isize = get_pattern(skip + i + 1, base)
+            .take_while(|r| r.start < end)
             .map(|r| {
-                let end = partial_sum[cmp::min(r.end, partial_sum.len() - 1)];
-                let sum = end - partial_sum[r.start];
+                let start = partial_sum[r.start - skip];
+                let end = partial_sum[cmp::min(r.end, end) - skip];
 
-                sum * r.m
+                (end - start) * r.m
             })
             .sum();
 
@@ -79,65 +119,98 @@ fn run_phase(signal: &mut DigitList) {
     }
 }
 
+fn run_phases(
+    signal: &mut [Digit],
+    skip: usize,
+    base: &[isize],
+    phases: usize,
+) -> Result<(), Box<dyn Error>> {
+    if base.iter().all(|&m| m == 0) {
+        return Err("pattern needs a value other than zero".into());
+    }
+
+    for _ in 0..phases {
+        run_phase(signal, skip, base);
+    }
+
+    Ok(())
+}
+
 pub fn first(i: &Input) -> Result<String, Box<dyn Error>> {
     let mut signal = signal_from_input(i)?;
-    for _ in 0..100 {
-        run_phase(&mut signal);
-    }
+    run_phases(&mut signal, 0, &pattern_from_input(i)?, PHASES.get(i)?)?;
 
-    Ok(signal_to_string(&signal[..8]))
+    Ok(message(&signal))
 }
 
 fn get_offset(signal: impl Iterator<Item = Digit>) -> usize {
     signal.take(7).fold(0, |n, d| 10 * n + d as usize)
 }
 
-fn run_phase_offset(signal: &mut [Digit]) {
-    let mut partial_sum: isize = signal.iter().map(|v| *v as isize).sum();
-    for v in signal.iter_mut() {
-        let d = (partial_sum % 10).abs() as Digit;
-        partial_sum -= *v as isize;
-        *v = d;
-    }
-}
-
-pub fn second(i: &Input) -> Result<String, Box<dyn Error>> {
-    const REP: usize = 10_000;
-
-    let once_signal = signal_from_input(i)?;
+/// Decode the message at the offset of the repeated signal.
+fn decode(
+    once_signal: &[Digit],
+    repeat: usize,
+    base: &[isize],
+    phases: usize,
+) -> Result<String, Box<dyn Error>> {
     let offset = get_offset(once_signal.iter().copied());
-
-    let signal_len = REP * once_signal.len();
-    if 2 * offset <= signal_len {
-        // We're relying on the fact that when i >= len(signal) / 2, get_pattern(i)
-        // yields 0 for i < len(signal) and 1 for every i after that.
-        // This means that for i >= len(signal) the value is just sum(signal[i:])!
-        return Err("expected bigger offset!".into());
+    let signal_len = repeat * once_signal.len();
+    if offset >= signal_len {
+        return Err(format!(
+            "offset {} is beyond the end of the signal ({} digits)",
+            offset, signal_len
+        )
+        .into());
     }
-    let offset_start = offset % once_signal.len();
+
+    // digits only depend on the ones before them if the pattern doesn't start with zero
+    let skip = if base.first() == Some(&0) { offset } else { 0 };
     let mut signal: DigitList = once_signal
         .iter()
         .copied()
         .cycle()
-        .skip(offset_start)
-        .take(signal_len - offset)
+        .skip(skip % once_signal.len())
+        .take(signal_len - skip)
         .collect();
+    run_phases(&mut signal, skip, base, phases)?;
 
-    for _ in 0..100 {
-        run_phase_offset(&mut signal);
-    }
+    Ok(message(&signal[offset - skip..]))
+}
 
-    Ok(signal_to_string(&signal[..8]))
+pub fn second(i: &Input) -> Result<String, Box<dyn Error>> {
+    decode(
+        &signal_from_input(i)?,
+        REPEAT.get(i)?,
+        &pattern_from_input(i)?,
+        PHASES.get(i)?,
+    )
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    const BASE: &[isize] = &[0, 1, 0, -1];
+
+    /// Apply the pattern to every digit directly.
+    fn naive_phase(signal: &[Digit], base: &[isize]) -> DigitList {
+        (0..signal.len())
+            .map(|i| {
+                let sum: isize = signal
+                    .iter()
+                    .enumerate()
+                    .map(|(j, &d)| d as isize * base[(j + 1) / (i + 1) % base.len()])
+                    .sum();
+                (sum % 10).unsigned_abs() as Digit
+            })
+            .collect()
+    }
+
     #[test]
     fn test_get_pattern() {
         assert_eq!(
-            get_pattern(1).take(2).collect::<Vec<_>>(),
+            get_pattern(1, BASE).take(2).collect::<Vec<_>>(),
             vec![
                 PatternRange {
                     m: 1,
@@ -152,7 +225,7 @@ mod tests {
             ]
         );
         assert_eq!(
-            get_pattern(2).take(2).collect::<Vec<_>>(),
+            get_pattern(2, BASE).take(2).collect::<Vec<_>>(),
             vec![
                 PatternRange {
                     m: 1,
@@ -171,8 +244,50 @@ mod tests {
     #[test]
     fn test_run_phase() {
         let mut signal = vec![1, 2, 3, 4, 5, 6, 7, 8];
-        run_phase(&mut signal);
+        run_phase(&mut signal, 0, BASE);
         assert_eq!(signal_to_string(&signal), "48226158");
+
+        let signal: DigitList = "80871224585914546619083218645595"
+            .bytes()
+            .map(|b| b - b'0')
+            .collect();
+        for base in [&[1, 2][..], &[3, 0, -2], &[0, 1, 0, -1]] {
+            let mut fast = signal.clone();
+            run_phase(&mut fast, 0, base);
+            assert_eq!(fast, naive_phase(&signal, base));
+        }
+
+        // only the digits after the skipped ones are computed
+        let mut suffix = signal[5..].to_vec();
+        run_phase(&mut suffix, 5, BASE);
+        assert_eq!(suffix, naive_phase(&signal, BASE)[5..]);
+    }
+
+    #[test]
+    fn test_second() -> Result<(), Box<dyn Error>> {
+        assert_eq!(
+            second(&Input::new("03036732577212944063491565474664"))?,
+            "84462026"
+        );
+
+        // the offset is in the first half of the signal
+        let once: DigitList = "0000011234567890".bytes().map(|b| b - b'0').collect();
+        let mut signal = once.repeat(3);
+        for _ in 0..4 {
+            signal = naive_phase(&signal, BASE);
+        }
+        assert_eq!(decode(&once, 3, BASE, 4)?, signal_to_string(&signal[11..19]));
+
+        // patterns not starting with zero need the whole signal
+        let mut signal = once.repeat(2);
+        signal = naive_phase(&signal, &[1, -1]);
+        assert_eq!(decode(&once, 2, &[1, -1], 1)?, signal_to_string(&signal[11..19]));
+
+        assert!(decode(&once, 1, BASE, 1).is_ok());
+        assert!(decode(&[9, 9, 9, 9, 9, 9, 9], 1, BASE, 1).is_err());
+        assert!(decode(&once, 1, &[0, 0], 1).is_err());
+
+        Ok(())
     }
 
     #[test]
@@ -1,5 +1,8 @@
-use crate::input::Input;
+use crate::input::{Input, Param};
+use crate::lib::grid::{Point, Rect};
 use crate::lib::intcode::{Code, Machine};
+use crate::lib::render::{self, Recording, Style};
+use colored::Color;
 use std::cmp;
 use std::collections::{HashMap, HashSet};
 use std::error::Error;
@@ -7,6 +10,14 @@ use std::fmt;
 use std::iter;
 use std::ops;
 
+const ROUTES: Param = Param::new(
+    "routes",
+    "",
+    "file to write every route across the scaffold to with the ways to split it into movement functions",
+);
+
+pub const PARAMS: &[Param] = &[ROUTES];
+
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 struct Vec2<T> {
     x: T,
@@ -142,7 +153,7 @@ struct Frame {
 }
 
 impl Frame {
-    fn from_output(output: &[Code]) -> Self {
+    fn from_output(output: &[Code]) -> Result<Self, Box<dyn Error>> {
         let mut map = HashMap::new();
 
         let mut x = 0;
@@ -173,11 +184,11 @@ impl Frame {
 
         let size = Vec2::new(max_x + 1, y + 1);
 
-        Self {
+        Ok(Self {
             map,
             size,
-            robot: robot.unwrap(),
-        }
+            robot: robot.ok_or("no vacuum robot in camera output")?,
+        })
     }
 
     fn pos_is_type(&self, pos: &Vec2<usize>, chr: char) -> bool {
@@ -258,39 +269,142 @@ impl Frame {
         }))
     }
 
-    fn iter_routine<'a>(&'a self) -> impl Iterator<Item = Instruction> + 'a {
-        let mut current_pos = self.robot.pos;
-        let mut current_dir = self.robot.facing;
-        // this is just to avoid having to specify the type
-        let mut current_line = self.follow_line(current_pos, current_dir);
-        let mut has_current_line = false;
+    /// Search the ways to cover the scaffold without turning around until `f` accepts one.
+    /// Routes only differ in where they turn at intersections, going straight is tried first.
+    fn find_route<T>(&self, mut f: impl FnMut(&[Instruction]) -> Option<T>) -> Option<T> {
+        let edges = self
+            .map
+            .iter()
+            .filter(|&(_, &c)| c == SCAFFOLD)
+            .map(|(&pos, _)| self.iter_directions(pos).count())
+            .sum::<usize>()
+            / 2
+            // the robot hides the scaffold it's standing on
+            + self.iter_directions(self.robot.pos).count();
+
+        let mut route = Route {
+            pos: self.robot.pos,
+            facing: self.robot.facing,
+            visited: HashSet::new(),
+            instructions: Vec::new(),
+        };
+        self.extend_route(&mut route, edges, &mut f)
+    }
 
-        iter::from_fn(move || loop {
-            if has_current_line {
-                let mut count = 0;
-                for pos in &mut current_line {
-                    count += 1;
-                    current_pos = pos;
-                }
+    /// Every route across the scaffold, there are exponentially many of them.
+    fn routes(&self) -> Vec<Vec<Instruction>> {
+        let mut routes = Vec::new();
+        self.find_route(|route| {
+            routes.push(route.to_vec());
+            None::<()>
+        });
+
+        routes
+    }
+
+    fn extend_route<T>(
+        &self,
+        route: &mut Route,
+        edges: usize,
+        f: &mut impl FnMut(&[Instruction]) -> Option<T>,
+    ) -> Option<T> {
+        if route.visited.len() == edges {
+            return f(&route.instructions);
+        }
+
+        let start = route.instructions.is_empty();
+        // going straight first finds the route without detours first
+        let mut dirs: Vec<_> = self.iter_directions(route.pos).collect();
+        dirs.sort_by_key(|&dir| dir != route.facing);
+        for dir in dirs {
+            // the robot may only turn around before it starts moving
+            if dir == -route.facing && !start {
+                continue;
+            }
+            let next = match route.pos.add_dir(dir) {
+                Some(next) => next,
+                None => continue,
+            };
+            let edge = if (route.pos.x, route.pos.y) < (next.x, next.y) {
+                (route.pos, next)
+            } else {
+                (next, route.pos)
+            };
+            if route.visited.contains(&edge) {
+                continue;
+            }
+
+            let saved = (route.pos, route.facing, route.instructions.len());
+            let last = route.instructions.last().cloned();
+            route.step(dir, next);
+            route.visited.insert(edge);
+
+            let found = self.extend_route(route, edges, f);
+
+            route.visited.remove(&edge);
+            route.pos = saved.0;
+            route.facing = saved.1;
+            route.instructions.truncate(saved.2);
+            if let Some(last) = last {
+                route.instructions[saved.2 - 1] = last;
+            }
+            if found.is_some() {
+                return found;
+            }
+        }
+
+        None
+    }
 
-                has_current_line = false;
-                return Some(Instruction::Forward(count));
+    /// Draw the camera image, `highlight` is drawn over the scaffold.
+    fn render(&self, highlight: &HashSet<Vec2<usize>>) -> render::Frame {
+        let bounds = Rect::from_points(
+            self.map
+                .keys()
+                .map(|pos| Point::new(pos.x as isize, pos.y as isize)),
+        );
+
+        render::Frame::from_fn(bounds, |p| {
+            let pos = Vec2::new(p.x as usize, p.y as usize);
+            if highlight.contains(&pos) {
+                return Style::plain('O').fg(Color::Cyan).bold();
             }
 
-            let prev_dir = current_dir;
-            current_dir = self
-                .iter_directions(current_pos)
-                .find(|&dir| dir != -current_dir)?;
-            current_line = self.follow_line(current_pos, current_dir);
-            has_current_line = true;
-            // if we need to turn, send the instruction
-            if let Some(instr) = Instruction::from_direction(prev_dir, current_dir) {
-                return Some(instr);
+            match self.map.get(&pos) {
+                Some(&SCAFFOLD) => Style::plain(SCAFFOLD).fg(Color::White),
+                Some(&SPACE) | None => Style::plain(SPACE).dimmed(),
+                Some(&c) => Style::plain(c).fg(Color::Yellow).bold(),
             }
         })
     }
 }
 
+/// Partial route while searching for routes.
+struct Route {
+    pos: Vec2<usize>,
+    facing: Vec2<isize>,
+    /// Scaffold connections already covered, as pairs of adjacent positions.
+    visited: HashSet<(Vec2<usize>, Vec2<usize>)>,
+    instructions: Vec<Instruction>,
+}
+
+impl Route {
+    fn step(&mut self, dir: Vec2<isize>, next: Vec2<usize>) {
+        if dir == -self.facing {
+            self.instructions.extend([RIGHT, RIGHT].iter().cloned());
+        } else if let Some(turn) = Instruction::from_direction(self.facing, dir) {
+            self.instructions.push(turn);
+        }
+
+        match self.instructions.last_mut() {
+            Some(Instruction::Forward(amount)) => *amount += 1,
+            _ => self.instructions.push(Instruction::Forward(1)),
+        }
+        self.pos = next;
+        self.facing = dir;
+    }
+}
+
 impl fmt::Display for Frame {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         for y in 0..self.size.y {
@@ -311,8 +425,33 @@ impl fmt::Display for Frame {
     }
 }
 
+/// Split the continuous video feed into camera images, they're separated by blank lines.
+/// Prompts mixed into the feed are skipped.
+fn video_frames(feed: &[Code]) -> Vec<Frame> {
+    let text: String = feed.iter().map(|&c| c as u8 as char).collect();
+    let is_camera_line = |line: &&str| {
+        !line.is_empty()
+            && line.chars().all(|c| {
+                [SPACE, SCAFFOLD, ROBOT_UP, ROBOT_DOWN, ROBOT_LEFT, ROBOT_RIGHT]
+                    .contains(&c)
+            })
+    };
+
+    text.split("\n\n")
+        .filter_map(|chunk| {
+            let image: Vec<Code> = chunk
+                .lines()
+                .filter(is_camera_line)
+                .flat_map(|line| line.chars().chain(iter::once(EOL)))
+                .map(|c| c as Code)
+                .collect();
+            Frame::from_output(&image).ok()
+        })
+        .collect()
+}
+
 fn get_frame(m: &mut Machine) -> Result<Frame, Box<dyn Error>> {
-    m.run(&[]).map(|out| Frame::from_output(&out))
+    Frame::from_output(&m.run(&[])?)
 }
 
 pub fn first(i: &Input) -> Result<String, Box<dyn Error>> {
@@ -327,7 +466,7 @@ pub fn first(i: &Input) -> Result<String, Box<dyn Error>> {
     Ok(checksum.to_string())
 }
 
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
 enum Instruction {
     Forward(usize),
     Turn(char),
@@ -383,175 +522,405 @@ impl fmt::Display for Instruction {
     }
 }
 
-fn get_instruction_slice(instructions: &[Instruction], len: usize) -> &[Instruction] {
-    let mut routine_chars = 0;
-    let mut end = 0;
+/// Number of characters the instructions take up when separated by commas.
+fn routine_chars(instructions: &[Instruction]) -> usize {
+    let chars: usize = instructions.iter().map(Instruction::chars).sum();
+    chars + instructions.len().saturating_sub(1)
+}
 
-    for instr in instructions {
-        let chars = instr.chars();
-        if routine_chars + chars > len {
-            break;
+fn instructions_to_code(instructions: &[Instruction], code: &mut Vec<Code>) {
+    let instr_len = instructions.len();
+    for (i, instr) in instructions.iter().enumerate() {
+        instr.push_code(code);
+        if i != instr_len - 1 {
+            code.push(',' as Code);
         }
-        routine_chars += chars;
-        end += 1;
     }
-
-    &instructions[..=end]
 }
 
-fn find_instruction_pattern<'a>(
-    instructions: &'a [Instruction],
-    pattern: &'a [Instruction],
-) -> impl Iterator<Item = (usize, usize)> + 'a {
-    let mut start = 0;
-    let end = instructions.len() - pattern.len();
-
-    iter::from_fn(move || {
-        while start <= end {
-            let bounds = (start, start + pattern.len());
-            let slice = &instructions[bounds.0..bounds.1];
-            if slice == pattern {
-                start = bounds.1;
-                return Some(bounds);
-            }
+/// The robot always expects this many movement functions.
+const MOVEMENT_FUNCTIONS: usize = 3;
 
-            start += 1;
-        }
+/// Characters the robot can store for the main routine and each movement function.
+const ROUTINE_CHARS: usize = 20;
 
-        None
-    })
+/// Movement functions and the main routine calling them by index.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+struct Compression {
+    main: Vec<usize>,
+    routines: Vec<Vec<Instruction>>,
 }
 
-fn remove_instruction_pattern(
-    instructions: &[Instruction],
-    pattern: &[Instruction],
-) -> Vec<Instruction> {
-    if pattern.len() > instructions.len() {
-        return instructions.to_vec();
+impl Compression {
+    fn function_name(routine: usize) -> char {
+        (b'A' + routine as u8) as char
     }
 
-    let mut remaining = Vec::with_capacity(instructions.len());
-
-    let mut next_start = 0;
-    for (start, end) in find_instruction_pattern(instructions, pattern) {
-        remaining.extend(instructions[next_start..start].iter().cloned());
-        next_start = end;
+    fn main_chars(&self) -> usize {
+        (self.main.len() * 2).saturating_sub(1)
     }
 
-    remaining.extend(instructions[next_start..].iter().cloned());
-    remaining
-}
-
-fn split_instructions(
-    instructions: &[Instruction],
-    num: usize,
-    routines: &mut Vec<Vec<Instruction>>,
-) -> bool {
-    if num == 0 {
-        return instructions.is_empty();
+    #[cfg(test)]
+    fn expand(&self) -> Vec<Instruction> {
+        self.main
+            .iter()
+            .flat_map(|&r| self.routines[r].iter().cloned())
+            .collect()
     }
 
-    let max_len = cmp::min(instructions.len(), 10);
+    /// Unused movement functions still have to be sent, they get a single turn.
+    fn push_code(&self, code: &mut Vec<Code>) {
+        for (i, &r) in self.main.iter().enumerate() {
+            if i > 0 {
+                code.push(',' as Code);
+            }
+            code.push(Self::function_name(r) as Code);
+        }
+        code.push(EOL as Code);
 
-    for len in 2..=max_len {
-        let routine = get_instruction_slice(instructions, len);
-        let leftover = remove_instruction_pattern(instructions, routine);
-        if split_instructions(&leftover, num - 1, routines) {
-            routines.push(routine.to_vec());
-            return true;
+        for r in 0..cmp::max(self.routines.len(), MOVEMENT_FUNCTIONS) {
+            match self.routines.get(r) {
+                Some(routine) => instructions_to_code(routine, code),
+                None => RIGHT.push_code(code),
+            }
+            code.push(EOL as Code);
         }
     }
-
-    false
 }
 
-fn instructions_to_code(instructions: &[Instruction], code: &mut Vec<Code>) {
-    let instr_len = instructions.len();
-    for (i, instr) in instructions.iter().enumerate() {
-        instr.push_code(code);
-        if i != instr_len - 1 {
-            code.push(',' as Code);
-        }
+impl fmt::Display for Compression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut code = Vec::new();
+        self.push_code(&mut code);
+        let text: String = code.iter().map(|&c| c as u8 as char).collect();
+        write!(f, "{}", text)
     }
 }
 
-struct MainRoutine {
-    main: Vec<char>,
-    a: Vec<Instruction>,
-    b: Vec<Instruction>,
-    c: Vec<Instruction>,
+/// Find every way to split the instructions into at most `max_routines` movement functions
+/// where the functions and the main routine fit into `budget` characters.
+/// Functions are named in the order they're first called.
+fn compress(instructions: &[Instruction], max_routines: usize, budget: usize) -> Vec<Compression> {
+    let mut solutions = Vec::new();
+    compress_from(
+        instructions,
+        max_routines,
+        budget,
+        &mut Compression::default(),
+        &mut solutions,
+    );
+
+    solutions
 }
 
-impl MainRoutine {
-    fn from_instructions(instructions: &[Instruction]) -> Result<Self, Box<dyn Error>> {
-        let mut routines = Vec::new();
-        if !split_instructions(instructions, 3, &mut routines) {
-            return Err("couldn't split instructions into 3 routines".into());
+fn compress_from(
+    instructions: &[Instruction],
+    max_routines: usize,
+    budget: usize,
+    current: &mut Compression,
+    solutions: &mut Vec<Compression>,
+) {
+    if current.main_chars() > budget {
+        return;
+    }
+    if instructions.is_empty() {
+        solutions.push(current.clone());
+        return;
+    }
+
+    for r in 0..current.routines.len() {
+        if instructions.starts_with(&current.routines[r]) {
+            let len = current.routines[r].len();
+            current.main.push(r);
+            compress_from(&instructions[len..], max_routines, budget, current, solutions);
+            current.main.pop();
         }
+    }
 
-        let mut drain = routines.drain(..);
-        let a = drain.next().unwrap();
-        let b = drain.next().unwrap();
-        let c = drain.next().unwrap();
+    if current.routines.len() == max_routines {
+        return;
+    }
+    for len in 1..=instructions.len() {
+        let routine = &instructions[..len];
+        if routine_chars(routine) > budget {
+            break;
+        }
+        // would be the same as calling an existing function
+        if current.routines.iter().any(|r| r[..] == *routine) {
+            continue;
+        }
 
-        let mut indices: Vec<_> = find_instruction_pattern(instructions, &a)
-            .map(|(i, _)| (i, 'A'))
-            .collect();
-        indices.extend(find_instruction_pattern(instructions, &b).map(|(i, _)| (i, 'B')));
-        indices.extend(find_instruction_pattern(instructions, &c).map(|(i, _)| (i, 'C')));
+        current.main.push(current.routines.len());
+        current.routines.push(routine.to_vec());
+        compress_from(&instructions[len..], max_routines, budget, current, solutions);
+        current.routines.pop();
+        current.main.pop();
+    }
+}
 
-        indices.sort_by_key(|(i, _)| *i);
+/// Call `f` with every distinct compression of the routes across the scaffold until it returns
+/// a value. Routes are compressed one at a time as they're found.
+fn find_compressions<T>(
+    frame: &Frame,
+    max_routines: usize,
+    budget: usize,
+    mut f: impl FnMut(&Compression) -> Option<T>,
+) -> Option<T> {
+    let mut seen = HashSet::new();
+    frame.find_route(|route| {
+        compress(route, max_routines, budget)
+            .into_iter()
+            .filter(|solution| seen.insert(solution.clone()))
+            .find_map(|solution| f(&solution))
+    })
+}
 
-        Ok(Self {
-            main: indices.iter().map(|(_, c)| *c).collect(),
-            a,
-            b,
-            c,
-        })
-    }
+/// Compress the first route across the scaffold which can be compressed.
+fn find_compression(frame: &Frame, max_routines: usize, budget: usize) -> Option<Compression> {
+    find_compressions(frame, max_routines, budget, |solution| Some(solution.clone()))
+}
 
-    fn push_code(&self, code: &mut Vec<Code>) {
-        for (i, &c) in self.main.iter().enumerate() {
-            code.push(c as Code);
-            if i != self.main.len() - 1 {
-                code.push(',' as Code);
-            }
+/// List every route with the ways to split it into the movement functions of the robot.
+fn routes_report(frame: &Frame) -> String {
+    let mut report = String::new();
+    for (n, route) in frame.routes().iter().enumerate() {
+        let instructions: Vec<_> = route.iter().map(Instruction::to_string).collect();
+        report += &format!("Route {}: {}\n", n + 1, instructions.join(","));
+        for solution in compress(route, MOVEMENT_FUNCTIONS, ROUTINE_CHARS) {
+            report += &format!("\n{}", solution);
         }
-        code.push(EOL as Code);
-
-        instructions_to_code(&self.a, code);
-        code.push(EOL as Code);
-        instructions_to_code(&self.b, code);
-        code.push(EOL as Code);
-        instructions_to_code(&self.c, code);
-        code.push(EOL as Code);
+        report.push('\n');
     }
+
+    report
 }
 
-pub fn second(i: &Input) -> Result<String, Box<dyn Error>> {
+/// Wake the robot up and let it clean the scaffold, recording the video feed if requested.
+/// Returns the amount of dust it collected.
+fn collect_dust(i: &Input, rec: Option<&mut Recording>) -> Result<Code, Box<dyn Error>> {
     let mut m = Machine::from_input(i)?;
     let frame = get_frame(&mut m.clone())?;
 
     m.write(0, 2);
     m.start();
 
-    let instructions: Vec<_> = frame.iter_routine().collect();
-    let routine = MainRoutine::from_instructions(&instructions)?;
+    let compression = find_compression(&frame, MOVEMENT_FUNCTIONS, ROUTINE_CHARS).ok_or_else(|| {
+        format!(
+            "couldn't split any route into {} movement functions",
+            MOVEMENT_FUNCTIONS
+        )
+    })?;
 
     let mut inp = Vec::new();
-    routine.push_code(&mut inp);
+    compression.push_code(&mut inp);
 
     m.input.extend(&inp);
 
-    m.send('n' as Code)?;
+    let video = if rec.is_some() { 'y' } else { 'n' };
+    m.send(video as Code)?;
     m.send(EOL as Code)?;
 
     if !m.is_done() {
         return Err("robot didn't finish".into());
     }
 
-    m.take_output()
-        .last()
-        .ok_or_else(|| "received no output".into())
-        .map(|v| v.to_string())
+    let out = m.take_output();
+    let (&dust, feed) = out.split_last().ok_or("received no output")?;
+    if let Some(rec) = rec {
+        let frames = video_frames(feed);
+        for (n, frame) in frames.iter().enumerate() {
+            let caption = if n + 1 == frames.len() {
+                format!("Collected {} dust", dust)
+            } else {
+                format!("Frame {}/{}", n + 1, frames.len())
+            };
+            rec.push(frame.render(&HashSet::new()).with_caption(caption));
+        }
+    }
+
+    Ok(dust)
+}
+
+pub fn second(i: &Input) -> Result<String, Box<dyn Error>> {
+    let frame = get_frame(&mut Machine::from_input(i)?)?;
+    ROUTES.write_file(i, || routes_report(&frame))?;
+
+    collect_dust(i, None).map(|dust| dust.to_string())
+}
+
+/// Show the intersections for the first part and the video feed of the robot for the second.
+pub fn visualize(i: &Input, second: bool) -> Result<Recording, Box<dyn Error>> {
+    let mut rec = Recording::new();
+    if second {
+        collect_dust(i, Some(&mut rec))?;
+        return Ok(rec);
+    }
+
+    let frame = get_frame(&mut Machine::from_input(i)?)?;
+    let intersections: HashSet<_> = frame
+        .find_intersections()
+        .ok_or("couldn't find intersections")?
+        .collect();
+    let checksum: usize = intersections.iter().map(|pos| pos.x * pos.y).sum();
+    rec.push(
+        frame
+            .render(&intersections)
+            .with_caption(format!("Sum of alignment parameters: {}", checksum)),
+    );
+
+    Ok(rec)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCAFFOLD_MAP: &str = "\
+#######...#####
+#.....#...#...#
+#.....#...#...#
+......#...#...#
+......#...###.#
+......#.....#.#
+^########...#.#
+......#.#...#.#
+......#########
+........#...#..
+....#########..
+....#...#......
+....#...#......
+....#...#......
+....#####......
+";
+
+    fn frame() -> Frame {
+        let output: Vec<Code> = SCAFFOLD_MAP.chars().map(|c| c as Code).collect();
+        Frame::from_output(&output).expect("camera output has a robot")
+    }
+
+    fn parse_routine(s: &str) -> Vec<Instruction> {
+        s.split(',')
+            .map(|part| match part.parse() {
+                Ok(amount) => Instruction::Forward(amount),
+                Err(_) => Instruction::Turn(part.chars().next().unwrap()),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_video_frames() {
+        let mut feed = String::from(SCAFFOLD_MAP);
+        feed.push_str("\nMain:\nFunction A:\nFunction B:\nFunction C:\nContinuous video feed?\n");
+        feed.push_str(&SCAFFOLD_MAP.replace("^#", "#>"));
+        feed.push('\n');
+        let feed: Vec<Code> = feed.chars().map(|c| c as Code).collect();
+
+        let frames = video_frames(&feed);
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].robot.pos, Vec2::new(0, 6));
+        assert_eq!(frames[1].robot.pos, Vec2::new(1, 6));
+        assert_eq!(frames[1].robot.facing, Vec2::RIGHT);
+
+        let rendered = frames[1].render(&HashSet::new());
+        assert_eq!((rendered.width(), rendered.height()), (15, 15));
+        assert_eq!(rendered.get(Point::new(1, 6)).unwrap().ch, '>');
+    }
+
+    #[test]
+    fn test_routes() {
+        let routes = frame().routes();
+        assert_eq!(
+            routes[0],
+            parse_routine("R,8,R,8,R,4,R,4,R,8,L,6,L,2,R,4,R,4,R,8,R,8,R,8,L,6,L,2")
+        );
+        // turning at one of the intersections instead of going straight
+        assert!(routes.contains(&parse_routine(
+            "R,8,R,8,R,4,R,4,R,8,L,2,R,2,L,8,L,4,L,4,L,2,R,4,R,6,R,8,L,6,L,2"
+        )));
+        assert_eq!(routes.len(), 16);
+        for route in &routes {
+            let steps: usize = route
+                .iter()
+                .map(|instr| match instr {
+                    Instruction::Forward(amount) => *amount,
+                    Instruction::Turn(_) => 0,
+                })
+                .sum();
+            assert_eq!(steps, 80);
+        }
+    }
+
+    #[test]
+    fn test_compress() {
+        let route = parse_routine("R,8,R,8,R,4,R,4,R,8,L,6,L,2,R,4,R,4,R,8,R,8,R,8,L,6,L,2");
+        let expected = Compression {
+            main: vec![0, 1, 2, 1, 0, 2],
+            routines: vec![
+                parse_routine("R,8,R,8"),
+                parse_routine("R,4,R,4,R,8"),
+                parse_routine("L,6,L,2"),
+            ],
+        };
+
+        let solutions = compress(&route, 3, 20);
+        assert!(solutions.contains(&expected));
+        for solution in &solutions {
+            assert_eq!(solution.expand(), route);
+            assert!(solution.routines.len() <= 3);
+            assert!(solution.main_chars() <= 20);
+            assert!(solution.routines.iter().all(|r| routine_chars(r) <= 20));
+        }
+        assert_eq!(
+            expected.to_string(),
+            "A,B,C,B,A,C\nR,8,R,8\nR,4,R,4,R,8\nL,6,L,2\n"
+        );
+
+        // a tighter budget leaves no room for the functions above
+        assert!(!compress(&route, 3, 10).contains(&expected));
+        assert!(compress(&route, 1, 20).is_empty());
+    }
+
+    #[test]
+    fn test_find_compression() {
+        let routes = frame().routes();
+
+        // the search stops at the first accepted route
+        let mut tried = 0;
+        let third = frame().find_route(|route| {
+            tried += 1;
+            Some(route.to_vec()).filter(|_| tried == 3)
+        });
+        assert_eq!((tried, third.as_ref()), (3, routes.get(2)));
+
+        let solution = find_compression(&frame(), 3, 20).unwrap();
+        assert_eq!(solution.expand(), routes[0]);
+        assert_eq!(find_compression(&frame(), 2, 20), None);
+    }
+
+    #[test]
+    fn test_find_compressions() {
+        let routes = frame().routes();
+        let mut solutions = Vec::new();
+        find_compressions(&frame(), 3, 20, |solution| {
+            solutions.push(solution.clone());
+            None::<()>
+        });
+
+        assert!(solutions.len() > 1);
+        assert_eq!(solutions.first(), find_compression(&frame(), 3, 20).as_ref());
+        for (n, solution) in solutions.iter().enumerate() {
+            assert!(routes.contains(&solution.expand()));
+            assert!(!solutions[..n].contains(solution));
+        }
+    }
+
+    #[test]
+    fn test_routes_report() {
+        let report = routes_report(&frame());
+        assert!(report.starts_with(
+            "Route 1: R,8,R,8,R,4,R,4,R,8,L,6,L,2,R,4,R,4,R,8,R,8,R,8,L,6,L,2\n\n"
+        ));
+        assert!(report.contains("\nA,B,C,B,A,C\nR,8,R,8\nR,4,R,4,R,8\nL,6,L,2\n"));
+        assert_eq!(report.matches("Route ").count(), 16);
+    }
 }
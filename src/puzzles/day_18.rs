@@ -1,201 +1,248 @@
 use crate::input::Input;
-use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use crate::lib::grid::{Grid, Point};
+use crate::lib::search;
 use std::error::Error;
 
-type Position = (usize, usize);
-fn surrounding_positions((x, y): Position) -> Vec<Position> {
-    let mut surroundings = vec![(x + 1, y), (x, y + 1)];
+/// Set of keys (or doors), bit 0 is `a`.
+type KeySet = u32;
 
-    if x > 0 {
-        surroundings.push((x - 1, y));
-    }
-    if y > 0 {
-        surroundings.push((x, y - 1));
+fn key_bit(key: u8) -> KeySet {
+    1 << key
+}
+
+fn key_name(key: u8) -> char {
+    (b'a' + key) as char
+}
+
+fn key_names(keys: KeySet) -> Vec<char> {
+    (0..26)
+        .filter(|&k| keys & key_bit(k) != 0)
+        .map(key_name)
+        .collect()
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Tile {
+    Wall,
+    Open,
+    Start,
+    Door(u8),
+    Key(u8),
+}
+
+impl Tile {
+    fn from_char(c: char) -> Option<Self> {
+        let tile = match c {
+            '#' => Self::Wall,
+            '.' => Self::Open,
+            '@' => Self::Start,
+            'A'..='Z' => Self::Door(c as u8 - b'A'),
+            'a'..='z' => Self::Key(c as u8 - b'a'),
+            _ => return None,
+        };
+
+        Some(tile)
     }
+}
 
-    surroundings
+/// Node of the key graph.
+/// Keys use their index, the start positions of the robots follow after the last key.
+type Node = u8;
+
+const ROBOT_NODES: Node = 26;
+
+/// Shortest way from a node to a key.
+#[derive(Clone, Debug)]
+struct Edge {
+    key: u8,
+    steps: usize,
+    /// Doors passed on the way.
+    doors: KeySet,
+    /// Other keys passed on the way.
+    keys: KeySet,
 }
-type DoorID = char;
 
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
-struct SharedState {
-    keys: BTreeSet<DoorID>,
-    positions: Vec<Position>,
+struct State {
+    positions: Vec<Node>,
+    keys: KeySet,
 }
 
-impl SharedState {
-    fn new(keys: BTreeSet<DoorID>, positions: Vec<Position>) -> Self {
-        Self { keys, positions }
-    }
+/// Order in which the robots collect the keys.
+#[derive(Debug, Eq, PartialEq)]
+struct Route {
+    steps: usize,
+    /// Keys collected by each robot in order.
+    robots: Vec<Vec<char>>,
 }
 
-#[derive(Debug)]
 struct Vault {
-    walls: HashSet<Position>,
-    doors: HashMap<Position, DoorID>,
-    keys: HashMap<Position, DoorID>,
-    starts: Vec<Position>,
+    grid: Grid<Tile>,
 }
 
 impl Vault {
-    fn new(
-        walls: HashSet<Position>,
-        doors: HashMap<Position, DoorID>,
-        keys: HashMap<Position, DoorID>,
-        starts: Vec<Position>,
-    ) -> Self {
-        Self {
-            walls,
-            doors,
-            keys,
-            starts,
-        }
-    }
-
     fn from_input(i: &Input) -> Result<Vault, Box<dyn Error>> {
-        let mut walls = HashSet::new();
-        let mut doors = HashMap::new();
-        let mut keys = HashMap::new();
-
-        let mut starts = Vec::new();
+        let grid = Grid::from_input(i, Tile::from_char)?;
+        Ok(Vault { grid })
+    }
 
-        for (y, line) in i.lines().enumerate() {
-            for (x, tile) in line.chars().enumerate() {
-                match tile {
-                    '#' => {
-                        walls.insert((x, y));
-                    }
-                    '.' => (),
-                    '@' => starts.push((x, y)),
-                    _ => {
-                        if tile.is_ascii_uppercase() {
-                            doors.insert((x, y), tile);
-                        } else {
-                            keys.insert((x, y), tile.to_ascii_uppercase());
-                        }
-                    }
-                }
-            }
-        }
-        Ok(Vault::new(walls, doors, keys, starts))
+    fn starts(&self) -> Vec<Point> {
+        self.grid
+            .iter()
+            .filter(|(_, &tile)| tile == Tile::Start)
+            .map(|(p, _)| p)
+            .collect()
     }
 
+    /// Wall off the surroundings of the single start and place four robots on the diagonals.
     fn split(&mut self) -> bool {
-        if self.starts.len() != 1 {
+        let start = match self.starts()[..] {
+            [start] => start,
+            _ => return false,
+        };
+        if start.neighbours8().any(|p| !self.grid.contains(p)) {
             return false;
         }
 
-        let (start_x, start_y) = self.starts[0];
-
-        for x in start_x - 1..=start_x + 1 {
-            self.walls.insert((x, start_y));
-        }
-
-        for y in start_y - 1..=start_y + 1 {
-            self.walls.insert((start_x, y));
+        for (i, &offset) in Point::OFFSETS_8.iter().enumerate() {
+            let diagonal = i % 2 == 1;
+            self.grid[start + offset] = if diagonal { Tile::Start } else { Tile::Wall };
         }
-        self.starts = vec![
-            (start_x - 1, start_y - 1),
-            (start_x - 1, start_y + 1),
-            (start_x + 1, start_y + 1),
-            (start_x + 1, start_y - 1),
-        ];
+        self.grid[start] = Tile::Wall;
 
         true
     }
 
-    fn valid_position(&self, keys: &BTreeSet<DoorID>, pos: &Position) -> bool {
-        if self.walls.contains(pos) {
-            return false;
-        }
+    /// Find the shortest way from the point to every reachable key.
+    fn edges_from(&self, start: Point) -> Vec<Edge> {
+        let neighbours = |&p: &Point| {
+            self.grid
+                .neighbours4(p)
+                .filter(|&n| self.grid[n] != Tile::Wall)
+                .collect::<Vec<_>>()
+        };
+        let search = search::bfs(vec![start], neighbours, |_| false);
+
+        let mut edges = Vec::new();
+        for (&p, &steps) in search.distances.iter() {
+            let key = match self.grid[p] {
+                Tile::Key(key) if p != start => key,
+                _ => continue,
+            };
+
+            let mut edge = Edge {
+                key,
+                steps,
+                doors: 0,
+                keys: 0,
+            };
+            let path = search.path_to(&p).unwrap_or_default();
+            for &p in &path[1..path.len() - 1] {
+                match self.grid[p] {
+                    Tile::Door(door) => edge.doors |= key_bit(door),
+                    Tile::Key(key) => edge.keys |= key_bit(key),
+                    _ => (),
+                }
+            }
 
-        if let Some(door_id) = self.doors.get(pos) {
-            return keys.contains(door_id);
+            edges.push(edge);
         }
 
-        true
+        edges
     }
 
-    fn bfs_keys(
-        &self,
-        keys: &BTreeSet<DoorID>,
-        start_pos: Position,
-    ) -> HashMap<DoorID, (Position, usize)> {
-        let mut key_distances = HashMap::new();
-        let mut seen = HashSet::new();
-        seen.insert(start_pos);
-
-        let mut queue = VecDeque::new();
-        queue.push_back((start_pos, 0));
-
-        while let Some((pos, prev_dist)) = queue.pop_front() {
-            for pos in surrounding_positions(pos) {
-                if !self.valid_position(keys, &pos) || seen.contains(&pos) {
-                    continue;
-                }
-                seen.insert(pos);
-
-                let dist = prev_dist + 1;
-                if let Some(door_id) = self.keys.get(&pos) {
-                    if !keys.contains(door_id) {
-                        key_distances.insert(*door_id, (pos, dist));
-                        continue;
-                    }
-                }
-
-                queue.push_back((pos, dist));
+    /// Build the graph connecting the starts and keys.
+    /// The edges are indexed by node.
+    fn key_graph(&self, starts: &[Point]) -> Vec<Vec<Edge>> {
+        let mut graph = vec![Vec::new(); ROBOT_NODES as usize + starts.len()];
+        for (p, &tile) in self.grid.iter() {
+            if let Tile::Key(key) = tile {
+                graph[key as usize] = self.edges_from(p);
             }
         }
+        for (robot, &start) in starts.iter().enumerate() {
+            graph[ROBOT_NODES as usize + robot] = self.edges_from(start);
+        }
 
-        key_distances
+        graph
     }
 
-    fn steps_from_shared_state(
-        &self,
-        cache: &mut HashMap<SharedState, usize>,
-        state: SharedState,
-    ) -> usize {
-        if let Some(dist) = cache.get(&state) {
-            return *dist;
-        }
+    fn all_keys(&self) -> KeySet {
+        self.grid.iter().fold(0, |keys, (_, &tile)| match tile {
+            Tile::Key(key) => keys | key_bit(key),
+            _ => keys,
+        })
+    }
 
-        if state.keys.len() == self.keys.len() {
-            cache.insert(state, 0);
-            return 0;
+    fn collect_keys(&self) -> Result<Route, Box<dyn Error>> {
+        let starts = self.starts();
+        if starts.is_empty() {
+            return Err("vault has no entrance".into());
         }
 
-        let mut min_dist = std::usize::MAX;
-        for (state_idx, pos) in state.positions.iter().enumerate() {
-            let key_distances = self.bfs_keys(&state.keys, *pos);
-            for (door_id, (key_pos, mut dist)) in key_distances {
-                let mut next_state = state.clone();
-                next_state.positions[state_idx] = key_pos;
-                next_state.keys.insert(door_id);
-                dist += self.steps_from_shared_state(cache, next_state);
-                if dist < min_dist {
-                    min_dist = dist;
+        let graph = self.key_graph(&starts);
+        let all_keys = self.all_keys();
+
+        let neighbours = |state: &State| {
+            let mut next = Vec::new();
+            for (robot, &node) in state.positions.iter().enumerate() {
+                for edge in graph[node as usize].iter() {
+                    let missing = !state.keys;
+                    // picking up a key on the way is never worse than walking past it
+                    if missing & key_bit(edge.key) == 0
+                        || missing & edge.doors != 0
+                        || missing & edge.keys != 0
+                    {
+                        continue;
+                    }
+
+                    let mut positions = state.positions.clone();
+                    positions[robot] = edge.key;
+                    let keys = state.keys | key_bit(edge.key);
+                    next.push((State { positions, keys }, edge.steps));
                 }
             }
-        }
 
-        if min_dist == std::usize::MAX {
-            panic!("impossible to collect missing keys");
+            next
+        };
+
+        let start = State {
+            positions: (0..starts.len() as Node).map(|r| ROBOT_NODES + r).collect(),
+            keys: 0,
+        };
+        let search = search::dijkstra(vec![start], neighbours, |s| s.keys == all_keys);
+
+        let path = match search.goal_path() {
+            Some(path) => path,
+            None => {
+                let collectable = search.visited.iter().fold(0, |keys, s| keys | s.keys);
+                let missing: Vec<_> = key_names(all_keys & !collectable)
+                    .into_iter()
+                    .map(String::from)
+                    .collect();
+                return Err(format!("impossible to collect keys: {}", missing.join(", ")).into());
+            }
+        };
+
+        let mut robots = vec![Vec::new(); starts.len()];
+        for states in path.windows(2) {
+            let (prev, next) = (&states[0], &states[1]);
+            let robot = (0..starts.len())
+                .find(|&r| prev.positions[r] != next.positions[r])
+                .ok_or("no robot moved")?;
+            robots[robot].push(key_name(next.positions[robot]));
         }
 
-        cache.insert(state, min_dist);
-        min_dist
-    }
-
-    fn steps_from_start(&self) -> usize {
-        let mut cache = HashMap::new();
-        let state = SharedState::new(BTreeSet::new(), self.starts.clone());
-        self.steps_from_shared_state(&mut cache, state)
+        Ok(Route {
+            steps: search.goal_distance().unwrap_or_default(),
+            robots,
+        })
     }
 }
 
 pub fn first(i: &Input) -> Result<String, Box<dyn Error>> {
     let vault = Vault::from_input(i)?;
-    Ok(vault.steps_from_start().to_string())
+    Ok(vault.collect_keys()?.steps.to_string())
 }
 
 pub fn second(i: &Input) -> Result<String, Box<dyn Error>> {
@@ -203,7 +250,7 @@ pub fn second(i: &Input) -> Result<String, Box<dyn Error>> {
     if !vault.split() {
         return Err("couldn't split vault".into());
     }
-    Ok(vault.steps_from_start().to_string())
+    Ok(vault.collect_keys()?.steps.to_string())
 }
 
 #[cfg(test)]
@@ -238,4 +285,81 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_second() -> Result<(), Box<dyn Error>> {
+        assert_eq!(
+            second(&Input::new(
+                "
+                #######
+                #a.#Cd#
+                ##...##
+                ##.@.##
+                ##...##
+                #cB#Ab#
+                #######
+                "
+            ))?,
+            "8"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_route() -> Result<(), Box<dyn Error>> {
+        let vault = Vault::from_input(&Input::new(
+            "
+            #########
+            #b.A.@.a#
+            #########
+            ",
+        ))?;
+        assert_eq!(
+            vault.collect_keys()?,
+            Route {
+                steps: 8,
+                robots: vec![vec!['a', 'b']],
+            }
+        );
+
+        let mut vault = Vault::from_input(&Input::new(
+            "
+            ###############
+            #d.ABC.#.....a#
+            ######...######
+            ######.@.######
+            ######...######
+            #b.....#.....c#
+            ###############
+            ",
+        ))?;
+        assert!(vault.split());
+        assert_eq!(
+            vault.collect_keys()?,
+            Route {
+                steps: 24,
+                robots: vec![vec!['d'], vec!['a'], vec!['b'], vec!['c']],
+            }
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_unreachable_keys() -> Result<(), Box<dyn Error>> {
+        let vault = Vault::from_input(&Input::new(
+            "
+            #########
+            #b.A.@#a#
+            #########
+            ",
+        ))?;
+        assert_eq!(
+            vault.collect_keys().unwrap_err().to_string(),
+            "impossible to collect keys: a, b"
+        );
+
+        Ok(())
+    }
 }
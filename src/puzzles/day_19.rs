@@ -1,8 +1,13 @@
-use crate::input::Input;
+use crate::input::{Input, Param};
 use crate::lib::intcode::{Code, Machine};
 use std::cmp;
 use std::error::Error;
 
+const AREA: Param = Param::new("area", "50", "Size of the area to scan in the first part");
+const SHIP: Param = Param::new("ship", "100", "Size of the ship which has to fit in the beam");
+
+pub const PARAMS: &[Param] = &[AREA, SHIP];
+
 fn scan_once(mut m: Machine, x: Code, y: Code) -> Result<bool, Box<dyn Error>> {
     m.run(&[x, y])?
         .first()
@@ -25,7 +30,7 @@ fn count_beam_area(m: &Machine, area: usize) -> Result<usize, Box<dyn Error>> {
 
 pub fn first(i: &Input) -> Result<String, Box<dyn Error>> {
     let m = Machine::from_input(i)?;
-    count_beam_area(&m, 50).map(|c| c.to_string())
+    count_beam_area(&m, AREA.get(i)?).map(|c| c.to_string())
 }
 
 fn find_beam_start(
@@ -87,5 +92,5 @@ fn find_area(m: &Machine, width: usize) -> Result<(usize, usize), Box<dyn Error>
 
 pub fn second(i: &Input) -> Result<String, Box<dyn Error>> {
     let m = Machine::from_input(i)?;
-    find_area(&m, 100).map(|(x, y)| (10_000 * x + y).to_string())
+    find_area(&m, SHIP.get(i)?).map(|(x, y)| (10_000 * x + y).to_string())
 }
@@ -1,79 +1,111 @@
-use crate::input::Input;
+use crate::input::{Input, Param};
 use crate::lib::intcode;
+use crate::lib::springscript::{self, Mode, Oracle, Outcome, Program};
 use std::error::Error;
+use std::fs;
 
-fn render_output(out: &[intcode::Code]) -> String {
-    let mut s = String::with_capacity(out.len());
-    s.push_str("Failed:\n");
-    s.extend(out.iter().map(|c| *c as u8 as char));
-    s
-}
+const WALK_PROGRAM: Param = Param::new(
+    "walk_program",
+    "",
+    "file with a springscript program to run in the first part instead of finding one (without WALK)",
+);
+const RUN_PROGRAM: Param = Param::new(
+    "run_program",
+    "",
+    "file with a springscript program to run in the second part instead of finding one (without RUN)",
+);
 
-fn get_output(m: &mut intcode::Machine) -> Result<String, Box<dyn Error>> {
-    let out = m.take_output();
-    let last = *out.last().ok_or_else(|| "no output")?;
-    if last > std::u8::MAX as isize {
-        Ok(last.to_string())
-    } else {
-        Ok(render_output(&out))
-    }
+pub const PARAMS: &[Param] = &[WALK_PROGRAM, RUN_PROGRAM];
+
+/// Springdroid which runs every program from a fresh copy of its memory.
+struct Droid {
+    machine: intcode::Machine,
 }
 
-fn send_code(m: &mut intcode::Machine, code: &str) -> Result<(), Box<dyn Error>> {
-    for instr in code.lines() {
-        let instr = instr.trim();
-        if instr.is_empty() {
-            continue;
+impl Oracle for Droid {
+    fn check(&mut self, program: &Program) -> Result<Outcome, Box<dyn Error>> {
+        let mut m = self.machine.clone();
+        m.start();
+        m.send_ascii(&program.to_string())?;
+
+        let out = m.take_output();
+        let last = *out.last().ok_or("no output")?;
+        if last > u8::MAX as intcode::Code {
+            return Ok(Outcome::Survived(last as i64));
         }
 
-        m.send_ascii(instr)?;
-        m.send(10)?;
+        let text: String = out.iter().map(|&c| c as u8 as char).collect();
+        Ok(Outcome::Fell(springscript::parse_failure(&text)?))
     }
+}
+
+/// Load the program given as parameter, if there is one.
+fn program_from_param(
+    i: &Input,
+    param: &Param,
+    mode: Mode,
+) -> Result<Option<Program>, Box<dyn Error>> {
+    let path: String = param.get(i)?;
+    if path.is_empty() {
+        return Ok(None);
+    }
+
+    let text = fs::read_to_string(&path)
+        .map_err(|e| format!("couldn't read {} {}: {}", param.name, path, e))?;
+    let program =
+        Program::parse(&text, mode).map_err(|e| format!("invalid program {}: {}", path, e))?;
+    Ok(Some(program))
+}
 
-    Ok(())
+fn survey_hull(i: &Input, param: &Param, mode: Mode) -> Result<String, Box<dyn Error>> {
+    let program = program_from_param(i, param, mode)?;
+    let mut droid = Droid {
+        machine: intcode::Machine::from_input(i)?,
+    };
+
+    let damage = match program {
+        Some(program) => match droid.check(&program)? {
+            Outcome::Survived(damage) => damage,
+            Outcome::Fell(hull) => return Err(format!("droid fell on hull {}", hull).into()),
+        },
+        None => springscript::synthesize(&mut droid, mode, &mut Vec::new())?.1,
+    };
+
+    Ok(damage.to_string())
 }
 
 pub fn first(i: &Input) -> Result<String, Box<dyn Error>> {
-    let mut m = intcode::Machine::from_input(i)?;
-    m.start();
-
-    // (!A or !B or !C) and D
-    send_code(
-        &mut m,
-        "
-        OR A T
-        AND B T
-        AND C T
-        NOT T J
-        AND D J        
-        ",
-    )?;
-    m.output.clear();
-    m.send_ascii("WALK\n")?;
-
-    get_output(&mut m)
+    survey_hull(i, &WALK_PROGRAM, Mode::Walk)
 }
 
 pub fn second(i: &Input) -> Result<String, Box<dyn Error>> {
-    let mut m = intcode::Machine::from_input(i)?;
-    m.start();
-
-    // (!A or !B or !C) and (E or H) and D
-    send_code(
-        &mut m,
-        "
-        OR A T
-        AND B T
-        AND C T
-        NOT T T
-        OR E J
-        OR H J
-        AND T J
-        AND D J        
-        ",
-    )?;
-    m.output.clear();
-    m.send_ascii("RUN\n")?;
-
-    get_output(&mut m)
+    survey_hull(i, &RUN_PROGRAM, Mode::Run)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::temp_path;
+
+    #[test]
+    fn test_program_from_param() -> Result<(), Box<dyn Error>> {
+        assert!(program_from_param(&Input::new(""), &WALK_PROGRAM, Mode::Walk)?.is_none());
+
+        let path = temp_path("walk.springscript");
+        let input = Input::new("").with_param("walk_program", &path.to_string_lossy());
+        fs::write(&path, "NOT A J\nNOT C T\nOR T J\nAND D J\n")?;
+        let program = program_from_param(&input, &WALK_PROGRAM, Mode::Walk)?;
+        assert_eq!(
+            program.map(|p| p.to_string()),
+            Some(String::from("NOT A J\nNOT C T\nOR T J\nAND D J\nWALK\n"))
+        );
+
+        // sensor E can only be used while running
+        fs::write(&path, "NOT E J\n")?;
+        let invalid = program_from_param(&input, &WALK_PROGRAM, Mode::Walk);
+        fs::remove_file(&path)?;
+        assert!(invalid.is_err());
+
+        Ok(())
+    }
 }
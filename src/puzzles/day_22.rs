@@ -1,6 +1,29 @@
-use crate::input::Input;
+use crate::input::{Input, Param};
 use std::error::Error;
 
+const DECK: Param = Param::new("deck", "10007", "Size of the deck in the first part");
+const CARD: Param = Param::new("card", "2019", "Card to find the position of in the first part");
+const BIG_DECK: Param = Param::new(
+    "big_deck",
+    "119315717514047",
+    "Size of the deck in the second part (has to be prime)",
+);
+const REPEAT: Param = Param::new(
+    "repeat",
+    "101741582076661",
+    "How often the shuffle process is repeated in the second part",
+);
+const POSITION: Param = Param::new(
+    "position",
+    "2020",
+    "Position to find the card of in the second part",
+);
+
+pub const PARAMS: &[Param] = &[DECK, CARD, BIG_DECK, REPEAT, POSITION];
+
+/// Largest deck of the second part, products of two positions have to fit into an `i128`.
+const MAX_BIG_DECK: usize = 1 << 62;
+
 type Card = usize;
 
 fn deal_into_new_stack(deck: &mut [Card]) {
@@ -68,6 +91,43 @@ fn modular_pow(mut base: i128, mut exponent: i128, modulus: i128) -> i128 {
     result
 }
 
+/// Deterministic Miller-Rabin test, the bases are enough for every 64 bit number.
+fn is_prime(n: usize) -> bool {
+    const BASES: [i128; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+
+    let n = n as i128;
+    if n < 2 {
+        return false;
+    }
+    for &base in BASES.iter() {
+        if n % base == 0 {
+            return n == base;
+        }
+    }
+
+    let (mut d, mut s) = (n - 1, 0);
+    while d % 2 == 0 {
+        d /= 2;
+        s += 1;
+    }
+
+    'bases: for &base in BASES.iter() {
+        let mut x = modular_pow(base, d, n);
+        if x == 1 || x == n - 1 {
+            continue;
+        }
+        for _ in 1..s {
+            x = x * x % n;
+            if x == n - 1 {
+                continue 'bases;
+            }
+        }
+        return false;
+    }
+
+    true
+}
+
 fn invert_n(deck_len: usize, n: i128) -> i128 {
     let deck_len = deck_len as i128;
     modular_pow(n, deck_len - 2, deck_len)
@@ -153,33 +213,37 @@ fn perform_shuffles<'a>(deck: &mut [Card], lines: impl IntoIterator<Item = &'a s
 }
 
 pub fn first(i: &Input) -> Result<String, Box<dyn Error>> {
-    let mut deck: Vec<_> = (0..10_007).collect();
+    let card: Card = CARD.get(i)?;
+    let mut deck: Vec<_> = (0..DECK.get(i)?).collect();
     perform_shuffles(&mut deck, i.lines());
 
-    Ok(deck
-        .iter()
-        .enumerate()
-        .find(|(_, &v)| v == 2019)
-        .map(|(i, _)| i.to_string())
-        .unwrap())
+    deck.iter()
+        .position(|&v| v == card)
+        .map(|pos| pos.to_string())
+        .ok_or_else(|| "card isn't in the deck".into())
 }
 
 pub fn second(i: &Input) -> Result<String, Box<dyn Error>> {
-    const DECK_LEN: usize = 119_315_717_514_047;
-    const TOTAL_SHUFFLES: usize = 101_741_582_076_661;
+    let deck_len: usize = BIG_DECK.get(i)?;
+    // the inverses are calculated with Fermat's little theorem
+    if deck_len > MAX_BIG_DECK || !is_prime(deck_len) {
+        return Err(format!("big deck has to be a prime below {}: {}", MAX_BIG_DECK, deck_len).into());
+    }
+    let total_shuffles: usize = REPEAT.get(i)?;
+    let position: i128 = POSITION.get(i)?;
 
     let mut increment = 1;
     let mut offset = 0;
     for shuffle in iter_shuffles(i.lines()) {
-        shuffle.simulate_step(DECK_LEN, &mut increment, &mut offset);
+        shuffle.simulate_step(deck_len, &mut increment, &mut offset);
     }
 
-    let total_increment = modular_pow(increment, TOTAL_SHUFFLES as i128, DECK_LEN as i128);
-    let mut total_offset = offset * (1 - total_increment) % DECK_LEN as i128;
-    total_offset *= invert_n(DECK_LEN, (1 - increment) % DECK_LEN as i128);
-    total_offset %= DECK_LEN as i128;
+    let total_increment = modular_pow(increment, total_shuffles as i128, deck_len as i128);
+    let mut total_offset = offset * (1 - total_increment) % deck_len as i128;
+    total_offset *= invert_n(deck_len, (1 - increment) % deck_len as i128);
+    total_offset %= deck_len as i128;
 
-    let index = (total_offset + 2020 * total_increment).rem_euclid(DECK_LEN as i128);
+    let index = (total_offset + position * total_increment).rem_euclid(deck_len as i128);
     Ok(index.to_string())
 }
 
@@ -221,4 +285,22 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_is_prime() {
+        let primes: Vec<_> = (0..30).filter(|&n| is_prime(n)).collect();
+        assert_eq!(primes, vec![2, 3, 5, 7, 11, 13, 17, 19, 23, 29]);
+        assert!(is_prime(119_315_717_514_047));
+        assert!(!is_prime(3_215_031_751));
+        assert!(!is_prime(119_315_717_514_047 * 3));
+    }
+
+    #[test]
+    fn test_second_invalid_deck() {
+        let input = |deck: &str| Input::new("cut 3").with_param("big_deck", deck);
+        assert!(second(&input("10007")).is_ok());
+        for deck in ["0", "1", "10", "4611686018427388039"] {
+            assert!(second(&input(deck)).is_err(), "deck {}", deck);
+        }
+    }
 }
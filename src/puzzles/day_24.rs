@@ -1,10 +1,17 @@
-use crate::input::Input;
+use crate::input::{Input, Param};
+use crate::lib::grid::Rect;
+use crate::lib::render::{Frame, Recording, Style};
+use colored::Color;
 use std::collections::{BTreeMap, HashSet};
 use std::error;
 use std::fmt;
 
 type Error = Box<dyn error::Error>;
 
+const MINUTES: Param = Param::new("minutes", "200", "Number of minutes to simulate in the second part");
+
+pub const PARAMS: &[Param] = &[MINUTES];
+
 fn should_toggle(is_bug: bool, neighbors: usize) -> bool {
     if is_bug {
         neighbors != 1
@@ -115,6 +122,20 @@ impl Layout {
 
         total
     }
+
+    fn frame(&self) -> Frame {
+        Frame::from_fn(Some(Rect::with_size(self.width, self.height)), |p| {
+            bug_style(self.is_bug(p.x as usize, p.y as usize))
+        })
+    }
+}
+
+fn bug_style(is_bug: bool) -> Style {
+    if is_bug {
+        Style::plain('#').fg(Color::Green).bold()
+    } else {
+        Style::plain('.').dimmed()
+    }
 }
 
 impl fmt::Display for Layout {
@@ -302,6 +323,24 @@ impl RecursiveLayout {
             self.step();
         }
     }
+
+    /// Render all levels next to each other, separated by an empty column.
+    fn frame(&self) -> Frame {
+        let levels: Vec<_> = self.levels.values().collect();
+        let stride = self.width + 1;
+        let bounds = Rect::with_size(levels.len() * stride - 1, self.height);
+
+        Frame::from_fn(Some(bounds), |p| {
+            let (level, x, y) = (p.x as usize / stride, p.x as usize % stride, p.y as usize);
+            if x == self.width {
+                Style::plain(' ')
+            } else if (x, y) == self.recursion_pos {
+                Style::plain('?').dimmed()
+            } else {
+                bug_style(levels[level].is_bug(x, y))
+            }
+        })
+    }
 }
 
 impl fmt::Display for RecursiveLayout {
@@ -316,13 +355,49 @@ impl fmt::Display for RecursiveLayout {
 
 pub fn second(i: &Input) -> Result<String, Error> {
     let mut layout = RecursiveLayout::from_input(i);
-    layout.run_minutes(200);
+    layout.run_minutes(MINUTES.get(i)?);
     Ok(layout.bug_count().to_string())
 }
 
+/// Show the layout every minute until it repeats for the first part.
+/// For the second part all levels are shown next to each other.
+pub fn visualize(i: &Input, second: bool) -> Result<Recording, Error> {
+    let mut rec = Recording::new();
+
+    if second {
+        let mut layout = RecursiveLayout::from_input(i);
+        for minute in 0..=MINUTES.get(i)? {
+            if minute > 0 {
+                layout.step();
+            }
+            let caption = format!("Minute {}: {} bugs", minute, layout.bug_count());
+            rec.push(layout.frame().with_caption(caption));
+        }
+    } else {
+        let mut layout = Layout::from_input(i);
+        let mut seen = HashSet::new();
+        let mut minute = 0;
+        while seen.insert(layout.clone()) {
+            rec.push(layout.frame().with_caption(format!("Minute {}", minute)));
+            layout.step();
+            minute += 1;
+        }
+
+        let caption = format!(
+            "Minute {}: layout repeated, biodiversity {}",
+            minute,
+            layout.biodiversity()
+        );
+        rec.push(layout.frame().with_caption(caption));
+    }
+
+    Ok(rec)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::lib::grid::Point;
 
     #[test]
     fn test_first() -> Result<(), Error> {
@@ -355,4 +430,32 @@ mod tests {
         layout.run_minutes(10);
         assert_eq!(layout.bug_count(), 99);
     }
+
+    #[test]
+    fn test_visualize() -> Result<(), Error> {
+        let input = Input::new(
+            "
+            ....#
+            #..#.
+            #..##
+            ..#..
+            #....
+            ",
+        );
+
+        let rec = visualize(&input, false)?;
+        assert_eq!(rec.len(), 87);
+        assert_eq!(
+            rec.frames()[86].plain(),
+            ".....\n.....\n.....\n#....\n.#...\nMinute 86: layout repeated, biodiversity 2129920\n"
+        );
+
+        let rec = visualize(&input.with_param("minutes", "10"), true)?;
+        let last = &rec.frames()[10];
+        assert_eq!(last.caption, "Minute 10: 99 bugs");
+        assert_eq!(last.height(), 5);
+        assert_eq!(last.get(Point::new(2, 2)).map(|s| s.ch), Some('?'));
+
+        Ok(())
+    }
 }
@@ -1,211 +1,682 @@
 use crate::input::Input;
+use crate::lib::adventure::{self, Event, Room};
 use crate::lib::intcode;
-use std::collections::HashSet;
+use crate::lib::search;
+use crate::lib::terminal::{self, RawMode};
+use std::collections::HashMap;
 use std::error;
 
 type Error = Box<dyn error::Error>;
 
-const PRESSURE_SENSITIVE_FLOOR: &str = "== Pressure-Sensitive Floor ==";
-const TOO_HEAVY: &str = "Alert! Droids on this ship are heavier than the detected value!";
-const TOO_LIGHT: &str = "Alert! Droids on this ship are lighter than the detected value!";
+/// Instructions the droid may execute to answer a command before it's considered stuck in a loop.
+const STEP_LIMIT: usize = 1_000_000;
 
-const CANT_GO_THAT_WAY: &str = "You can't go that way.";
+const DIRECTIONS: [&str; 4] = ["north", "east", "south", "west"];
 
-struct DummyRand {
-    state: usize,
+fn opposite(dir: &str) -> Result<&'static str, Error> {
+    match dir {
+        "north" => Ok("south"),
+        "east" => Ok("west"),
+        "south" => Ok("north"),
+        "west" => Ok("east"),
+        _ => Err(format!("unknown direction {}", dir).into()),
+    }
 }
 
-impl DummyRand {
-    const MAX: usize = std::usize::MAX;
-    const MAGIC: usize = 0x2545_F491_4F6C_DD1D;
+#[derive(Debug, Eq, PartialEq)]
+enum Response {
+    /// The droid is waiting for the next command.
+    Waiting(String),
+    Halted(String),
+    /// The droid didn't finish answering the command.
+    Stuck,
+}
 
-    fn new(seed: usize) -> Self {
-        Self { state: seed }
-    }
+/// Something the solver can send commands to.
+/// Cloning it takes a snapshot which can be restored later.
+trait Adventure: Clone {
+    fn command(&mut self, command: &str) -> Result<Response, Error>;
+}
 
-    fn rand(&mut self) -> usize {
-        let mut x = self.state;
-        x ^= x >> 12;
-        x ^= x << 25;
-        x ^= x >> 27;
-        self.state = x;
-        x.wrapping_mul(Self::MAGIC)
-    }
+/// Intcode droid playing the adventure.
+#[derive(Clone)]
+struct Droid {
+    machine: intcode::Machine,
+}
 
-    fn max(&mut self, n: usize) -> usize {
-        let g = Self::MAX - (Self::MAX % (n + 1));
-        let mut r;
-        loop {
-            r = self.rand();
-            if r < g {
-                break;
-            }
+impl Droid {
+    /// Start the machine, returns the droid together with the description of the first room.
+    fn start(mut machine: intcode::Machine) -> Result<(Self, String), Error> {
+        machine.start();
+        let mut droid = Self { machine };
+        match droid.run()? {
+            Response::Waiting(output) => Ok((droid, output)),
+            _ => Err("droid didn't ask for a command".into()),
         }
-
-        r % (n + 1)
-    }
-
-    fn range(&mut self, min: usize, max: usize) -> usize {
-        debug_assert!(min <= max);
-        let diff = max - min;
-        self.max(diff) + min
     }
 
-    fn bool(&mut self) -> bool {
-        self.rand() % 2 == 0
-    }
-
-    fn shuffle<T>(&mut self, elements: &mut [T]) {
-        for i in 0..elements.len() - 2 {
-            let j = self.range(i, elements.len() - 1);
-            elements.swap(i, j);
+    fn run(&mut self) -> Result<Response, Error> {
+        for _ in 0..STEP_LIMIT {
+            if !self.machine.run_once()? {
+                let output = self.machine.take_ascii_output().ok_or("non-ascii output")?;
+                return Ok(match self.machine.is_done() {
+                    true => Response::Halted(output),
+                    false => Response::Waiting(output),
+                });
+            }
         }
+
+        Ok(Response::Stuck)
     }
+}
 
-    fn binary_sample<'a, T>(
-        &'a mut self,
-        it: impl Iterator<Item = T> + 'a,
-    ) -> impl Iterator<Item = T> + 'a {
-        it.filter(move |_| self.bool())
+impl Adventure for Droid {
+    fn command(&mut self, command: &str) -> Result<Response, Error> {
+        let line = command.chars().chain(Some('\n'));
+        self.machine.input.extend(line.map(|c| c as intcode::Code));
+        self.run()
     }
 }
 
-struct Droid {
-    original_machine: intcode::Machine,
-    rng: DummyRand,
-    forbidden: HashSet<String>,
-    all_items: HashSet<String>,
-    machine: intcode::Machine,
-    items: HashSet<String>,
+/// Maps the whole ship, collects every item which is safe to carry and then tries
+/// combinations of them at the pressure-sensitive floor.
+struct Solver<A: Adventure> {
+    adventure: A,
+    ship: ShipMap,
+    unsafe_items: Vec<String>,
+    /// Room in front of the floor and the door leading to it.
+    checkpoint: Option<(String, String)>,
 }
 
-impl<'a> Droid {
-    const DIRECTIONS: [&'a str; 4] = ["east", "west", "north", "south"];
+impl<A: Adventure> Solver<A> {
+    /// Create a solver for an adventure waiting for a command after printing `output`.
+    fn new(adventure: A, output: &str) -> Self {
+        let mut ship = ShipMap::default();
+        ship.update("", output);
 
-    fn new(mut m: intcode::Machine) -> Self {
-        m.start();
-        let mut forbidden = HashSet::new();
-        forbidden.insert(String::from("infinite loop"));
-        forbidden.insert(String::from("giant electromagnet"));
         Self {
-            machine: m.clone(),
-            original_machine: m,
-            rng: DummyRand::new(1),
-            forbidden,
-            all_items: HashSet::new(),
-            items: HashSet::new(),
+            adventure,
+            ship,
+            unsafe_items: Vec::new(),
+            checkpoint: None,
         }
     }
 
-    fn from_input(i: &Input) -> Result<Self, Error> {
-        intcode::Machine::from_input(i).map(Self::new)
+    /// Send a command which mustn't stop the droid.
+    fn respond(&mut self, command: &str) -> Result<String, Error> {
+        match self.adventure.command(command)? {
+            Response::Waiting(output) => Ok(output),
+            Response::Halted(output) => {
+                Err(format!("droid stopped after {}: {}", command, output.trim()).into())
+            }
+            Response::Stuck => Err(format!("droid got stuck after {}", command).into()),
+        }
     }
 
-    fn reset_machine(&mut self) {
-        self.machine = self.original_machine.clone();
+    fn send(&mut self, command: &str) -> Result<String, Error> {
+        let output = self.respond(command)?;
+        self.ship.update(command, &output);
+        Ok(output)
     }
 
-    fn reset(&mut self) {
-        self.reset_machine();
+    fn current_name(&self) -> Result<String, Error> {
+        self.ship
+            .current
+            .clone()
+            .ok_or_else(|| "droid is lost".into())
+    }
 
-        self.items = self
-            .rng
-            .binary_sample(self.all_items.iter())
-            .cloned()
-            .collect();
+    /// Check that the droid can still leave the room and come back.
+    fn can_move(&mut self, back: Option<&str>) -> Result<bool, Error> {
+        let here = self.current_name()?;
+        let door = match back {
+            Some(door) => door.to_string(),
+            None => match self.ship.current_room().and_then(|r| r.doors.first()) {
+                Some(door) => door.clone(),
+                None => return Ok(false),
+            },
+        };
+
+        let moved = match self.adventure.command(&door)? {
+            Response::Waiting(output) => {
+                adventure::last_room(&adventure::parse(&output)).is_some_and(|r| r.name != here)
+            }
+            _ => false,
+        };
+        if moved {
+            let output = self.respond(opposite(&door)?)?;
+            if adventure::last_room(&adventure::parse(&output)).is_none_or(|r| r.name != here) {
+                return Err(format!("couldn't return to {}", here).into());
+            }
+        }
+
+        Ok(moved)
     }
 
-    fn handle_pressure_sensitive_floor(&mut self, output: &str) -> Option<usize> {
-        if output.contains(TOO_HEAVY) || output.contains(TOO_LIGHT) {
-            self.reset();
-            return None;
+    /// Take the item unless that stops the droid, gets it stuck or keeps it from moving.
+    fn take_if_safe(&mut self, item: &str, back: Option<&str>) -> Result<(), Error> {
+        let snapshot = (self.adventure.clone(), self.ship.clone());
+        let command = format!("take {}", item);
+
+        let safe = match self.adventure.command(&command)? {
+            Response::Waiting(output) => {
+                self.ship.update(&command, &output);
+                self.can_move(back)?
+            }
+            _ => false,
+        };
+        if !safe {
+            self.adventure = snapshot.0;
+            self.ship = snapshot.1;
+            self.unsafe_items.push(item.to_string());
         }
 
-        output
-            .split_ascii_whitespace()
-            .filter_map(|w| w.parse::<usize>().ok())
-            .next()
+        Ok(())
     }
 
-    fn handle_items(&mut self, output: &str) -> Result<bool, Error> {
-        for line in output.lines() {
-            if !line.starts_with("- ") {
+    /// Visit every room reachable from the current one and return to it.
+    /// `back` is the door leading to the room the droid came from.
+    fn explore(&mut self, back: Option<&str>) -> Result<(), Error> {
+        let room = self.ship.current_room().cloned().ok_or("droid is lost")?;
+        for item in &room.items {
+            self.take_if_safe(item, back)?;
+        }
+
+        for door in &room.doors {
+            let link = (room.name.clone(), door.clone());
+            if Some(door.as_str()) == back || self.ship.links.contains_key(&link) {
                 continue;
             }
 
-            let item = line[2..].to_string();
-            if Self::DIRECTIONS.contains(&item.as_str()) || self.forbidden.contains(&item) {
+            let output = self.respond(door)?;
+            let events = adventure::parse(&output);
+            // the floor sends the droid back unless it has the right weight
+            if events.iter().any(|e| matches!(e, Event::Ejected(_))) {
+                self.checkpoint = Some(link);
                 continue;
             }
 
-            if !self.all_items.contains(&item) {
-                self.all_items.insert(item.clone());
-            } else if !self.items.contains(&item) {
-                continue;
+            let seen = adventure::last_room(&events)
+                .is_some_and(|r| self.ship.rooms.contains_key(&r.name));
+            self.ship.update(door, &output);
+            let back_door = opposite(door)?;
+            if !seen {
+                self.explore(Some(back_door))?;
             }
+            self.send(back_door)?;
+        }
+
+        Ok(())
+    }
 
-            self.machine.send_ascii(&format!("take {}\n", item))?;
+    /// Doors leading from the current room to the given one.
+    fn route(&self, to: &str) -> Result<Vec<String>, Error> {
+        let links = &self.ship.links;
+        let neighbours = |room: &String| {
+            links
+                .iter()
+                .filter(|((from, _), _)| from == room)
+                .map(|(_, to)| to.clone())
+                .collect::<Vec<_>>()
+        };
+        let search = search::bfs(vec![self.current_name()?], neighbours, |room| room == to);
+        let path = search
+            .goal_path()
+            .ok_or_else(|| format!("no known way to {}", to))?;
+
+        let doors = path.windows(2).filter_map(|rooms| {
+            links
+                .iter()
+                .find(|((from, _), to)| from == &rooms[0] && *to == &rooms[1])
+                .map(|((_, door), _)| door.clone())
+        });
+
+        Ok(doors.collect())
+    }
 
-            if self.machine.is_done() {
-                self.items.remove(&item);
-                self.forbidden.insert(item.clone());
-                self.reset_machine();
-                return Ok(false);
+    /// Walk through all combinations of the carried items, changing a single item every time.
+    fn pass_floor(&mut self, door: &str) -> Result<String, Error> {
+        let items = self.ship.inventory.clone();
+        if items.len() > 24 {
+            return Err(format!("too many items to try: {}", items.len()).into());
+        }
+
+        let mut held = vec![true; items.len()];
+        for k in 0..1usize << items.len() {
+            // consecutive gray codes differ in the bit of the lowest set bit of k
+            if k > 0 {
+                let i = k.trailing_zeros() as usize;
+                let action = if held[i] { "drop" } else { "take" };
+                self.send(&format!("{} {}", action, items[i]))?;
+                held[i] = !held[i];
+            }
+
+            let output = match self.adventure.command(door)? {
+                Response::Waiting(output) | Response::Halted(output) => output,
+                Response::Stuck => return Err("droid got stuck on the floor".into()),
+            };
+            for event in adventure::parse(&output) {
+                match event {
+                    Event::Ejected(_) => break,
+                    Event::Success { password } => return Ok(password),
+                    _ => (),
+                }
             }
         }
 
-        Ok(true)
+        Err("no combination of items is accepted by the floor".into())
+    }
+
+    fn solve(&mut self) -> Result<String, Error> {
+        self.send("inv")?;
+        self.explore(None)?;
+
+        let (checkpoint, door) = self
+            .checkpoint
+            .clone()
+            .ok_or("couldn't find the pressure-sensitive floor")?;
+        for step in self.route(&checkpoint)? {
+            self.send(&step)?;
+        }
+
+        self.pass_floor(&door)
     }
+}
 
-    fn choose_next_dir(&mut self, output: &str) -> Result<(), Error> {
-        let mut directions: Vec<&str> = Self::DIRECTIONS.to_vec();
-        self.rng.shuffle(&mut directions);
+pub fn first(i: &Input) -> Result<String, Error> {
+    let (droid, output) = Droid::start(intcode::Machine::from_input(i)?)?;
+    Solver::new(droid, &output).solve()
+}
 
-        let dir = directions
-            .drain(..)
-            .find(|dir| output.contains(dir))
-            .ok_or_else(|| "no possible direction")?;
+/// Keeps track of the ship while the adventure is played.
+#[derive(Clone, Default)]
+struct ShipMap {
+    rooms: HashMap<String, Room>,
+    /// Room reached through a door of a room.
+    links: HashMap<(String, String), String>,
+    current: Option<String>,
+    inventory: Vec<String>,
+    /// Output which described the current room.
+    room_output: String,
+}
 
-        self.machine.send_ascii(dir)?;
-        self.machine.send_ascii("\n")?;
+impl ShipMap {
+    /// Commands available everywhere, `map`, `solve` and `quit` are handled by the shell.
+    const COMMANDS: [&'static str; 4] = ["inv", "map", "solve", "quit"];
 
-        Ok(())
+    fn current_room(&self) -> Option<&Room> {
+        self.current.as_ref().and_then(|name| self.rooms.get(name))
     }
 
-    fn run_until_next_command(&mut self) -> Result<String, Error> {
-        self.machine.run_until_stop()?;
-        let output = self
-            .machine
-            .take_ascii_output()
-            .ok_or_else(|| "non-ascii output")?;
+    fn current_room_mut(&mut self) -> Option<&mut Room> {
+        let rooms = &mut self.rooms;
+        self.current
+            .as_ref()
+            .and_then(move |name| rooms.get_mut(name))
+    }
 
-        if output.contains(CANT_GO_THAT_WAY) {
-            return Err("reached invalid position".into());
+    /// Update the map with the output of a command.
+    fn update(&mut self, command: &str, output: &str) {
+        // only the first room is reached through the door, others follow after being ejected
+        let mut moved = DIRECTIONS.contains(&command);
+        for event in adventure::parse(output) {
+            match event {
+                Event::Room(room) => {
+                    if let Some(previous) = self.current.take().filter(|_| moved) {
+                        self.links
+                            .insert((previous, command.to_string()), room.name.clone());
+                    }
+                    moved = false;
+                    self.current = Some(room.name.clone());
+                    self.room_output = output.to_string();
+                    self.rooms.insert(room.name.clone(), room);
+                }
+                Event::Taken(item) => {
+                    if let Some(room) = self.current_room_mut() {
+                        room.items.retain(|i| i != &item);
+                    }
+                    self.inventory.push(item);
+                }
+                Event::Dropped(item) => {
+                    if let Some(room) = self.current_room_mut() {
+                        room.items.push(item.clone());
+                    }
+                    self.inventory.retain(|i| i != &item);
+                }
+                Event::Inventory(items) => self.inventory = items,
+                _ => (),
+            }
         }
+    }
 
-        Ok(output)
+    /// Commands which make sense in the current room.
+    fn completions(&self) -> Vec<String> {
+        let mut commands: Vec<String> = ShipMap::COMMANDS.iter().map(|c| c.to_string()).collect();
+        if let Some(room) = self.current_room() {
+            commands.extend(room.doors.iter().cloned());
+            commands.extend(room.items.iter().map(|i| format!("take {}", i)));
+        }
+        commands.extend(self.inventory.iter().map(|i| format!("drop {}", i)));
+
+        commands
     }
 
-    fn run(&mut self) -> Result<usize, Error> {
-        loop {
-            let output = self.run_until_next_command()?;
-            if output.contains(PRESSURE_SENSITIVE_FLOOR) {
-                if let Some(result) = self.handle_pressure_sensitive_floor(&output) {
-                    return Ok(result);
-                }
-                continue;
+    fn describe(&self) -> String {
+        let mut names: Vec<_> = self.rooms.keys().collect();
+        names.sort();
+
+        let mut lines = Vec::new();
+        for name in names {
+            let room = &self.rooms[name];
+            let marker = if self.current.as_ref() == Some(name) {
+                "*"
+            } else {
+                " "
+            };
+            let doors: Vec<String> = room
+                .doors
+                .iter()
+                .map(|d| match self.links.get(&(name.clone(), d.clone())) {
+                    Some(to) => format!("{} -> {}", d, to),
+                    None => format!("{} -> ?", d),
+                })
+                .collect();
+            lines.push(format!("{} {}: {}", marker, name, doors.join(", ")));
+            if !room.items.is_empty() {
+                lines.push(format!("    items: {}", room.items.join(", ")));
             }
+        }
 
-            if !self.handle_items(&output)? {
+        lines.join("\n")
+    }
+}
+
+/// Play the adventure in the terminal.
+/// Besides the commands of the game, `map` shows the explored rooms and `solve` tries to finish from the current state.
+pub fn play(i: &Input) -> Result<String, Error> {
+    let (mut droid, output) = Droid::start(intcode::Machine::from_input(i)?)?;
+    print!("{}", output);
+
+    let mut ship = ShipMap::default();
+    ship.update("", &output);
+
+    let _raw = RawMode::enable()?;
+    let keys = terminal::spawn_key_reader();
+
+    loop {
+        let line = match terminal::read_line(&keys, "> ", &ship.completions())? {
+            Some(line) => line.trim().to_string(),
+            None => return Ok("Left the ship".to_string()),
+        };
+
+        match line.as_str() {
+            "" => continue,
+            "quit" => return Ok("Left the ship".to_string()),
+            "map" => {
+                println!("{}\n", ship.describe());
                 continue;
             }
+            "solve" => {
+                let mut solver = Solver::new(droid, &ship.room_output);
+                return solver
+                    .solve()
+                    .map(|password| format!("Solved, the password is {}", password));
+            }
+            _ => (),
+        }
 
-            self.choose_next_dir(&output)?;
+        match droid.command(&line)? {
+            Response::Waiting(output) => {
+                print!("{}", output);
+                ship.update(&line, &output);
+            }
+            Response::Halted(output) => {
+                print!("{}", output);
+                return Ok("The droid stopped".to_string());
+            }
+            Response::Stuck => return Ok("The droid got stuck in an infinite loop".to_string()),
         }
     }
 }
 
-pub fn first(i: &Input) -> Result<String, Error> {
-    let mut droid = Droid::from_input(i)?;
-    droid.run().map(|v| v.to_string())
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lib::adventure::{DOORS_HERE, INVENTORY, ITEMS_HERE, NO_ITEMS};
+
+    const HULL_BREACH: &str = "
+
+
+== Hull Breach ==
+You got in through a hole in the floor here. To keep your ship from also freezing, the hole has been sealed.
+
+Doors here lead:
+- north
+- east
+
+Command?
+";
+
+    const KITCHEN: &str = "
+
+
+== Kitchen ==
+Everything's freeze-dried.
+
+Doors here lead:
+- south
+
+Items here:
+- mug
+- food ration
+
+Command?
+";
+
+    #[test]
+    fn test_ship_map() {
+        let mut ship = ShipMap::default();
+        ship.update("", HULL_BREACH);
+        ship.update("north", KITCHEN);
+        ship.update("take mug", "\nYou take the mug.\n\nCommand?\n");
+
+        assert_eq!(ship.current.as_deref(), Some("Kitchen"));
+        assert_eq!(ship.inventory, vec!["mug"]);
+        assert_eq!(ship.current_room().unwrap().items, vec!["food ration"]);
+        assert_eq!(
+            ship.links
+                .get(&("Hull Breach".to_string(), "north".to_string())),
+            Some(&"Kitchen".to_string())
+        );
+
+        let completions = ship.completions();
+        assert!(completions.contains(&"south".to_string()));
+        assert!(completions.contains(&"take food ration".to_string()));
+        assert!(completions.contains(&"drop mug".to_string()));
+        assert!(!completions.contains(&"north".to_string()));
+
+        assert_eq!(
+            ship.describe(),
+            "  Hull Breach: north -> Kitchen, east -> ?\n* Kitchen: south -> ?\n    items: food ration"
+        );
+    }
+
+    const FAKE_DOORS: [(&str, &str, &str); 7] = [
+        ("Hull Breach", "north", "Kitchen"),
+        ("Hull Breach", "east", "Lab"),
+        ("Kitchen", "south", "Hull Breach"),
+        ("Lab", "west", "Hull Breach"),
+        ("Lab", "north", "Security Checkpoint"),
+        ("Security Checkpoint", "south", "Lab"),
+        ("Security Checkpoint", "east", "Pressure-Sensitive Floor"),
+    ];
+
+    const TOO_HEAVY: &str = "Alert! Droids on this ship are heavier than the detected value!";
+    const TOO_LIGHT: &str = "Alert! Droids on this ship are lighter than the detected value!";
+
+    /// Small ship which behaves like the real adventure.
+    /// Only the book and the mug together have the right weight.
+    #[derive(Clone)]
+    struct FakeShip {
+        room: &'static str,
+        items: Vec<(&'static str, &'static str)>,
+        inventory: Vec<&'static str>,
+        magnetized: bool,
+    }
+
+    impl FakeShip {
+        fn new() -> Self {
+            Self {
+                room: "Hull Breach",
+                items: vec![
+                    ("Hull Breach", "book"),
+                    ("Kitchen", "mug"),
+                    ("Kitchen", "molten lava"),
+                    ("Lab", "coin"),
+                    ("Lab", "giant electromagnet"),
+                    ("Lab", "infinite loop"),
+                ],
+                inventory: Vec::new(),
+                magnetized: false,
+            }
+        }
+
+        fn describe(&self, room: &str) -> String {
+            let list = |entries: Vec<&str>| {
+                entries
+                    .iter()
+                    .map(|e| format!("- {}\n", e))
+                    .collect::<String>()
+            };
+            let doors = FAKE_DOORS.iter().filter(|(from, _, _)| *from == room);
+            let items: Vec<_> = self.items.iter().filter(|(r, _)| *r == room).collect();
+
+            let mut out = format!("\n\n\n== {} ==\nA room.\n\n", room);
+            out += &format!("{}\n{}", DOORS_HERE, list(doors.map(|d| d.1).collect()));
+            if !items.is_empty() {
+                out += &format!(
+                    "\n{}\n{}",
+                    ITEMS_HERE,
+                    list(items.iter().map(|i| i.1).collect())
+                );
+            }
+            out + "\nCommand?\n"
+        }
+
+        fn weight(&self) -> usize {
+            self.inventory
+                .iter()
+                .map(|&i| match i {
+                    "mug" => 1,
+                    "book" => 2,
+                    _ => 4,
+                })
+                .sum()
+        }
+    }
+
+    impl Adventure for FakeShip {
+        fn command(&mut self, command: &str) -> Result<Response, Error> {
+            let response = |text: &str| Ok(Response::Waiting(format!("\n{}\n\nCommand?\n", text)));
+
+            if let Some(item) = command.strip_prefix("take ") {
+                let index = self
+                    .items
+                    .iter()
+                    .position(|&(r, i)| r == self.room && i == item);
+                let (_, item) = self.items.remove(index.ok_or("no such item")?);
+                match item {
+                    "molten lava" => return Ok(Response::Halted("You melt!".to_string())),
+                    "infinite loop" => return Ok(Response::Stuck),
+                    "giant electromagnet" => self.magnetized = true,
+                    _ => (),
+                }
+                self.inventory.push(item);
+                return response(&format!("You take the {}.", item));
+            }
+            if let Some(item) = command.strip_prefix("drop ") {
+                let index = self.inventory.iter().position(|&i| i == item);
+                let item = self.inventory.remove(index.ok_or("not carrying item")?);
+                self.items.push((self.room, item));
+                return response(&format!("You drop the {}.", item));
+            }
+            if command == "inv" {
+                let items: String = self
+                    .inventory
+                    .iter()
+                    .map(|i| format!("\n- {}", i))
+                    .collect();
+                return match items.is_empty() {
+                    true => response(NO_ITEMS),
+                    false => response(&format!("{}{}", INVENTORY, items)),
+                };
+            }
+            if self.magnetized {
+                return response("The giant electromagnet is stuck to you.  You can't move!!");
+            }
+
+            let door = FAKE_DOORS
+                .iter()
+                .find(|(from, dir, _)| *from == self.room && *dir == command);
+            match door {
+                Some((_, _, "Pressure-Sensitive Floor")) => {
+                    let floor = self.describe("Pressure-Sensitive Floor");
+                    match self.weight() {
+                        3 => Ok(Response::Halted(format!(
+                            "{}You should be able to get in by typing 1234 on the keypad.\n",
+                            floor
+                        ))),
+                        w => {
+                            let alert = if w > 3 { TOO_HEAVY } else { TOO_LIGHT };
+                            let back = self.describe(self.room);
+                            Ok(Response::Waiting(format!("{}{}\n{}", floor, alert, back)))
+                        }
+                    }
+                }
+                Some((_, _, to)) => {
+                    self.room = to;
+                    Ok(Response::Waiting(self.describe(to)))
+                }
+                None => response("You can't go that way."),
+            }
+        }
+    }
+
+    #[test]
+    fn test_solver() -> Result<(), Error> {
+        let ship = FakeShip::new();
+        let output = ship.describe(ship.room);
+        let mut solver = Solver::new(ship, &output);
+
+        assert_eq!(solver.solve()?, "1234");
+        solver.unsafe_items.sort();
+        assert_eq!(
+            solver.unsafe_items,
+            vec!["giant electromagnet", "infinite loop", "molten lava"]
+        );
+        assert_eq!(
+            solver.checkpoint,
+            Some(("Security Checkpoint".to_string(), "east".to_string()))
+        );
+        assert_eq!(solver.ship.rooms.len(), 4);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_solver_resumed() -> Result<(), Error> {
+        // start in the kitchen, already carrying the coin
+        let mut ship = FakeShip::new();
+        ship.room = "Kitchen";
+        ship.items.retain(|&(_, i)| i != "coin");
+        ship.inventory.push("coin");
+
+        let output = ship.describe(ship.room);
+        let mut solver = Solver::new(ship, &output);
+        assert_eq!(solver.solve()?, "1234");
+        solver.ship.inventory.sort();
+        assert_eq!(solver.ship.inventory, vec!["book", "mug"]);
+
+        Ok(())
+    }
 }
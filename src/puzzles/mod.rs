@@ -1,49 +1,228 @@
-use crate::input::Input;
+use crate::input::{Input, Param};
+use crate::lib::render::Recording;
 
 use std::error::Error;
 
-type PuzzleFn = dyn Fn(&Input) -> Result<String, Box<dyn Error>>;
-type PuzzleMap = HashMap<(usize, usize), &'static PuzzleFn>;
+type PuzzleFn = fn(&Input) -> Result<String, Box<dyn Error>>;
+type VisualizeFn = fn(&Input, bool) -> Result<Recording, Box<dyn Error>>;
+type PlayFn = fn(&Input) -> Result<String, Box<dyn Error>>;
 
+/// Everything the puzzle module of a day provides.
+struct Day {
+    name: &'static str,
+    first: PuzzleFn,
+    second: Option<PuzzleFn>,
+    params: &'static [Param],
+    visualize: Option<VisualizeFn>,
+    play: Option<PlayFn>,
+}
+
+impl Day {
+    fn new(name: &'static str, first: PuzzleFn, second: Option<PuzzleFn>) -> Self {
+        Self {
+            name,
+            first,
+            second,
+            params: &[],
+            visualize: None,
+            play: None,
+        }
+    }
+}
+
+/// Add an optional item of a day module.
+macro_rules! day_item {
+    ( $day:ident, $x:ident, params ) => {
+        Day {
+            params: $x::PARAMS,
+            ..$day
+        }
+    };
+    ( $day:ident, $x:ident, visualize ) => {
+        Day {
+            visualize: Some($x::visualize),
+            ..$day
+        }
+    };
+    ( $day:ident, $x:ident, play ) => {
+        Day {
+            play: Some($x::play),
+            ..$day
+        }
+    };
+}
+
+/// Declare the day modules, optionally followed by the items they provide besides the two parts
+/// in braces. `first_only` marks days without a second part.
 macro_rules! day_modules {
-    ( $( $x:ident ),* ) => {
+    ( @day $x:ident first_only $( , $item:ident )* ) => {{
+        let day = Day::new(stringify!($x), $x::first, None);
+        $( let day = day_item!(day, $x, $item); )*
+        day
+    }};
+    ( @day $x:ident $( $item:ident ),* ) => {{
+        let day = Day::new(stringify!($x), $x::first, Some($x::second));
+        $( let day = day_item!(day, $x, $item); )*
+        day
+    }};
+    ( $( $x:ident $( { $( $items:tt )* } )? ),* $(,)? ) => {
         $(
             mod $x;
         )*
 
-        use std::collections::HashMap;
-
-        fn _build_puzzle_map() -> PuzzleMap {
-            let mut temp_map: PuzzleMap = HashMap::new();
-            $(
-                let mod_name = stringify!($x);
-                let day = mod_name.rsplit('_').take(1).next().unwrap().parse().unwrap();
-
-                temp_map.insert((day, 0), &$x::first);
-                temp_map.insert((day, 1), &$x::second);
-            )*
-            temp_map
+        fn get_day(day: u8) -> Option<Day> {
+            let name = format!("day_{:02}", day);
+            let days = vec![ $( day_modules!(@day $x $( $( $items )* )?) ),* ];
+            days.into_iter().find(|d| d.name == name)
         }
     };
 }
 
-day_modules![
-    day_01, day_02, day_03, day_04, day_05, day_06, day_07, day_08, day_09, day_10, day_11, day_12,
-    day_13, day_14, day_15, day_16, day_17, day_18, day_19, day_20, day_21, day_22, day_23, day_24
-];
+day_modules! {
+    day_01,
+    day_02,
+    day_03,
+    day_04,
+    day_05,
+    day_06,
+    day_07,
+    day_08 { params, visualize },
+    day_09,
+    day_10 { params },
+    day_11 { params, visualize },
+    day_12 { params, visualize },
+    day_13 { params, visualize, play },
+    day_14 { params },
+    day_15 { visualize },
+    day_16 { params },
+    day_17 { params, visualize },
+    day_18,
+    day_19 { params },
+    day_20,
+    day_21 { params },
+    day_22 { params },
+    day_23,
+    day_24 { params, visualize },
+    day_25 { first_only, play },
+}
 
-mod day_25;
+/// Whether the puzzle of the given day declares a parameter with the name.
+pub fn has_param(day: u8, name: &str) -> bool {
+    get_day(day).is_some_and(|d| d.params.iter().any(|p| p.name == name))
+}
 
-fn build_puzzle_map() -> PuzzleMap {
-    let mut map = _build_puzzle_map();
-    map.insert((25, 0), &day_25::first);
+/// Make sure the puzzle of the given day declares all parameters set for the input.
+pub fn check_params(day: u8, input: &Input) -> Result<(), Box<dyn Error>> {
+    let params = get_day(day).map_or(&[][..], |d| d.params);
+    for name in input.param_names() {
+        if params.iter().any(|p| p.name == name) {
+            continue;
+        }
 
-    map
+        if params.is_empty() {
+            return Err(
+                format!("unknown parameter {}, day {} has no parameters", name, day).into(),
+            );
+        }
+
+        let available: Vec<_> = params
+            .iter()
+            .map(|p| format!("  {} (default: {}): {}", p.name, p.default, p.help))
+            .collect();
+        return Err(format!(
+            "unknown parameter {} for day {}, available parameters:\n{}",
+            name,
+            day,
+            available.join("\n")
+        )
+        .into());
+    }
+
+    Ok(())
 }
 
+/// Solve a part of the puzzle of the given day.
+/// Returns `None` if the day doesn't have the part.
 pub fn run_puzzle(day: u8, second: bool, input: &Input) -> Option<Result<String, Box<dyn Error>>> {
-    let puzzles = build_puzzle_map();
-    let puzzle = puzzles.get(&(day as usize, second as usize))?;
+    let day = get_day(day)?;
+    let puzzle = if second { day.second? } else { day.first };
 
     Some(puzzle(input))
 }
+
+/// Record the visualization of a puzzle part.
+/// Returns `None` if the day doesn't have a visualization.
+pub fn visualize(
+    day: u8,
+    second: bool,
+    input: &Input,
+) -> Option<Result<Recording, Box<dyn Error>>> {
+    let visualize = get_day(day)?.visualize?;
+
+    Some(visualize(input, second))
+}
+
+/// Play the puzzle of the given day interactively in the terminal.
+/// Returns `None` if the day can't be played.
+pub fn play(day: u8, input: &Input) -> Option<Result<String, Box<dyn Error>>> {
+    let play = get_day(day)?.play?;
+
+    Some(play(input))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::examples;
+    use std::path::Path;
+
+    #[test]
+    fn test_has_param() {
+        assert!(has_param(8, "raw"));
+        assert!(has_param(11, "raw"));
+        assert!(!has_param(1, "raw"));
+        assert!(!has_param(8, "speed"));
+        assert!(!has_param(26, "raw"));
+    }
+
+    #[test]
+    fn test_fixtures() -> Result<(), Box<dyn Error>> {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures");
+        let mut failures = Vec::new();
+
+        for day in 1..=25 {
+            for (name, example) in examples::load_fixtures(&dir, day)? {
+                let mut input = Input::new(&example.input);
+                for (param, value) in example.params.iter() {
+                    input.set_param(param, value);
+                }
+                if let Err(e) = check_params(day, &input) {
+                    failures.push(format!("{}: {}", name, e));
+                    continue;
+                }
+
+                let parts = [(false, example.first), (true, example.second)];
+
+                for (second, expected) in parts.iter() {
+                    let expected = match expected {
+                        Some(expected) => expected,
+                        None => continue,
+                    };
+
+                    let result = run_puzzle(day, *second, &input)
+                        .ok_or_else(|| format!("{}: missing part", name))?
+                        .map_err(|e| e.to_string());
+                    if result.as_ref() != Ok(expected) {
+                        failures.push(format!(
+                            "{} (second: {}): expected {}, got {:?}",
+                            name, second, expected, result
+                        ));
+                    }
+                }
+            }
+        }
+
+        assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+        Ok(())
+    }
+}